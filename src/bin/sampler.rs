@@ -0,0 +1,1033 @@
+// headless sampler: draws particles for one orbital, superposition, molecular orbital or
+// slater-type atom and writes them to disk
+// never touches winit or wgpu, so it runs on servers without a display or gpu
+
+use atom_simulator::cube::{CubeAtom, CubeField, CubeFile};
+use atom_simulator::dirac::DiracState;
+use atom_simulator::export::{self, ExportFormat};
+use atom_simulator::hybrid;
+use atom_simulator::isosurface;
+use atom_simulator::mcmc::{self, MetropolisOptions};
+use atom_simulator::molecule::MolecularOrbital;
+use atom_simulator::momentum::MomentumOrbital;
+use atom_simulator::physics::{self, ColorMode, Orbital, QuantumNumbers, Sampler};
+use atom_simulator::plot::{Figure, PlotFormat};
+use atom_simulator::properties;
+use atom_simulator::slater::{self, Atom, Occupation, SlaterOrbital};
+use atom_simulator::spin::{self, SpinOrbital};
+use atom_simulator::stark::{self, StarkState};
+use atom_simulator::superposition::Superposition;
+use atom_simulator::transitions::{self, Transition};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "\
+usage: sampler <n> <l> <m> [options]
+       sampler --label <orbital> [options]
+       sampler --superposition <state> [options]
+       sampler --molecule <orbital> [options]
+       sampler --element <symbol> [--label <orbital>] [options]
+       sampler [--element <symbol>] --configuration <text|all> [options]
+       sampler --stark <n,k,m> [--efield <F>] [--axis <a>] [options]
+       sampler --spin <nlj[,mj]> [--bfield <T>] [options]
+       sampler --dirac <nlj[,mj]|n,kappa,mj> [--z <Z>] [--compare] [options]
+       sampler --transition \"<state> -> <state>\" [--species <k> | --z <Z>]
+       sampler --lines <N> [--species <k> | --z <Z>]
+
+sampled states may have n up to 80
+
+options:
+  --label <orbital>   real orbital label instead of n l m (2px, 3dxy, 4fz3, ...)
+                      or a hybrid such as sp3 or sp3d2:4
+  --real              use real harmonics for the n l m form
+  --momentum          sample the orbital's momentum distribution |phi(p)|^2 instead;
+                      coordinates (and --cube, --extent) are then in hbar/bohr
+  --superposition <s> coherent superposition such as '1s + 2pz' or '0.6*1s - 0.8i*[2,1,1]'
+  --time <t>          evolve the superposition to time t in atomic units (default: 0)
+  --molecule <m>      lcao molecular orbital: a diatomic preset (h2+, sigma1s, sigma*1s,
+                      sigma2s, sigma*2s, sigma2p, sigma*2p, pi2p, pi*2p) or an expansion
+                      such as '1s@(0,0,-1) - 1s@(0,0,1)' with centres in bohr
+  --bond <R>          bond length of a diatomic preset in bohr (default: 2 n^2 a)
+  --element <X>       many-electron atom (symbol or Z up to 54) filled by aufbau, with
+                      slater-type orbitals from slater's rules; --label picks one orbital,
+                      otherwise the valence density is sampled
+  --configuration <c> whole-configuration density such as '1s2 2s1' or '[Ar] 3d5 4s1', the
+                      neutral atom unless --element gives Z; 'all' takes every occupied
+                      subshell of --element; --phase colors particles by subshell
+  --stark <n,k,m>     parabolic stark state of a hydrogen-like atom in a uniform field,
+                      k = n1 - n2 from -(n-|m|-1) to n-|m|-1 in steps of 2
+  --efield <F>        field strength in atomic units, for the reported shift (default: 1e-3)
+  --axis <a>          field direction: x, y, z, -z, ... or components such as 1,1,0
+                      in orbital axes (default: z)
+  --spin <state>      spin-orbit coupled state |n l j mj> such as 2p3/2 or 2p3/2,-1/2
+                      (mj defaults to j); particles are red for spin up and green for
+                      spin down, or phase-colored per component with --phase
+  --bfield <T>        magnetic field along z in tesla, for the zeeman shift (default: 1)
+  --dirac <state>     exact dirac-coulomb state such as 1s1/2, 2p3/2,-1/2 or 2,1,1/2
+                      (n, kappa, mj); the large component is heatmap-colored and the
+                      small one magenta, or red/green by spin with --phase
+  --z <Z>             nuclear charge of the dirac state, up to 137 for 1s1/2 (default: 1),
+                      or of a fixed-nucleus ion for --transition and --lines
+  --compare           sample the dirac cloud at -x beside the schrodinger cloud at +x,
+                      --count particles each
+  --transition <t>    print the electric dipole matrix element, selection rule, oscillator
+                      strength, wavelength and einstein A of a transition such as
+                      \"3dz2 -> 2pz\" or \"[2,1,1] -> 1s\", then the decays of the upper state
+  --lines <N>         print the first N lyman, balmer and paschen lines with their
+                      wavelengths, oscillator strengths and einstein A coefficients
+  --count <N>         number of particles (default: 100000)
+  --seed <S>          random seed for reproducible output (default: from entropy)
+  --species <k>       hydrogen-like system, 1-based index into the list below (default: 1)
+  --phase             color particles by the phase of psi instead of density
+  --mcmc              sample with a metropolis random walk instead of the exact samplers
+  --burn-in <N>       metropolis steps discarded before the first sample (default: 2000)
+  --thin <N>          metropolis steps between kept samples (default: 10)
+  --step <L>          initial metropolis step in bohr (default: n^2 a of the largest term)
+  --enclosed <f>      keep only the particles inside the surface holding this fraction
+                      of the probability, e.g. 0.9
+  --out <path>        output file, '-' for stdout
+                      (default: particles.txt, or none when --cube or --plot is given)
+  --format <fmt>      txt, ply (binary), ply-ascii, xyz or csv
+                      (default: guessed from the --out extension, else txt)
+  --plot <path>       plot P(r), the radial cdf and the polar distribution of an orbital
+                      over histograms of the particles, as .svg or .png
+
+volumetric output:
+  --cube <path>       evaluate the orbital on a grid and write a gaussian cube file
+  --field <f>         density (|psi|^2), psi (real part) or imag (default: density)
+  --grid <N>          points per axis (default: 80)
+  --extent <L>        grid spans [-L, L] bohr on each axis (default: fits the orbital)
+  --help              show this message";
+
+// what to sample: a single eigenstate, a superposition at a given time, a molecular orbital,
+// one slater-type orbital or the valence shells of an atom, a stark state, a spinor or a dirac
+// state, alone or beside its schrodinger counterpart, or an orbital in momentum space
+enum Target {
+    Orbital(Orbital),
+    Momentum(MomentumOrbital),
+    Superposition(Superposition, f64),
+    Molecule(MolecularOrbital),
+    Slater(Atom, SlaterOrbital),
+    Shells(Atom, Vec<Occupation>),
+    Stark(StarkState),
+    Spin(SpinOrbital),
+    Dirac(DiracState, bool),
+}
+
+impl Target {
+    fn density(&self, pos: &glm::DVec3) -> f64 {
+        match self {
+            Target::Orbital(orbital) => orbital.psi_at(pos).norm_sqr(),
+            Target::Momentum(mo) => mo.density(pos),
+            Target::Superposition(state, time) => state.density(pos, *time),
+            Target::Molecule(mo) => mo.density(pos),
+            Target::Slater(_, orbital) => orbital.density_at(pos),
+            Target::Shells(atom, occupations) => atom.shell_density(occupations, pos),
+            Target::Stark(state) => state.density(pos),
+            Target::Spin(state) => state.density(pos),
+            Target::Dirac(state, false) => state.density(pos),
+            Target::Dirac(state, true) => state.side_by_side_density(pos),
+        }
+    }
+
+    // the single wavefunction behind the density, for phase coloring of metropolis samples
+    // shell sums, spinors and dirac states have no one phase
+    fn psi(&self, pos: &glm::DVec3) -> Option<Complex64> {
+        match self {
+            Target::Orbital(orbital) => Some(orbital.psi_at(pos)),
+            Target::Momentum(mo) => Some(mo.phi(pos)),
+            Target::Superposition(state, time) => Some(state.psi(pos, *time)),
+            Target::Molecule(mo) => Some(mo.psi(pos)),
+            Target::Slater(_, orbital) => Some(orbital.psi_at(pos)),
+            Target::Stark(state) => Some(state.psi(pos)),
+            Target::Shells(..) | Target::Spin(_) | Target::Dirac(..) => None,
+        }
+    }
+
+    // largest principal quantum number in the state
+    fn largest_n(&self) -> i32 {
+        let n = |orbital: &Orbital| orbital.quantum_numbers.n();
+        match self {
+            Target::Orbital(orbital) => n(orbital),
+            Target::Momentum(mo) => n(mo.orbital()),
+            Target::Superposition(state, _) => state
+                .terms()
+                .iter()
+                .map(|t| n(&t.orbital))
+                .max()
+                .unwrap_or(1),
+            Target::Molecule(mo) => mo.terms().iter().map(|t| n(&t.orbital)).max().unwrap_or(1),
+            Target::Slater(_, orbital) => orbital.subshell.n,
+            Target::Shells(_, occupations) => {
+                occupations.iter().map(|o| o.subshell.n).max().unwrap_or(1)
+            }
+            Target::Stark(state) => state.n(),
+            Target::Spin(state) => state.n(),
+            Target::Dirac(state, _) => state.n(),
+        }
+    }
+
+    // rough extent n^2 a of the state (p0 / n in momentum space), for the metropolis step
+    // and start
+    fn length(&self) -> f64 {
+        let extent = |orbital: &Orbital| {
+            let n = orbital.quantum_numbers.n() as f64;
+            n * n * orbital.species.length_scale()
+        };
+        match self {
+            Target::Orbital(orbital) => extent(orbital),
+            Target::Momentum(mo) => mo.mean_square_momentum().sqrt(),
+            Target::Superposition(state, _) => state
+                .terms()
+                .iter()
+                .map(|t| extent(&t.orbital))
+                .fold(0.0, f64::max),
+            Target::Molecule(mo) => mo
+                .terms()
+                .iter()
+                .map(|t| extent(&t.orbital))
+                .fold(0.0, f64::max),
+            Target::Slater(_, orbital) => orbital.mean_radius(),
+            Target::Shells(atom, occupations) => occupations
+                .iter()
+                .filter_map(|o| atom.orbital(o.subshell, 0).ok())
+                .map(|orbital| orbital.mean_radius())
+                .fold(0.0, f64::max),
+            Target::Stark(state) => {
+                let n = state.n() as f64;
+                n * n * state.species().length_scale()
+            }
+            Target::Spin(state) => {
+                let n = state.n() as f64;
+                n * n * state.species().length_scale()
+            }
+            Target::Dirac(state, _) => state.mean_radius(),
+        }
+    }
+}
+
+// a particle cloud, or one of the spectroscopy tables, which need no sampling
+enum Command {
+    Sample(Options),
+    Transition(Transition),
+    Lines(physics::Species, usize),
+}
+
+struct Options {
+    target: Target,
+    count: usize,
+    seed: Option<u64>,
+    color_mode: ColorMode,
+    enclosed: Option<f64>,
+    mcmc: Option<MetropolisOptions>,
+    out: Option<String>,
+    format: ExportFormat,
+    cube: Option<CubeOptions>,
+    plot: Option<(String, PlotFormat)>,
+}
+
+struct CubeOptions {
+    path: String,
+    field: CubeField,
+    points: usize,
+    half_width: Option<f64>,
+}
+
+// the normalizations use gamma(n + l + 1) and gamma(l + |m| + 1), which overflow past
+// 170!, and the sampling tables stop resolving the radial nodes well before that
+const MAX_N: i32 = 80;
+
+// every way of naming what to sample; hybrids go through --label and --momentum is a flag
+// on an orbital, so neither is listed
+const STATE_FORMS: [&str; 11] = [
+    "<n> <l> <m>",
+    "--label",
+    "--superposition",
+    "--molecule",
+    "--element",
+    "--configuration",
+    "--stark",
+    "--spin",
+    "--dirac",
+    "--transition",
+    "--lines",
+];
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut numbers = Vec::new();
+    let mut label = None;
+    let mut superposition = None;
+    let mut time = 0.0;
+    let mut molecule = None;
+    let mut bond_length = None;
+    let mut element = None;
+    let mut configuration = None;
+    let mut stark_state = None;
+    let mut efield = None;
+    let mut axis = None;
+    let mut spin_state = None;
+    let mut bfield = None;
+    let mut dirac_state = None;
+    let mut nuclear_charge = None;
+    let mut transition = None;
+    let mut line_count = None;
+    let mut compare = false;
+    let mut real = false;
+    let mut momentum = false;
+    let mut count = 100_000;
+    let mut seed = None;
+    let mut species: usize = 1;
+    let mut color_mode = ColorMode::Density;
+    let mut enclosed = None;
+    let mut use_mcmc = false;
+    let mut burn_in = None;
+    let mut thinning = None;
+    let mut step = None;
+    let mut out = None;
+    let mut format = None;
+    let mut cube_path = None;
+    let mut plot_path = None;
+    let mut field = CubeField::Density;
+    let mut points = 80;
+    let mut half_width = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--label" => label = Some(parse_value::<String>(&arg, args.next())?),
+            "--real" => real = true,
+            "--momentum" => momentum = true,
+            "--superposition" => superposition = Some(parse_value::<String>(&arg, args.next())?),
+            "--time" => time = parse_value(&arg, args.next())?,
+            "--molecule" => molecule = Some(parse_value::<String>(&arg, args.next())?),
+            "--bond" => bond_length = Some(parse_value(&arg, args.next())?),
+            "--element" => element = Some(parse_value::<String>(&arg, args.next())?),
+            "--configuration" => configuration = Some(parse_value::<String>(&arg, args.next())?),
+            "--stark" => stark_state = Some(parse_value::<String>(&arg, args.next())?),
+            "--spin" => spin_state = Some(parse_value::<String>(&arg, args.next())?),
+            "--bfield" => bfield = Some(parse_value(&arg, args.next())?),
+            "--dirac" => dirac_state = Some(parse_value::<String>(&arg, args.next())?),
+            "--z" => nuclear_charge = Some(parse_value(&arg, args.next())?),
+            "--compare" => compare = true,
+            "--transition" => transition = Some(parse_value::<String>(&arg, args.next())?),
+            "--lines" => line_count = Some(parse_value(&arg, args.next())?),
+            "--efield" => efield = Some(parse_value(&arg, args.next())?),
+            "--axis" => {
+                let text: String = parse_value(&arg, args.next())?;
+                axis = Some(stark::parse_axis(&text)?);
+            }
+            "--count" => count = parse_value(&arg, args.next())?,
+            "--seed" => seed = Some(parse_value(&arg, args.next())?),
+            "--species" => species = parse_value(&arg, args.next())?,
+            "--phase" => color_mode = ColorMode::Phase,
+            "--enclosed" => enclosed = Some(parse_value(&arg, args.next())?),
+            "--mcmc" => use_mcmc = true,
+            "--burn-in" => burn_in = Some(parse_value(&arg, args.next())?),
+            "--thin" => thinning = Some(parse_value(&arg, args.next())?),
+            "--step" => step = Some(parse_value(&arg, args.next())?),
+            "--out" => out = Some(parse_value::<String>(&arg, args.next())?),
+            "--cube" => cube_path = Some(parse_value::<String>(&arg, args.next())?),
+            "--plot" => plot_path = Some(parse_value::<String>(&arg, args.next())?),
+            "--field" => {
+                let name: String = parse_value(&arg, args.next())?;
+                field =
+                    CubeField::parse(&name).ok_or_else(|| format!("unknown field '{}'", name))?;
+            }
+            "--grid" => points = parse_value(&arg, args.next())?,
+            "--extent" => half_width = Some(parse_value(&arg, args.next())?),
+            "--format" => {
+                let name: String = parse_value(&arg, args.next())?;
+                format = Some(
+                    ExportFormat::parse(&name)
+                        .ok_or_else(|| format!("unknown format '{}'", name))?,
+                );
+            }
+            "--help" | "-h" => return Err(String::new()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => numbers.push(parse_value::<i32>("quantum number", Some(arg))?),
+        }
+    }
+
+    let species = *physics::SPECIES_PRESETS
+        .get(species.wrapping_sub(1))
+        .ok_or_else(|| {
+            format!(
+                "--species must be between 1 and {}",
+                physics::SPECIES_PRESETS.len()
+            )
+        })?;
+
+    if bond_length.is_some() && molecule.is_none() {
+        return Err("--bond needs --molecule".to_string());
+    }
+
+    if (efield.is_some() || axis.is_some()) && stark_state.is_none() {
+        return Err("--efield and --axis need --stark".to_string());
+    }
+
+    if bfield.is_some() && spin_state.is_none() {
+        return Err("--bfield needs --spin".to_string());
+    }
+
+    if transition.is_some() || line_count.is_some() {
+        let other_state = !numbers.is_empty()
+            || [
+                &label,
+                &superposition,
+                &molecule,
+                &element,
+                &configuration,
+                &stark_state,
+                &spin_state,
+                &dirac_state,
+            ]
+            .iter()
+            .any(|s| s.is_some());
+        if other_state || (transition.is_some() && line_count.is_some()) {
+            return Err("--transition and --lines do not combine with other states".to_string());
+        }
+        let species = match nuclear_charge {
+            Some(z) if z >= 1 => physics::hydrogen_like(z),
+            Some(_) => return Err("--z must be at least 1".to_string()),
+            None => species,
+        };
+        return match (transition, line_count) {
+            (Some(text), _) => Transition::parse(&text, species)
+                .map(Command::Transition)
+                .map_err(|e| format!("invalid transition: {}", e)),
+            (None, Some(0)) => Err("--lines must be positive".to_string()),
+            (None, Some(count)) => Ok(Command::Lines(species, count)),
+            (None, None) => unreachable!(),
+        };
+    }
+
+    if (nuclear_charge.is_some() || compare) && dirac_state.is_none() {
+        return Err(
+            "--z needs --dirac, --transition or --lines, and --compare needs --dirac".to_string(),
+        );
+    }
+
+    let target = match (molecule, superposition, label, numbers.as_slice()) {
+        (None, None, None, [])
+            if dirac_state.is_some()
+                && spin_state.is_none()
+                && stark_state.is_none()
+                && element.is_none()
+                && configuration.is_none() =>
+        {
+            let state = DiracState::parse(
+                dirac_state.as_deref().unwrap_or_default(),
+                nuclear_charge.unwrap_or(1),
+            )
+            .map_err(|e| format!("invalid dirac state: {}", e))?;
+            Target::Dirac(state, compare)
+        }
+        _ if dirac_state.is_some() => {
+            return Err("--dirac does not combine with other states".to_string());
+        }
+        (None, None, None, [])
+            if spin_state.is_some()
+                && stark_state.is_none()
+                && element.is_none()
+                && configuration.is_none() =>
+        {
+            let state = SpinOrbital::parse(spin_state.as_deref().unwrap_or_default(), species)
+                .map_err(|e| format!("invalid j state: {}", e))?;
+            Target::Spin(state.with_field(bfield.unwrap_or(spin::DEFAULT_FIELD_T)))
+        }
+        _ if spin_state.is_some() => {
+            return Err("--spin does not combine with other states".to_string());
+        }
+        (None, None, None, [])
+            if stark_state.is_some() && element.is_none() && configuration.is_none() =>
+        {
+            let state = StarkState::parse(stark_state.as_deref().unwrap_or_default(), species)
+                .and_then(|s| s.with_axis(axis.unwrap_or(glm::vec3(0.0, 0.0, 1.0))))
+                .map_err(|e| format!("invalid stark state: {}", e))?;
+            Target::Stark(state.with_field(efield.unwrap_or(stark::DEFAULT_FIELD)))
+        }
+        _ if stark_state.is_some() => {
+            return Err("--stark does not combine with other states".to_string());
+        }
+        (None, None, None, []) if configuration.is_some() => {
+            let z = element.as_deref().map(slater::parse_element).transpose()?;
+            let atom = match (z, configuration.as_deref()) {
+                (Some(z), Some("all")) => Atom::aufbau(z)?,
+                (_, text) => Atom::with_configuration(z, text.unwrap_or_default())
+                    .map_err(|e| format!("invalid configuration: {}", e))?,
+            };
+            let occupations = atom.occupations.clone();
+            Target::Shells(atom, occupations)
+        }
+        _ if configuration.is_some() => {
+            return Err("--configuration only combines with --element".to_string());
+        }
+        (None, None, label, []) if element.is_some() => {
+            let z = slater::parse_element(element.as_deref().unwrap_or_default())?;
+            let atom = Atom::aufbau(z)?;
+            match label {
+                Some(label) => {
+                    let orbital = atom.orbital_from_label(&label)?;
+                    Target::Slater(atom, orbital)
+                }
+                None => {
+                    let valence = atom.valence();
+                    Target::Shells(atom, valence)
+                }
+            }
+        }
+        _ if element.is_some() => {
+            return Err("--element only combines with --label".to_string());
+        }
+        (Some(text), None, None, []) => {
+            let mo = MolecularOrbital::from_spec(&text, bond_length, species)
+                .map_err(|e| format!("invalid molecular orbital: {}", e))?;
+            Target::Molecule(mo)
+        }
+        (None, Some(text), None, []) => {
+            let state = Superposition::parse(&text, species)
+                .map_err(|e| format!("invalid superposition: {}", e))?;
+            Target::Superposition(state, time)
+        }
+        (None, None, Some(label), []) if label.to_lowercase().starts_with("sp") => {
+            let (set, index) = hybrid::parse_hybrid_label(&label)?;
+            Target::Superposition(set.hybrid(index, species), 0.0)
+        }
+        (None, None, Some(label), []) => {
+            Target::Orbital(Orbital::from_label(&label)?.with_species(species))
+        }
+        (None, None, None, [n, l, m]) => {
+            let qn = QuantumNumbers::new(*n, *l, *m).map_err(|e| e.to_string())?;
+            let harmonic = if real {
+                physics::Harmonic::Real
+            } else {
+                physics::Harmonic::Complex
+            };
+            Target::Orbital(
+                Orbital::new(qn)
+                    .with_harmonic(harmonic)
+                    .with_species(species),
+            )
+        }
+        _ => {
+            let [rest @ .., last] = STATE_FORMS;
+            return Err(format!(
+                "expected exactly one of {} or {}",
+                rest.join(", "),
+                last
+            ));
+        }
+    };
+
+    let target = match target {
+        Target::Orbital(orbital) if momentum => Target::Momentum(MomentumOrbital::new(orbital)),
+        _ if momentum => {
+            return Err("--momentum needs an orbital from <n> <l> <m> or --label".to_string());
+        }
+        target => target,
+    };
+    if target.largest_n() > MAX_N {
+        return Err(format!("n must be at most {}", MAX_N));
+    }
+
+    if count == 0 {
+        return Err("--count must be positive".to_string());
+    }
+
+    if points < 2 {
+        return Err("--grid needs at least 2 points".to_string());
+    }
+    if enclosed.is_some_and(|f: f64| !(f > 0.0 && f <= 1.0)) {
+        return Err("--enclosed must be in (0, 1]".to_string());
+    }
+    if thinning == Some(0) {
+        return Err("--thin must be positive".to_string());
+    }
+    if step.is_some_and(|l: f64| l <= 0.0) {
+        return Err("--step must be positive".to_string());
+    }
+    if !use_mcmc && (burn_in.is_some() || thinning.is_some() || step.is_some()) {
+        return Err("--burn-in, --thin and --step need --mcmc".to_string());
+    }
+    let mcmc = use_mcmc.then(|| {
+        let mut settings = MetropolisOptions::for_length(target.length());
+        settings.burn_in = burn_in.unwrap_or(settings.burn_in);
+        settings.thinning = thinning.unwrap_or(settings.thinning);
+        settings.step = step.unwrap_or(settings.step);
+        settings
+    });
+
+    if half_width.is_some_and(|w: f64| w <= 0.0) {
+        return Err("--extent must be positive".to_string());
+    }
+
+    let cube = cube_path.map(|path| CubeOptions {
+        path,
+        field,
+        points,
+        half_width,
+    });
+    let plot = match plot_path {
+        Some(path) => {
+            if !matches!(target, Target::Orbital(_)) {
+                return Err("--plot needs an orbital from <n> <l> <m> or --label".to_string());
+            }
+            let format = PlotFormat::from_path(&path)
+                .ok_or_else(|| format!("--plot needs a .svg or .png path, got '{}'", path))?;
+            Some((path, format))
+        }
+        None => None,
+    };
+    if out.is_none() && cube.is_none() && plot.is_none() {
+        out = Some(String::from("particles.txt"));
+    }
+
+    let format = format
+        .or_else(|| out.as_deref().and_then(ExportFormat::from_path))
+        .unwrap_or(ExportFormat::Text);
+
+    Ok(Command::Sample(Options {
+        target,
+        count,
+        seed,
+        color_mode,
+        enclosed,
+        mcmc,
+        out,
+        format,
+        cube,
+        plot,
+    }))
+}
+
+fn print_usage() {
+    eprintln!("{}", USAGE);
+    eprintln!("\nspecies:");
+    for (i, species) in physics::SPECIES_PRESETS.iter().enumerate() {
+        eprintln!("  {}. {}", i + 1, species.name);
+    }
+}
+
+fn create_output(path: &str) -> io::Result<Box<dyn Write>> {
+    if path == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout().lock())))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+fn describe(options: &Options) -> String {
+    match &options.target {
+        Target::Orbital(orbital) => {
+            let qn = orbital.quantum_numbers;
+            format!(
+                "n={} l={} m={} harmonic={:?} species={}",
+                qn.n(),
+                qn.l(),
+                qn.m(),
+                orbital.harmonic,
+                orbital.species.name
+            )
+        }
+        Target::Momentum(mo) => {
+            let qn = mo.orbital().quantum_numbers;
+            format!(
+                "momentum n={} l={} m={} harmonic={:?} p0={:.6} J(0)={:.6} species={}",
+                qn.n(),
+                qn.l(),
+                qn.m(),
+                mo.orbital().harmonic,
+                mo.momentum_scale(),
+                mo.compton_profile(0.0),
+                mo.orbital().species.name
+            )
+        }
+        Target::Superposition(state, time) => format!(
+            "state=\"{}\" t={} species={}",
+            state.label(),
+            time,
+            state.species().name
+        ),
+        Target::Molecule(mo) => {
+            let centres: Vec<String> = mo
+                .nuclei()
+                .iter()
+                .map(|n| {
+                    let p = n.position;
+                    format!("({:.3},{:.3},{:.3})", p.x, p.z, p.y)
+                })
+                .collect();
+            format!(
+                "molecule=\"{}\" nuclei={} species={}",
+                mo.label(),
+                centres.join(","),
+                mo.species().name
+            )
+        }
+        Target::Slater(atom, orbital) => format!(
+            "element={} orbital={} Z_eff={:.2} zeta={:.4}",
+            atom.symbol(),
+            orbital.label(),
+            orbital.effective_charge,
+            orbital.zeta
+        ),
+        Target::Shells(atom, occupations) => {
+            let shells: Vec<String> = occupations
+                .iter()
+                .map(|o| format!("{}{}", o.subshell.label(), o.electrons))
+                .collect();
+            format!(
+                "element={} configuration=\"{}\" shells=\"{}\"",
+                atom.symbol(),
+                atom.configuration(),
+                shells.join(" ")
+            )
+        }
+        Target::Stark(state) => {
+            let axis = state.axis();
+            format!(
+                "stark n={} k={} m={} F={:.4e} axis=({:.3},{:.3},{:.3}) shift={:.4e} species={}",
+                state.n(),
+                state.k(),
+                state.m(),
+                state.field(),
+                axis.x,
+                axis.y,
+                axis.z,
+                state.energy_shift(),
+                state.species().name
+            )
+        }
+        Target::Spin(state) => format!(
+            "state=\"{}\" spin_up={:.4} fine_structure={:.4e} B={} zeeman={:.4e} species={}",
+            state.label(),
+            state.spin_up_probability(),
+            state.fine_structure_shift(),
+            state.field(),
+            state.zeeman_shift(),
+            state.species().name
+        ),
+        Target::Dirac(state, compare) => format!(
+            "dirac state=\"{}\" Z={} E={:.8e} schrodinger={:.8e} large={:.6} compare={}",
+            state.label(),
+            state.z(),
+            state.binding_energy(),
+            state.schrodinger_energy(),
+            state.large_fraction(),
+            compare
+        ),
+    }
+}
+
+fn write_particles(options: &Options) -> io::Result<()> {
+    let mut sampler = match options.seed {
+        Some(seed) => Sampler::with_seed(seed),
+        None => Sampler::new(),
+    };
+    sampler.color_mode = options.color_mode;
+
+    eprintln!(
+        "Sampling {} particles for {}...",
+        options.count,
+        describe(options)
+    );
+    let mut particles = match (&options.target, &options.mcmc) {
+        (target, Some(settings)) => {
+            let chain =
+                mcmc::metropolis(&mut sampler, |p| target.density(p), options.count, settings);
+            eprintln!(
+                "Metropolis: acceptance rate {:.3}, step {:.3} bohr after {} burn-in steps, keeping one step in {}",
+                chain.acceptance_rate(),
+                chain.step,
+                settings.burn_in,
+                settings.thinning
+            );
+            chain.particles(options.color_mode, |p| target.psi(p))
+        }
+        (Target::Orbital(orbital), None) => sampler.generate(orbital, options.count),
+        (Target::Momentum(mo), None) => mo.sample(&mut sampler, options.count),
+        (Target::Molecule(mo), None) => mo.sample(&mut sampler, options.count),
+        (Target::Slater(_, orbital), None) => orbital
+            .sample(&mut sampler, options.count)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?,
+        (Target::Shells(atom, occupations), None) => {
+            if options.color_mode == ColorMode::Phase {
+                let legend: Vec<String> = occupations
+                    .iter()
+                    .enumerate()
+                    .map(|(i, o)| {
+                        let c = slater::subshell_color(i) * 255.0;
+                        let hex = format!("#{:02x}{:02x}{:02x}", c.x as u8, c.y as u8, c.z as u8);
+                        format!("{} {}", o.subshell.label(), hex)
+                    })
+                    .collect();
+                eprintln!("Subshell colors: {}", legend.join(", "));
+            }
+            atom.sample_shells(occupations, &mut sampler, options.count)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        }
+        (Target::Superposition(state, time), None) => {
+            state.sample(&mut sampler, *time, options.count)
+        }
+        (Target::Stark(state), None) => state.sample(&mut sampler, options.count),
+        (Target::Spin(state), None) => state.sample(&mut sampler, options.count),
+        (Target::Dirac(state, false), None) => state.sample(&mut sampler, options.count),
+        (Target::Dirac(state, true), None) => {
+            state.sample_side_by_side(&mut sampler, options.count)
+        }
+    };
+
+    // checked and plotted before any culling, which would bias the moments and histograms
+    if let Target::Orbital(orbital) = &options.target {
+        for line in properties::report(orbital, &particles) {
+            eprintln!("{}", line);
+        }
+        if let Some((path, format)) = &options.plot {
+            let mut out = BufWriter::new(File::create(path)?);
+            Figure::orbital(orbital, &particles, &mut sampler).write(&mut out, *format)?;
+            out.flush()?;
+            eprintln!("Wrote {}", path);
+        }
+    }
+
+    if let Some(fraction) = options.enclosed {
+        let threshold = isosurface::particle_threshold(&particles, fraction);
+        particles = isosurface::cull_particles(&particles, threshold);
+        eprintln!(
+            "Kept {} particles inside the {:.1}% surface (density >= {:.4e} bohr^-3)",
+            particles.len(),
+            fraction * 100.0,
+            threshold
+        );
+    }
+
+    let comment = format!(
+        "{} count={} seed={}",
+        describe(options),
+        options.count,
+        options
+            .seed
+            .map_or_else(|| "entropy".to_string(), |s| s.to_string())
+    );
+
+    let Some(path) = &options.out else {
+        return Ok(());
+    };
+    let mut out = create_output(path)?;
+    export::write_particles(&mut out, &particles, options.format, &comment)?;
+    out.flush()?;
+
+    if path != "-" {
+        eprintln!("Wrote {}", path);
+    }
+    Ok(())
+}
+
+fn write_cube(options: &Options, cube_options: &CubeOptions) -> io::Result<()> {
+    let half_width = cube_options
+        .half_width
+        .unwrap_or_else(|| match &options.target {
+            Target::Orbital(orbital) => CubeFile::default_half_width(orbital),
+            Target::Momentum(mo) => mo.p_max(),
+            Target::Superposition(state, _) => state
+                .terms()
+                .iter()
+                .map(|t| CubeFile::default_half_width(&t.orbital))
+                .fold(0.0, f64::max),
+            Target::Molecule(mo) => mo.extent(),
+            Target::Slater(_, orbital) => orbital.r_max(),
+            Target::Shells(atom, occupations) => occupations
+                .iter()
+                .filter_map(|o| atom.orbital(o.subshell, 0).ok())
+                .map(|orbital| orbital.r_max())
+                .fold(0.0, f64::max),
+            Target::Stark(state) => state
+                .state()
+                .terms()
+                .iter()
+                .map(|t| CubeFile::default_half_width(&t.orbital))
+                .fold(0.0, f64::max),
+            Target::Spin(state) => state
+                .components()
+                .iter()
+                .filter_map(|(_, orbital)| orbital.as_ref())
+                .map(CubeFile::default_half_width)
+                .fold(0.0, f64::max),
+            Target::Dirac(state, compare) => {
+                let offset = if *compare {
+                    state.comparison_offset()
+                } else {
+                    0.0
+                };
+                offset + CubeFile::shell_half_width(state.n(), state.species().length_scale())
+            }
+        });
+
+    eprintln!(
+        "Evaluating {:?} on a {}^3 grid over [-{:.3}, {:.3}] bohr...",
+        cube_options.field, cube_options.points, half_width, half_width
+    );
+    let (field, points) = (cube_options.field, cube_options.points);
+    let mut cube = match &options.target {
+        Target::Orbital(orbital) => CubeFile::from_orbital(orbital, field, half_width, points),
+        // momentum space has no nucleus to list
+        Target::Momentum(mo) => CubeFile::from_fn(half_width, points, |p| field.value(mo.phi(p))),
+        Target::Superposition(state, time) => {
+            let mut cube =
+                CubeFile::from_fn(half_width, points, |p| field.value(state.psi(p, *time)));
+            cube.atoms.push(CubeAtom::nucleus(state.species().z));
+            cube
+        }
+        Target::Molecule(mo) => {
+            let mut cube = CubeFile::from_fn(half_width, points, |p| field.value(mo.psi(p)));
+            for nucleus in mo.nuclei() {
+                cube.atoms.push(CubeAtom {
+                    position: physics::swap_yz(&nucleus.position),
+                    ..CubeAtom::nucleus(nucleus.z)
+                });
+            }
+            cube
+        }
+        Target::Slater(atom, orbital) => {
+            let mut cube =
+                CubeFile::from_fn(half_width, points, |p| field.value(orbital.psi_at(p)));
+            cube.atoms.push(CubeAtom::nucleus(atom.z));
+            cube
+        }
+        // a sum of densities has no phase, so every field writes the density
+        Target::Shells(atom, occupations) => {
+            let mut cube =
+                CubeFile::from_fn(half_width, points, |p| atom.shell_density(occupations, p));
+            cube.atoms.push(CubeAtom::nucleus(atom.z));
+            cube
+        }
+        Target::Stark(state) => {
+            let mut cube = CubeFile::from_fn(half_width, points, |p| field.value(state.psi(p)));
+            cube.atoms.push(CubeAtom::nucleus(state.species().z));
+            cube
+        }
+        // psi and imag write the spin-up component, density the total of both
+        Target::Spin(state) => {
+            let mut cube = CubeFile::from_fn(half_width, points, |p| {
+                let (up, down) = state.spinor(p);
+                match field {
+                    CubeField::Density => up.norm_sqr() + down.norm_sqr(),
+                    _ => field.value(up),
+                }
+            });
+            cube.atoms.push(CubeAtom::nucleus(state.species().z));
+            cube
+        }
+        // a four-component spinor has no single phase, so every field writes the density
+        Target::Dirac(state, compare) => {
+            let offsets: &[f64] = if *compare { &[-1.0, 1.0] } else { &[0.0] };
+            let mut cube = CubeFile::from_fn(half_width, points, |p| options.target.density(p));
+            for side in offsets {
+                cube.atoms.push(CubeAtom {
+                    position: glm::vec3(side * state.comparison_offset(), 0.0, 0.0),
+                    ..CubeAtom::nucleus(state.z())
+                });
+            }
+            cube
+        }
+    };
+    cube.comments = [
+        format!("Electron Cloud {}", describe(options)),
+        format!(
+            "field={:?}, spacing {:.5} angstrom, orbital axes",
+            cube_options.field,
+            cube.spacing_angstrom()
+        ),
+    ];
+
+    let mut out = create_output(&cube_options.path)?;
+    cube.write(&mut out)?;
+    out.flush()?;
+
+    if cube_options.path != "-" {
+        eprintln!("Wrote {}", cube_options.path);
+    }
+    Ok(())
+}
+
+fn run(options: &Options) -> io::Result<()> {
+    if options.out.is_some() || options.plot.is_some() {
+        write_particles(options)?;
+    }
+    if let Some(cube_options) = &options.cube {
+        write_cube(options, cube_options)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Sample(options)) => options,
+        Ok(Command::Transition(transition)) => {
+            let decays = transitions::decay_report(transition.upper());
+            for line in transition.report().iter().chain(&decays) {
+                println!("{}", line);
+            }
+            return;
+        }
+        Ok(Command::Lines(species, count)) => {
+            for line in transitions::line_table(species, count) {
+                println!("{}", line);
+            }
+            return;
+        }
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("error: {}\n", msg);
+            }
+            print_usage();
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Command, String> {
+        parse_args(text.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_large_n_is_rejected() {
+        assert!(parse("80 79 79").is_ok());
+        for text in [
+            "171 0 0",
+            "81 0 0",
+            "--label 90s",
+            "--superposition 1s+[100,0,0]",
+        ] {
+            assert_eq!(parse(text).err(), Some("n must be at most 80".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_missing_state_lists_every_form() {
+        for text in ["", "--label 1s --molecule h2+", "1 0 0 --superposition 1s"] {
+            let error = parse(text).err().unwrap_or_default();
+            for form in STATE_FORMS {
+                assert!(error.contains(form), "{} not in '{}'", form, error);
+            }
+        }
+    }
+}
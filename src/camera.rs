@@ -1,5 +1,6 @@
 use nalgebra_glm as glm;
 use std::f32::consts::PI;
+#[cfg(feature = "viewer")]
 use winit::event::{ElementState, MouseButton};
 
 pub struct Camera {
@@ -60,6 +61,7 @@ impl Camera {
         self.last_y = y;
     }
 
+    #[cfg(feature = "viewer")]
     pub fn process_mouse_button(&mut self, button: MouseButton, state: ElementState) {
         if button == MouseButton::Left {
             if state == ElementState::Pressed {
@@ -104,6 +106,7 @@ mod tests {
         assert_ne!(cam.elevation, PI / 2.0);
     }
 
+    #[cfg(feature = "viewer")]
     #[test]
     fn test_camera_button_press_and_release() {
         let mut cam = Camera::new(glm::vec3(0.0, 0.0, 0.0), 10.0);
@@ -0,0 +1,573 @@
+use crate::physics::{self, ColorMode, Orbital, Particle, Sampler, BOHR_ANGSTROM};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+// gaussian cube files: volumetric data on a regular grid, as written by
+// gaussian, orca, psi4, ... and read by vmd, avogadro and friends
+// all lengths are in bohr; grids, atoms and origins are kept in the orbital frame of the
+// file (polar axis along z) and converted with physics::swap_yz where they meet the
+// sampler frame
+
+// which quantity of the orbital is stored at each voxel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeField {
+    // |psi|^2 in bohr^-3
+    Density,
+    // Re(psi) in bohr^-3/2, the signed orbital for real harmonics
+    Real,
+    // Im(psi) in bohr^-3/2, zero for real harmonics
+    Imaginary,
+}
+
+impl CubeField {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "density" => Some(CubeField::Density),
+            "psi" | "real" => Some(CubeField::Real),
+            "imag" | "imaginary" => Some(CubeField::Imaginary),
+            _ => None,
+        }
+    }
+
+    // the stored quantity for one value of psi
+    pub fn value(self, psi: Complex64) -> f64 {
+        match self {
+            CubeField::Density => psi.norm_sqr(),
+            CubeField::Real => psi.re,
+            CubeField::Imaginary => psi.im,
+        }
+    }
+}
+
+// nucleus listed in the cube header
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CubeAtom {
+    pub number: i32,
+    pub charge: f64,
+    pub position: glm::DVec3,
+}
+
+impl CubeAtom {
+    // bare nucleus of charge z at the origin
+    pub fn nucleus(z: i32) -> Self {
+        Self {
+            number: z,
+            charge: z as f64,
+            position: glm::vec3(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// one cube file in memory
+// voxel (i, j, k) sits at origin + i * axes[0] + j * axes[1] + k * axes[2]
+// values are stored with k varying fastest, matching the file layout
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeFile {
+    pub comments: [String; 2],
+    pub atoms: Vec<CubeAtom>,
+    pub origin: glm::DVec3,
+    pub axes: [glm::DVec3; 3],
+    pub shape: [usize; 3],
+    pub values: Vec<f64>,
+}
+
+impl CubeFile {
+    // evaluates the orbital on a cubic grid of `points` per axis spanning [-half_width, half_width]
+    // the nucleus is written as the single atom at the origin
+    pub fn from_orbital(
+        orbital: &Orbital,
+        field: CubeField,
+        half_width: f64,
+        points: usize,
+    ) -> Self {
+        let mut cube = Self::from_fn(half_width, points, |pos| field.value(orbital.psi_at(pos)));
+        cube.atoms.push(CubeAtom::nucleus(orbital.species.z));
+        cube
+    }
+
+    // samples any scalar field on a cubic grid of `points` per axis spanning
+    // [-half_width, half_width], with no atoms and empty comments
+    // the field takes sampler-frame positions, so voxel (i, j, k) at orbital point p
+    // holds field(swap_yz(p))
+    pub fn from_fn(half_width: f64, points: usize, field: impl Fn(&glm::DVec3) -> f64) -> Self {
+        let spacing = 2.0 * half_width / (points - 1) as f64;
+        let origin = glm::vec3(-half_width, -half_width, -half_width);
+        let axes = [
+            glm::vec3(spacing, 0.0, 0.0),
+            glm::vec3(0.0, spacing, 0.0),
+            glm::vec3(0.0, 0.0, spacing),
+        ];
+
+        let mut cube = CubeFile {
+            comments: [String::new(), String::new()],
+            atoms: Vec::new(),
+            origin,
+            axes,
+            shape: [points; 3],
+            values: Vec::with_capacity(points * points * points),
+        };
+
+        for i in 0..points {
+            for j in 0..points {
+                for k in 0..points {
+                    let value = field(&physics::swap_yz(&cube.point(i, j, k)));
+                    cube.values.push(value);
+                }
+            }
+        }
+
+        cube
+    }
+
+    // reads a cube file from any buffered source
+    // negative voxel counts mark angstrom units and are converted to bohr,
+    // a negative atom count marks a molecular orbital file whose first listed orbital is kept
+    pub fn read<R: BufRead>(input: R) -> io::Result<Self> {
+        let mut lines = input.lines();
+        let mut next_line = |what: &str| -> io::Result<String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid(format!("unexpected end of file in {}", what))))
+        };
+
+        let comments = [next_line("comment")?, next_line("comment")?];
+
+        let header = parse_numbers(&next_line("grid header")?, 4)?;
+        let num_atoms = header[0] as i64;
+        let mut origin = glm::vec3(header[1], header[2], header[3]);
+        // optional fifth value: number of values per voxel
+        let values_per_voxel = header.get(4).map_or(1, |v| (*v as usize).max(1));
+
+        let mut shape = [0; 3];
+        let mut axes = [glm::vec3(0.0, 0.0, 0.0); 3];
+        let mut angstrom = false;
+        for (n, axis) in shape.iter_mut().zip(axes.iter_mut()) {
+            let row = parse_numbers(&next_line("grid header")?, 4)?;
+            let count = row[0] as i64;
+            if count == 0 {
+                return Err(invalid("grid axis with no points".to_string()));
+            }
+            angstrom |= count < 0;
+            *n = count.unsigned_abs() as usize;
+            *axis = glm::vec3(row[1], row[2], row[3]);
+        }
+
+        let mut atoms = Vec::with_capacity(num_atoms.unsigned_abs() as usize);
+        for _ in 0..num_atoms.unsigned_abs() {
+            let row = parse_numbers(&next_line("atom list")?, 5)?;
+            atoms.push(CubeAtom {
+                number: row[0] as i32,
+                charge: row[1],
+                position: glm::vec3(row[2], row[3], row[4]),
+            });
+        }
+
+        if angstrom {
+            let to_bohr = 1.0 / BOHR_ANGSTROM;
+            origin *= to_bohr;
+            for axis in &mut axes {
+                *axis *= to_bohr;
+            }
+            for atom in &mut atoms {
+                atom.position *= to_bohr;
+            }
+        }
+
+        // the rest is whitespace separated numbers; an orbital file starts with the
+        // orbital count and indices, and then stores one value per orbital at every voxel
+        let mut numbers = Vec::new();
+        for line in lines {
+            numbers.extend(parse_numbers(&line?, 0)?);
+        }
+        let (stride, skip) = if num_atoms < 0 {
+            let count = numbers.first().copied().unwrap_or(0.0) as usize;
+            (count.max(1), count + 1)
+        } else {
+            (values_per_voxel, 0)
+        };
+
+        let num_voxels = shape[0] * shape[1] * shape[2];
+        let data = numbers.get(skip..).unwrap_or(&[]);
+        if data.len() < num_voxels * stride {
+            return Err(invalid(format!(
+                "expected {} values, found {}",
+                num_voxels * stride,
+                data.len()
+            )));
+        }
+        let values = data
+            .iter()
+            .step_by(stride)
+            .take(num_voxels)
+            .copied()
+            .collect();
+
+        Ok(CubeFile {
+            comments,
+            atoms,
+            origin,
+            axes,
+            shape,
+            values,
+        })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    // default half width for an orbital: covers the outer lobe of |psi|^2 with margin
+    pub fn default_half_width(orbital: &Orbital) -> f64 {
+        Self::shell_half_width(orbital.quantum_numbers.n(), orbital.species.length_scale())
+    }
+
+    // the same box for any state whose outermost shell is n, in units of `length_scale`
+    pub fn shell_half_width(n: i32, length_scale: f64) -> f64 {
+        let n = n as f64;
+        (2.5 * n * n + 5.0) * length_scale
+    }
+
+    // cartesian position of voxel (i, j, k)
+    pub fn point(&self, i: usize, j: usize, k: usize) -> glm::DVec3 {
+        self.origin + self.axes[0] * i as f64 + self.axes[1] * j as f64 + self.axes[2] * k as f64
+    }
+
+    pub fn value(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.values[(i * self.shape[1] + j) * self.shape[2] + k]
+    }
+
+    // volume of one voxel, |a0 . (a1 x a2)|
+    pub fn voxel_volume(&self) -> f64 {
+        glm::dot(&self.axes[0], &glm::cross(&self.axes[1], &self.axes[2])).abs()
+    }
+
+    // centre of the grid box in the file frame
+    pub fn center(&self) -> glm::DVec3 {
+        let half = |axis: usize| (self.shape[axis] - 1) as f64 / 2.0;
+        self.origin + self.axes[0] * half(0) + self.axes[1] * half(1) + self.axes[2] * half(2)
+    }
+
+    // a cube whose negative values carry a real share of the total holds a signed orbital
+    // rather than a density; densities from other codes often dip just below zero through
+    // round-off or fitting noise, which stays under 1e-3 of the total and is ignored
+    pub fn is_signed(&self) -> bool {
+        let total: f64 = self.values.iter().map(|v| v.abs()).sum();
+        let negative: f64 = self.values.iter().filter(|v| **v < 0.0).map(|v| -v).sum();
+        negative > 1e-3 * total
+    }
+
+    // probability density of voxel value v: psi^2 for signed orbitals, v itself otherwise
+    fn density_of(&self, v: f64, signed: bool) -> f64 {
+        if signed {
+            v * v
+        } else {
+            v.max(0.0)
+        }
+    }
+
+    // monte carlo samples of the voxel density
+    // voxels are picked from a cdf over their weights, then the particle is spread
+    // uniformly over the voxel cell so the grid does not show up as a lattice
+    // density colors are relative to the densest voxel, phase colors show the sign of psi
+    // positions are returned in the sampler frame
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        let signed = self.is_signed();
+        let color_mode = sampler.color_mode;
+
+        let mut cdf = Vec::with_capacity(self.values.len());
+        let mut total = 0.0;
+        let mut max_density: f64 = 0.0;
+        for v in &self.values {
+            let density = self.density_of(*v, signed);
+            total += density;
+            max_density = max_density.max(density);
+            cdf.push(total);
+        }
+        if total <= 0.0 {
+            return Vec::new();
+        }
+
+        let rng = sampler.rng();
+        let mut particles = Vec::with_capacity(num_particles);
+        for _ in 0..num_particles {
+            let u = rng.gen::<f64>() * total;
+            let index = cdf.partition_point(|c| *c <= u).min(cdf.len() - 1);
+
+            let k = index % self.shape[2];
+            let j = (index / self.shape[2]) % self.shape[1];
+            let i = index / (self.shape[1] * self.shape[2]);
+
+            let mut position = self.point(i, j, k);
+            for axis in &self.axes {
+                position += axis * rng.gen_range(-0.5..0.5);
+            }
+
+            let value = self.values[index];
+            let density = self.density_of(value, signed);
+
+            // same log compression as the analytic clouds, relative to the peak
+            let intensity = (1000.0 * density / max_density).ln_1p() / 1001.0f64.ln();
+            let color = match color_mode {
+                ColorMode::Phase if signed => {
+                    physics::sign_color(if value < 0.0 { PI } else { 0.0 }, intensity)
+                }
+                _ => physics::heatmap_cool(intensity),
+            };
+
+            particles.push(Particle {
+                position: physics::swap_yz(&position),
+                color,
+                density,
+            });
+        }
+
+        particles
+    }
+
+    // writes the standard cube layout: two comment lines, grid header, atoms,
+    // then values six per line, restarting the line for every (i, j) row
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for comment in &self.comments {
+            writeln!(out, "{}", comment.replace('\n', " "))?;
+        }
+
+        writeln!(
+            out,
+            "{:5} {:12.6} {:12.6} {:12.6}",
+            self.atoms.len(),
+            self.origin.x,
+            self.origin.y,
+            self.origin.z
+        )?;
+        for (n, axis) in self.shape.iter().zip(&self.axes) {
+            writeln!(
+                out,
+                "{:5} {:12.6} {:12.6} {:12.6}",
+                n, axis.x, axis.y, axis.z
+            )?;
+        }
+        for atom in &self.atoms {
+            writeln!(
+                out,
+                "{:5} {:12.6} {:12.6} {:12.6} {:12.6}",
+                atom.number, atom.charge, atom.position.x, atom.position.y, atom.position.z
+            )?;
+        }
+
+        for row in self.values.chunks(self.shape[2]) {
+            for line in row.chunks(6) {
+                let text: Vec<String> = line.iter().map(|v| format_value(*v)).collect();
+                writeln!(out, "{}", text.join(""))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // grid spacing along the first axis in angstrom, for log messages
+    pub fn spacing_angstrom(&self) -> f64 {
+        glm::length(&self.axes[0]) * BOHR_ANGSTROM
+    }
+}
+
+// c-style "%13.5E": rust prints exponents without padding or sign (1.5E-6),
+// while fixed-width cube readers expect two exponent digits (1.50000E-06)
+fn format_value(v: f64) -> String {
+    let text = format!("{:.5E}", v);
+    let (mantissa, exponent) = text.split_once('E').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+
+    format!(
+        "{:>13}",
+        format!("{}E{}{:02}", mantissa, sign, exponent.abs())
+    )
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// parses whitespace separated numbers, requiring at least `min` of them
+// fortran double precision exponents (1.0D-05) are accepted too
+fn parse_numbers(line: &str, min: usize) -> io::Result<Vec<f64>> {
+    let numbers = line
+        .split_whitespace()
+        .map(|token| {
+            token
+                .replace(['D', 'd'], "E")
+                .parse::<f64>()
+                .map_err(|_| invalid(format!("invalid number '{}'", token)))
+        })
+        .collect::<io::Result<Vec<f64>>>()?;
+
+    if numbers.len() < min {
+        return Err(invalid(format!(
+            "expected {} numbers, found {} in line '{}'",
+            min,
+            numbers.len(),
+            line.trim()
+        )));
+    }
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::QuantumNumbers;
+
+    #[test]
+    fn test_density_cube_integrates_to_one() {
+        let orbital = Orbital::new(QuantumNumbers::new(2, 1, 1).unwrap());
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Density, 20.0, 61);
+        let total: f64 = cube.values.iter().sum::<f64>() * cube.voxel_volume();
+
+        assert!((total - 1.0).abs() < 0.02, "integral {}", total);
+    }
+
+    #[test]
+    fn test_real_orbital_cube_is_signed() {
+        let orbital = Orbital::from_label("2px").unwrap();
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Real, 10.0, 21);
+
+        // +x and -x lobes of 2px carry opposite signs
+        assert!(cube.value(15, 10, 10) > 0.0);
+        assert!(cube.value(5, 10, 10) < 0.0);
+        assert_eq!(cube.value(15, 10, 10), -cube.value(5, 10, 10));
+    }
+
+    #[test]
+    fn test_cube_uses_orbital_axes() {
+        let orbital = Orbital::from_label("2pz").unwrap();
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Real, 10.0, 21);
+
+        // the lobes of 2pz lie along the third grid axis, the file's z
+        assert!(cube.axes[2].z > 0.0);
+        assert!(cube.value(10, 10, 15) > 0.0);
+        assert!(cube.value(10, 10, 5) < 0.0);
+        assert!(cube.value(10, 15, 10).abs() < 1e-12);
+        assert!(cube.value(15, 10, 10).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(-3.414213e-6), " -3.41421E-06");
+        assert_eq!(format_value(0.0), "  0.00000E+00");
+        assert_eq!(format_value(1234.5), "  1.23450E+03");
+    }
+
+    #[test]
+    fn test_write_layout() {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let mut cube = CubeFile::from_orbital(&orbital, CubeField::Density, 5.0, 8);
+        cube.comments = ["title".to_string(), "field".to_string()];
+
+        let mut buf = Vec::new();
+        cube.write(&mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "title");
+        assert!(lines[2].trim_start().starts_with("1 "));
+        assert!(lines[3].trim_start().starts_with("8 "));
+        // 2 comments, 4 grid lines, 1 atom, 64 rows of 8 values split 6 + 2
+        assert_eq!(lines.len(), 2 + 4 + 1 + 64 * 2);
+    }
+
+    #[test]
+    fn test_read_round_trip() {
+        let orbital = Orbital::from_label("3dz2").unwrap();
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Real, 12.0, 9);
+
+        let mut buf = Vec::new();
+        cube.write(&mut buf).unwrap();
+        let read = CubeFile::read(buf.as_slice()).unwrap();
+
+        assert_eq!(read.shape, cube.shape);
+        assert_eq!(read.atoms.len(), 1);
+        assert_eq!(read.atoms[0].number, 1);
+        assert!((read.origin - cube.origin).norm() < 1e-6);
+        for (a, b) in read.values.iter().zip(&cube.values) {
+            assert!((a - b).abs() <= 1e-5 * b.abs() + 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_read_orbital_file_in_angstrom() {
+        // negative atom count: orbital list after the atoms, two orbitals per voxel
+        // negative voxel counts: angstrom units
+        let text = "\
+comment
+orbitals
+   -1    0.000000    0.000000    0.000000
+   -2    1.000000    0.000000    0.000000
+   -1    0.000000    1.000000    0.000000
+   -2    0.000000    0.000000    1.000000
+    8    8.000000    0.529177    0.000000    0.000000
+    2    5    6
+ 1.0D-01 -9.0 -2.0E-01 -9.0
+ 3.0E-01 -9.0  4.0E-01 -9.0
+";
+        let cube = CubeFile::read(text.as_bytes()).unwrap();
+
+        assert_eq!(cube.shape, [2, 1, 2]);
+        assert_eq!(cube.values, vec![0.1, -0.2, 0.3, 0.4]);
+        assert!((cube.axes[0].x - 1.0 / BOHR_ANGSTROM).abs() < 1e-12);
+        assert!((cube.atoms[0].position.x - 0.529177 / BOHR_ANGSTROM).abs() < 1e-12);
+        assert!(cube.is_signed());
+    }
+
+    #[test]
+    fn test_noisy_density_is_not_signed() {
+        let orbital = Orbital::from_label("2pz").unwrap();
+        let mut cube = CubeFile::from_orbital(&orbital, CubeField::Density, 12.0, 15);
+        assert!(!cube.is_signed());
+
+        // round-off just below zero in the far tail
+        let peak = cube.values.iter().copied().fold(0.0, f64::max);
+        for v in cube.values.iter_mut().take(40) {
+            *v = -1e-6 * peak;
+        }
+        assert!(!cube.is_signed());
+
+        let psi = CubeFile::from_orbital(&orbital, CubeField::Real, 12.0, 15);
+        assert!(psi.is_signed());
+    }
+
+    #[test]
+    fn test_read_rejects_truncated_data() {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Density, 5.0, 4);
+        let mut buf = Vec::new();
+        cube.write(&mut buf).unwrap();
+        buf.truncate(buf.len() - 30);
+
+        let err = CubeFile::read(buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_sample_follows_voxel_density() {
+        let orbital = Orbital::from_label("2pz").unwrap();
+        let cube = CubeFile::from_orbital(&orbital, CubeField::Real, 12.0, 25);
+        let mut sampler = Sampler::with_seed(3);
+        let particles = cube.sample(&mut sampler, 4000);
+
+        assert_eq!(particles.len(), 4000);
+        // 2pz lies along the orbital z axis, which is +y in the sampler frame,
+        // and has a nodal plane at y = 0
+        let along: f64 = particles.iter().map(|p| p.position.y.abs()).sum();
+        let across: f64 = particles.iter().map(|p| p.position.x.abs()).sum();
+        assert!(along > 1.5 * across, "{} vs {}", along, across);
+        // jitter stays inside the grid box
+        assert!(particles
+            .iter()
+            .all(|p| p.position.iter().all(|c| c.abs() <= 12.0 + 0.5)));
+    }
+}
@@ -0,0 +1,571 @@
+use crate::physics::{
+    self, build_cdf, invert_cdf, spherical_harmonic, spherical_to_cartesian, ColorMode, Harmonic,
+    Orbital, Particle, QuantumNumbers, Sampler, Species,
+};
+use crate::spin::{self, clebsch_gordan, SpinOrbital};
+use nalgebra_glm as glm;
+use rand::Rng;
+
+// exact bound states of the dirac equation in the field of a point nucleus of charge Z
+// psi = (g(r) Omega_kappa,mj, i f(r) Omega_-kappa,mj), where g and f are the large and small
+// radial components and Omega are spinor spherical harmonics; kappa = -(l + 1) for
+// j = l + 1/2 and kappa = l for j = l - 1/2, so the small component has the other parity
+// with gamma = sqrt(kappa^2 - (Z alpha)^2), E = c^2 / sqrt(1 + (Z alpha / (n_r + gamma))^2)
+// and n_r = n - |kappa|, the radial functions are (up to a common constant)
+//   g = sqrt(1 + E/c^2) rho^(gamma-1) e^(-rho/2) ((N - kappa) M(-n_r) - n_r M(1 - n_r))
+//   f = -sqrt(1 - E/c^2) rho^(gamma-1) e^(-rho/2) ((N - kappa) M(-n_r) + n_r M(1 - n_r))
+// with M(a) = M(a, 2 gamma + 1, rho) kummer's function, rho = 2 Z r / N and the apparent
+// principal quantum number N = sqrt(n_r^2 + 2 n_r gamma + kappa^2)
+// the nucleus is fixed and the electron has its rest mass, so Z alpha < |kappa| is needed;
+// beyond Z of about 137 the point-nucleus 1s1/2 state ceases to exist
+
+// points in the radial tables, which span [0, 10 n^2 / Z] like the hydrogenic ones
+const N_RADIAL: usize = 8192;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiracState {
+    n: i32,
+    kappa: i32,
+    two_mj: i32,
+    z: i32,
+    // 1 / sqrt of the integral of r^2 (g^2 + f^2) for the unnormalized functions
+    norm: f64,
+    // probability carried by the large component
+    large_fraction: f64,
+    mean_radius: f64,
+    dr: f64,
+    // inverse-cdf tables of r^2 g^2, r^2 f^2 and r^2 R_nl^2 of the schrodinger counterpart
+    large_cdf: Vec<f64>,
+    small_cdf: Vec<f64>,
+    schrodinger_cdf: Vec<f64>,
+}
+
+impl DiracState {
+    pub fn new(n: i32, kappa: i32, two_mj: i32, z: i32) -> Result<Self, String> {
+        if kappa == 0 {
+            return Err("kappa cannot be 0".to_string());
+        }
+        if n < 1 || kappa.abs() > n || (kappa == n) {
+            return Err(format!(
+                "kappa must satisfy 1 <= |kappa| <= n with kappa != n, not kappa={} for n={}",
+                kappa, n
+            ));
+        }
+        let two_j = 2 * kappa.abs() - 1;
+        if two_mj.abs() > two_j || (two_j + two_mj) % 2 != 0 {
+            return Err(format!(
+                "2 mj must be odd and at most {} in size, not {}",
+                two_j, two_mj
+            ));
+        }
+        if z < 1 || z as f64 * spin::ALPHA >= kappa.abs() as f64 {
+            return Err(format!(
+                "Z must be between 1 and {} for |kappa| = {}",
+                (kappa.abs() as f64 / spin::ALPHA).ceil() as i32 - 1,
+                kappa.abs()
+            ));
+        }
+
+        let mut state = Self {
+            n,
+            kappa,
+            two_mj,
+            z,
+            norm: 1.0,
+            large_fraction: 1.0,
+            mean_radius: 0.0,
+            dr: 10.0 * (n * n) as f64 / z as f64 / (N_RADIAL - 1) as f64,
+            large_cdf: Vec::new(),
+            small_cdf: Vec::new(),
+            schrodinger_cdf: Vec::new(),
+        };
+
+        // tables and normalization share one pass over the grid
+        let weights: Vec<(f64, f64)> = (0..N_RADIAL)
+            .map(|i| {
+                let r = i as f64 * state.dr;
+                if r == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let (g, f) = state.unnormalized_radial(r);
+                (r * r * g * g, r * r * f * f)
+            })
+            .collect();
+        let large: f64 = weights.iter().map(|w| w.0).sum();
+        let small: f64 = weights.iter().map(|w| w.1).sum();
+        let moment: f64 = weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| i as f64 * state.dr * (w.0 + w.1))
+            .sum();
+
+        state.norm = 1.0 / ((large + small) * state.dr).sqrt();
+        state.large_fraction = large / (large + small);
+        state.mean_radius = moment / (large + small);
+        state.large_cdf = build_cdf(N_RADIAL, |i| weights[i].0);
+        state.small_cdf = build_cdf(N_RADIAL, |i| weights[i].1);
+        let schrodinger = state.schrodinger_orbital(0);
+        state.schrodinger_cdf = build_cdf(N_RADIAL, |i| {
+            let r = i as f64 * state.dr;
+            let radial = schrodinger.radial(r);
+            r * r * radial * radial
+        });
+        Ok(state)
+    }
+
+    // "1s1/2", "2p3/2,-1/2" (mj defaults to j) or explicit "n,kappa,mj" such as "2,1,1/2"
+    pub fn parse(text: &str, z: i32) -> Result<Self, String> {
+        if text.contains(|c: char| c.is_ascii_alphabetic()) {
+            let pauli = SpinOrbital::parse(text, physics::HYDROGEN)?;
+            let two_j = (2.0 * pauli.j()) as i32;
+            let kappa = if two_j == 2 * pauli.l() + 1 {
+                -(pauli.l() + 1)
+            } else {
+                pauli.l()
+            };
+            return Self::new(pauli.n(), kappa, (2.0 * pauli.mj()) as i32, z);
+        }
+
+        let parts: Vec<&str> = text
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        let [n, kappa, mj] = parts.as_slice() else {
+            return Err("expected a label such as 2p1/2 or n,kappa,mj".to_string());
+        };
+        let n = n.parse().map_err(|_| format!("invalid n '{}'", n))?;
+        let kappa = kappa
+            .parse()
+            .map_err(|_| format!("invalid kappa '{}'", kappa))?;
+        let two_mj = match mj.split_once('/') {
+            Some((numerator, "2")) => numerator.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("mj must be a half-integer such as -1/2, not '{}'", mj))?;
+        Self::new(n, kappa, two_mj, z)
+    }
+
+    pub fn n(&self) -> i32 {
+        self.n
+    }
+
+    pub fn kappa(&self) -> i32 {
+        self.kappa
+    }
+
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+
+    pub fn j(&self) -> f64 {
+        self.kappa.abs() as f64 - 0.5
+    }
+
+    pub fn mj(&self) -> f64 {
+        self.two_mj as f64 / 2.0
+    }
+
+    // orbital angular momentum of the large component
+    pub fn l(&self) -> i32 {
+        orbital_l(self.kappa)
+    }
+
+    // orbital angular momentum of the small component
+    pub fn small_l(&self) -> i32 {
+        orbital_l(-self.kappa)
+    }
+
+    // fixed-nucleus species of the same charge, for the non-relativistic comparison
+    pub fn species(&self) -> Species {
+        physics::hydrogen_like(self.z)
+    }
+
+    pub fn gamma(&self) -> f64 {
+        let z_alpha = self.z as f64 * spin::ALPHA;
+        ((self.kappa * self.kappa) as f64 - z_alpha * z_alpha).sqrt()
+    }
+
+    // E / c^2, the total energy including the rest mass in units of m c^2
+    fn energy_ratio(&self) -> f64 {
+        let n_r = (self.n - self.kappa.abs()) as f64;
+        let ratio = self.z as f64 * spin::ALPHA / (n_r + self.gamma());
+        1.0 / (1.0 + ratio * ratio).sqrt()
+    }
+
+    // apparent principal quantum number N, equal to n when Z alpha -> 0
+    fn apparent_n(&self) -> f64 {
+        let n_r = (self.n - self.kappa.abs()) as f64;
+        let kappa = self.kappa as f64;
+        (n_r * n_r + 2.0 * n_r * self.gamma() + kappa * kappa).sqrt()
+    }
+
+    // E - m c^2 in hartree
+    pub fn binding_energy(&self) -> f64 {
+        let c = 1.0 / spin::ALPHA;
+        c * c * (self.energy_ratio() - 1.0)
+    }
+
+    // schrodinger energy -Z^2 / (2 n^2) in hartree
+    pub fn schrodinger_energy(&self) -> f64 {
+        self.species().energy(self.n)
+    }
+
+    pub fn large_fraction(&self) -> f64 {
+        self.large_fraction
+    }
+
+    // <r> in bohr, from the radial tables
+    pub fn mean_radius(&self) -> f64 {
+        self.mean_radius
+    }
+
+    // <r> of the schrodinger orbital, (3 n^2 - l(l+1)) / (2 Z)
+    pub fn schrodinger_mean_radius(&self) -> f64 {
+        let (n, l) = (self.n as f64, self.l() as f64);
+        (3.0 * n * n - l * (l + 1.0)) / (2.0 * self.z as f64)
+    }
+
+    // the same n, l, j, mj in the pauli picture, with the spin-orbit shift to order alpha^2
+    pub fn pauli(&self) -> SpinOrbital {
+        SpinOrbital::new(
+            self.n,
+            self.l(),
+            2 * self.kappa.abs() - 1,
+            self.two_mj,
+            self.species(),
+        )
+        .expect("dirac quantum numbers map onto a valid j state")
+    }
+
+    fn unnormalized_radial(&self, r: f64) -> (f64, f64) {
+        let n_r = self.n - self.kappa.abs();
+        let gamma = self.gamma();
+        let epsilon = self.energy_ratio();
+        let apparent_n = self.apparent_n();
+        let rho = 2.0 * self.z as f64 * r / apparent_n;
+        let b = 2.0 * gamma + 1.0;
+
+        let m0 = (apparent_n - self.kappa as f64) * kummer(-n_r, b, rho);
+        let m1 = if n_r > 0 {
+            n_r as f64 * kummer(1 - n_r, b, rho)
+        } else {
+            0.0
+        };
+        let common = rho.powf(gamma - 1.0) * (-rho / 2.0).exp();
+        (
+            (1.0 + epsilon).sqrt() * common * (m0 - m1),
+            -(1.0 - epsilon).sqrt() * common * (m0 + m1),
+        )
+    }
+
+    // normalized large and small radial components (g, f), int r^2 (g^2 + f^2) dr = 1
+    pub fn radial(&self, r: f64) -> (f64, f64) {
+        let (g, f) = self.unnormalized_radial(r);
+        (self.norm * g, self.norm * f)
+    }
+
+    // large and small component densities g^2 |Omega_kappa|^2 and f^2 |Omega_-kappa|^2
+    pub fn densities(&self, pos: &glm::DVec3) -> (f64, f64) {
+        let (r, theta, phi) = physics::cartesian_to_spherical(pos);
+        let (g, f) = self.radial(r);
+        let two_j = 2 * self.kappa.abs() - 1;
+        (
+            g * g * angular_density(self.l(), two_j, self.two_mj, theta, phi),
+            f * f * angular_density(self.small_l(), two_j, self.two_mj, theta, phi),
+        )
+    }
+
+    pub fn density(&self, pos: &glm::DVec3) -> f64 {
+        let (large, small) = self.densities(pos);
+        large + small
+    }
+
+    // density of the schrodinger counterpart, R_nl^2 |Omega_kappa|^2
+    pub fn schrodinger_density(&self, pos: &glm::DVec3) -> f64 {
+        let (r, theta, phi) = physics::cartesian_to_spherical(pos);
+        let radial = self.schrodinger_orbital(0).radial(r);
+        let two_j = 2 * self.kappa.abs() - 1;
+        radial * radial * angular_density(self.l(), two_j, self.two_mj, theta, phi)
+    }
+
+    fn schrodinger_orbital(&self, m: i32) -> Orbital {
+        Orbital::new(QuantumNumbers::new(self.n, self.l(), m).expect("l < n"))
+            .with_species(self.species())
+    }
+
+    // exact samples: each particle picks the large or small component by its probability,
+    // r from that component's table and the angles from one spin part of Omega
+    // density coloring shows the large component with the usual heatmap and the small one
+    // in magenta; phase coloring tints by spin like the j states
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        (0..num_particles)
+            .map(|_| {
+                let large = sampler.rng().gen::<f64>() < self.large_fraction;
+                let (cdf, l) = if large {
+                    (&self.large_cdf, self.l())
+                } else {
+                    (&self.small_cdf, self.small_l())
+                };
+                let r = invert_cdf(cdf, sampler.rng().gen()) as f64 * self.dr;
+                let position = self.sample_direction(sampler, l, r);
+                let (large_density, small_density) = self.densities(&position.0);
+                let density = large_density + small_density;
+                let own = if large { large_density } else { small_density };
+                let color = self.color(sampler.color_mode, large, position.1, own);
+                Particle {
+                    position: position.0,
+                    color,
+                    density,
+                }
+            })
+            .collect()
+    }
+
+    // the same picture for the schrodinger radial function, for side-by-side comparison
+    pub fn sample_schrodinger(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        (0..num_particles)
+            .map(|_| {
+                let r = invert_cdf(&self.schrodinger_cdf, sampler.rng().gen()) as f64 * self.dr;
+                let (position, up) = self.sample_direction(sampler, self.l(), r);
+                let density = self.schrodinger_density(&position);
+                let color = self.color(sampler.color_mode, true, up, density);
+                Particle {
+                    position,
+                    color,
+                    density,
+                }
+            })
+            .collect()
+    }
+
+    // distance of each cloud's nucleus from the origin in the side-by-side comparison,
+    // dirac at -x and schrodinger at +x, far enough apart that the clouds barely touch
+    pub fn comparison_offset(&self) -> f64 {
+        (3.0 * (self.n * self.n) as f64 + 2.0) / self.z as f64
+    }
+
+    // both clouds, num_particles each, with the dirac one on the left
+    pub fn sample_side_by_side(
+        &self,
+        sampler: &mut Sampler,
+        num_particles: usize,
+    ) -> Vec<Particle> {
+        let shift = glm::vec3(self.comparison_offset(), 0.0, 0.0);
+        let mut particles = self.sample(sampler, num_particles);
+        for particle in &mut particles {
+            particle.position -= shift;
+        }
+        for mut particle in self.sample_schrodinger(sampler, num_particles) {
+            particle.position += shift;
+            particles.push(particle);
+        }
+        particles
+    }
+
+    // density of the side-by-side picture, each cloud around its own nucleus
+    pub fn side_by_side_density(&self, pos: &glm::DVec3) -> f64 {
+        let shift = glm::vec3(self.comparison_offset(), 0.0, 0.0);
+        self.density(&(pos + shift)) + self.schrodinger_density(&(pos - shift))
+    }
+
+    // point at radius r along a direction drawn from |Omega|^2 for orbital momentum l,
+    // and whether it came from the spin-up part
+    fn sample_direction(&self, sampler: &mut Sampler, l: i32, r: f64) -> (glm::DVec3, bool) {
+        let two_j = 2 * self.kappa.abs() - 1;
+        let up_weight = spin_weights(l, two_j, self.two_mj)[0];
+        let up = sampler.rng().gen::<f64>() < up_weight;
+        let m = (self.two_mj - if up { 1 } else { -1 }) / 2;
+        let (theta, phi) = sampler.sample_angles(l, m, Harmonic::Complex);
+        (spherical_to_cartesian(r, theta, phi), up)
+    }
+
+    fn color(&self, color_mode: ColorMode, large: bool, up: bool, density: f64) -> glm::Vec4 {
+        let intensity = physics::density_intensity(density / (self.z as f64).powi(3));
+        match (color_mode, large) {
+            (ColorMode::Density, true) => physics::heatmap_cool(intensity),
+            (ColorMode::Density, false) => {
+                let v = (0.3 + 0.7 * intensity) as f32;
+                glm::vec4(0.95 * v, 0.25 * v, 0.85 * v, 0.6)
+            }
+            (ColorMode::Phase, _) => spin::spin_color(up, intensity),
+        }
+    }
+
+    // e.g. "2p1/2, mj=1/2 (kappa=1)"
+    pub fn label(&self) -> String {
+        format!(
+            "{}{}{}/2, mj={}/2 (kappa={})",
+            self.n,
+            physics::subshell_letter(self.l()),
+            2 * self.kappa.abs() - 1,
+            self.two_mj,
+            self.kappa
+        )
+    }
+
+    // the next mj of the level, wrapping around
+    pub fn step_mj(&self, delta: i32) -> Self {
+        let two_j = 2 * self.kappa.abs() - 1;
+        let index = ((self.two_mj + two_j) / 2 + delta).rem_euclid(two_j + 1);
+        Self {
+            two_mj: 2 * index - two_j,
+            ..self.clone()
+        }
+    }
+
+    // the partner level with the same l and the other j, e.g. 2p1/2 <-> 2p3/2, None when it
+    // does not exist in this shell
+    pub fn other_j(&self) -> Option<Self> {
+        let l = self.l();
+        let kappa = if self.kappa < 0 { l } else { -(l + 1) };
+        let two_j = 2 * kappa.abs() - 1;
+        Self::new(self.n, kappa, self.two_mj.clamp(-two_j, two_j), self.z).ok()
+    }
+}
+
+// l of the spherical harmonics in Omega_kappa
+fn orbital_l(kappa: i32) -> i32 {
+    if kappa > 0 {
+        kappa
+    } else {
+        -kappa - 1
+    }
+}
+
+// squared clebsch-gordan weights of the spin-up and spin-down parts of |l 1/2 j mj>
+fn spin_weights(l: i32, two_j: i32, two_mj: i32) -> [f64; 2] {
+    [1, -1].map(|two_ms| clebsch_gordan(2 * l, two_mj - two_ms, 1, two_ms, two_j, two_mj).powi(2))
+}
+
+// |Omega_kappa,mj|^2 at (theta, phi), the spin-summed angular density
+fn angular_density(l: i32, two_j: i32, two_mj: i32, theta: f64, phi: f64) -> f64 {
+    spin_weights(l, two_j, two_mj)
+        .iter()
+        .zip([1, -1])
+        .filter(|(w, _)| **w > 0.0)
+        .map(|(w, two_ms)| {
+            let m = (two_mj - two_ms) / 2;
+            w * spherical_harmonic(l, m, theta, phi, Harmonic::Complex).norm_sqr()
+        })
+        .sum()
+}
+
+// kummer's confluent hypergeometric function M(-k, b, x) for a non-positive first argument,
+// where the series ends after k + 1 terms
+fn kummer(a: i32, b: f64, x: f64) -> f64 {
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for s in 0..(-a) {
+        let s = s as f64;
+        term *= (a as f64 + s) / (b + s) * x / (s + 1.0);
+        sum += term;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_quantum_numbers() {
+        let state = DiracState::parse("2p1/2", 1).unwrap();
+        assert_eq!(
+            (state.n(), state.kappa(), state.l(), state.small_l()),
+            (2, 1, 1, 0)
+        );
+        let state = DiracState::parse("2p3/2,-1/2", 1).unwrap();
+        assert_eq!((state.kappa(), state.mj()), (-2, -0.5));
+        assert_eq!(DiracState::parse("3,-3,5/2", 1).unwrap().l(), 2);
+        assert!(DiracState::parse("1,1,1/2", 1).is_err());
+        assert!(DiracState::parse("1s1/2", 138).is_err());
+        assert!(DiracState::parse("2p3/2", 200).is_ok());
+        assert!(DiracState::parse("1s1/2", 92).is_ok());
+
+        let state = DiracState::parse("8,-7,1/2", 1).unwrap();
+        assert_eq!(state.label(), "8i13/2, mj=1/2 (kappa=-7)");
+    }
+
+    #[test]
+    fn test_energies() {
+        // hydrogen 1s: -1/2 - alpha^2 / 8 to leading order
+        let state = DiracState::parse("1s1/2", 1).unwrap();
+        let expected = -0.5 - spin::ALPHA.powi(2) / 8.0;
+        assert!((state.binding_energy() - expected).abs() < 1e-9);
+        // 2s1/2 and 2p1/2 stay degenerate, 2p3/2 agrees with the pauli fine structure
+        let s = DiracState::parse("2s1/2", 1).unwrap();
+        let p = DiracState::parse("2p1/2", 1).unwrap();
+        assert!((s.binding_energy() - p.binding_energy()).abs() < 1e-12);
+        let p32 = DiracState::parse("2p3/2", 1).unwrap();
+        let pauli = p32.schrodinger_energy() + p32.pauli().fine_structure_shift();
+        assert!((p32.binding_energy() - pauli).abs() < 1e-9);
+        // U91+ 1s: E = c^2 (gamma - 1)
+        let u = DiracState::parse("1s1/2", 92).unwrap();
+        let c2 = 1.0 / spin::ALPHA.powi(2);
+        assert!((u.binding_energy() - c2 * (u.gamma() - 1.0)).abs() < 1e-9 * c2);
+    }
+
+    #[test]
+    fn test_radial_functions() {
+        // 1s1/2 is closed-form: g ~ r^(gamma-1) e^(-Z r), f / g = -(1 - gamma) / (Z alpha)
+        let u = DiracState::parse("1s1/2", 92).unwrap();
+        let gamma = u.gamma();
+        let (g, f) = u.radial(0.01);
+        assert!((f / g + (1.0 - gamma) / (92.0 * spin::ALPHA)).abs() < 1e-12);
+        let (g2, _) = u.radial(0.02);
+        let expected = 2f64.powf(gamma - 1.0) * (-92.0 * 0.01f64).exp();
+        assert!((g2 / g - expected).abs() < 1e-9);
+
+        // the small component is (1 - gamma) / 2 of the 1s probability, and the exact
+        // <r> = (2 gamma + 1) / (2 Z) shows the contraction against 3 / (2 Z)
+        assert!((1.0 - u.large_fraction() - (1.0 - u.energy_ratio()) / 2.0).abs() < 1e-3);
+        let exact = (2.0 * gamma + 1.0) / (2.0 * 92.0);
+        assert!((u.mean_radius() - exact).abs() < 1e-3 * exact);
+        assert!(u.mean_radius() < u.schrodinger_mean_radius());
+
+        // light atoms reduce to schrodinger, e.g. <r> = 6 bohr for hydrogen 2s
+        let h = DiracState::parse("2s1/2", 1).unwrap();
+        assert!((h.mean_radius() - 6.0).abs() < 1e-3);
+        assert!(h.large_fraction() > 0.99999);
+    }
+
+    #[test]
+    fn test_sampling() {
+        let state = DiracState::parse("2p3/2,1/2", 80).unwrap();
+        let mut sampler = Sampler::with_seed(2);
+        let particles = state.sample(&mut sampler, 20_000);
+        let mean = particles
+            .iter()
+            .map(|p| glm::length(&p.position))
+            .sum::<f64>()
+            / 20_000.0;
+        assert!(
+            (mean - state.mean_radius()).abs() < 0.02 * state.mean_radius(),
+            "{} vs {}",
+            mean,
+            state.mean_radius()
+        );
+        let both = state.sample_side_by_side(&mut sampler, 100);
+        assert_eq!(both.len(), 200);
+        let left = both[..100].iter().map(|p| p.position.x).sum::<f64>() / 100.0;
+        assert!((left + state.comparison_offset()).abs() < 0.1 * state.comparison_offset());
+        let p = both[150].position;
+        let d = state.side_by_side_density(&p);
+        assert!(
+            (d - both[150].density).abs() < 1e-3 * d,
+            "{} vs {}",
+            d,
+            both[150].density
+        );
+
+        let schrodinger = state.sample_schrodinger(&mut sampler, 20_000);
+        let mean = schrodinger
+            .iter()
+            .map(|p| glm::length(&p.position))
+            .sum::<f64>()
+            / 20_000.0;
+        let expected = state.schrodinger_mean_radius();
+        assert!((mean - expected).abs() < 0.02 * expected);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::physics::{self, Particle};
+use std::io::{self, Write};
+
+// writers for sampled particle clouds
+// all positions are written in bohr in the orbital frame, z being the polar axis that the
+// sampler draws as +y, colors as the rgba used by the renderer
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    // whitespace columns: x y z r g b a
+    Text,
+    // stanford polygon file, point cloud only, per-vertex rgba
+    PlyAscii,
+    PlyBinary,
+    // xyz molecule format with one dummy atom per particle
+    Xyz,
+    // x, y, z, r, theta, phi, density with a header row
+    Csv,
+}
+
+impl ExportFormat {
+    // parses names as accepted on the command line
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "txt" | "text" => Some(ExportFormat::Text),
+            "ply" | "ply-binary" => Some(ExportFormat::PlyBinary),
+            "ply-ascii" => Some(ExportFormat::PlyAscii),
+            "xyz" => Some(ExportFormat::Xyz),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    // guesses the format from a file name, defaulting to binary ply for .ply
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1;
+        match ext.to_lowercase().as_str() {
+            "txt" | "dat" => Some(ExportFormat::Text),
+            "ply" => Some(ExportFormat::PlyBinary),
+            "xyz" => Some(ExportFormat::Xyz),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Text => "txt",
+            ExportFormat::PlyAscii | ExportFormat::PlyBinary => "ply",
+            ExportFormat::Xyz => "xyz",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+// writes the particles in the chosen format
+// comment is embedded where the format allows it (header or comment line)
+pub fn write_particles<W: Write>(
+    out: &mut W,
+    particles: &[Particle],
+    format: ExportFormat,
+    comment: &str,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Text => write_text(out, particles, comment),
+        ExportFormat::PlyAscii => write_ply(out, particles, comment, false),
+        ExportFormat::PlyBinary => write_ply(out, particles, comment, true),
+        ExportFormat::Xyz => write_xyz(out, particles, comment),
+        ExportFormat::Csv => write_csv(out, particles),
+    }
+}
+
+// plain whitespace-separated columns: x y z r g b a
+// one particle per line after a commented header, easy to load with numpy.loadtxt
+pub fn write_text<W: Write>(out: &mut W, particles: &[Particle], comment: &str) -> io::Result<()> {
+    writeln!(out, "# {}", comment)?;
+    writeln!(out, "# x y z r g b a")?;
+
+    for p in particles {
+        let pos = physics::swap_yz(&p.position);
+        writeln!(
+            out,
+            "{:.8e} {:.8e} {:.8e} {:.4} {:.4} {:.4} {:.4}",
+            pos.x, pos.y, pos.z, p.color.x, p.color.y, p.color.z, p.color.w
+        )?;
+    }
+
+    Ok(())
+}
+
+// point-cloud ply with float positions and uchar rgba, readable by meshlab and paraview
+pub fn write_ply<W: Write>(
+    out: &mut W,
+    particles: &[Particle],
+    comment: &str,
+    binary: bool,
+) -> io::Result<()> {
+    let format = if binary {
+        "binary_little_endian"
+    } else {
+        "ascii"
+    };
+
+    writeln!(out, "ply")?;
+    writeln!(out, "format {} 1.0", format)?;
+    writeln!(out, "comment {}", comment.replace('\n', " "))?;
+    writeln!(out, "element vertex {}", particles.len())?;
+    for axis in ["x", "y", "z"] {
+        writeln!(out, "property float {}", axis)?;
+    }
+    for channel in ["red", "green", "blue", "alpha"] {
+        writeln!(out, "property uchar {}", channel)?;
+    }
+    writeln!(out, "end_header")?;
+
+    for p in particles {
+        let pos = physics::swap_yz(&p.position);
+        let pos = [pos.x as f32, pos.y as f32, pos.z as f32];
+        let rgba = color_bytes(&p.color);
+
+        if binary {
+            for v in pos {
+                out.write_all(&v.to_le_bytes())?;
+            }
+            out.write_all(&rgba)?;
+        } else {
+            writeln!(
+                out,
+                "{} {} {} {} {} {} {}",
+                pos[0], pos[1], pos[2], rgba[0], rgba[1], rgba[2], rgba[3]
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// xyz format: count line, comment line, then one "X x y z" line per particle
+// positions are converted to angstrom as the format expects
+pub fn write_xyz<W: Write>(out: &mut W, particles: &[Particle], comment: &str) -> io::Result<()> {
+    writeln!(out, "{}", particles.len())?;
+    writeln!(out, "{} (angstrom)", comment.replace('\n', " "))?;
+
+    for p in particles {
+        let pos = physics::swap_yz(&p.position) * physics::BOHR_ANGSTROM;
+        writeln!(out, "X {:.8} {:.8} {:.8}", pos.x, pos.y, pos.z)?;
+    }
+
+    Ok(())
+}
+
+// csv with cartesian and spherical coordinates plus |psi|^2, for pandas and friends
+// theta is measured from the z axis
+pub fn write_csv<W: Write>(out: &mut W, particles: &[Particle]) -> io::Result<()> {
+    writeln!(out, "x,y,z,r,theta,phi,density")?;
+
+    for p in particles {
+        let (r, theta, phi) = physics::cartesian_to_spherical(&p.position);
+        let pos = physics::swap_yz(&p.position);
+        writeln!(
+            out,
+            "{:.8e},{:.8e},{:.8e},{:.8e},{:.8},{:.8},{:.8e}",
+            pos.x, pos.y, pos.z, r, theta, phi, p.density
+        )?;
+    }
+
+    Ok(())
+}
+
+fn color_bytes(color: &nalgebra_glm::Vec4) -> [u8; 4] {
+    let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        to_byte(color.x),
+        to_byte(color.y),
+        to_byte(color.z),
+        to_byte(color.w),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm as glm;
+
+    fn particles() -> Vec<Particle> {
+        vec![
+            Particle {
+                position: glm::vec3(1.0, 0.0, 0.0),
+                color: glm::vec4(1.0, 0.5, 0.0, 0.6),
+                density: 0.25,
+            },
+            Particle {
+                position: glm::vec3(0.0, 2.0, 0.0),
+                color: glm::vec4(0.0, 0.0, 1.0, 0.6),
+                density: 0.5,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_binary_ply_layout() {
+        let mut buf = Vec::new();
+        write_ply(&mut buf, &particles(), "test", true).unwrap();
+
+        let header_end = b"end_header\n";
+        let pos = buf
+            .windows(header_end.len())
+            .position(|w| w == header_end)
+            .unwrap();
+        let body = &buf[pos + header_end.len()..];
+
+        // three floats and four bytes per vertex
+        assert_eq!(body.len(), 2 * (3 * 4 + 4));
+        assert_eq!(&body[0..4], &1.0f32.to_le_bytes());
+        assert_eq!(&body[12..16], &[255, 128, 0, 153]);
+    }
+
+    #[test]
+    fn test_csv_spherical_columns() {
+        let mut buf = Vec::new();
+        write_csv(&mut buf, &particles()).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "x,y,z,r,theta,phi,density");
+        assert_eq!(lines.len(), 3);
+
+        // second particle sits on the orbital z axis: z = 2, r = 2, theta = 0
+        let cols: Vec<f64> = lines[2].split(',').map(|c| c.parse().unwrap()).collect();
+        assert_eq!(&cols[0..3], &[0.0, 0.0, 2.0]);
+        assert_eq!(cols[3], 2.0);
+        assert_eq!(cols[4], 0.0);
+        assert_eq!(cols[6], 0.5);
+    }
+
+    #[test]
+    fn test_xyz_header() {
+        let mut buf = Vec::new();
+        write_xyz(&mut buf, &particles(), "cloud").unwrap();
+        let text = String::from_utf8(buf).unwrap();
+
+        assert!(text.starts_with("2\ncloud"));
+        assert_eq!(text.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path("a.ply"),
+            Some(ExportFormat::PlyBinary)
+        );
+        assert_eq!(
+            ExportFormat::from_path("dir.v2/a.CSV"),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(ExportFormat::from_path("a"), None);
+    }
+}
@@ -36,10 +36,10 @@ pub fn generate_sphere(radius: f32, sectors: u32, stacks: u32) -> Vec<glm::Vec3>
     let mut indices = Vec::new();
 
     for i in 0..stacks {
-        let mut k1 = i * (sectors + 1);
-        let mut k2 = k1 + sectors + 1;
+        for j in 0..sectors {
+            let k1 = i * (sectors + 1) + j;
+            let k2 = k1 + sectors + 1;
 
-        for _ in 0..sectors {
             // first triangle (except top stack)
             if i != 0 {
                 indices.push(k1);
@@ -53,9 +53,6 @@ pub fn generate_sphere(radius: f32, sectors: u32, stacks: u32) -> Vec<glm::Vec3>
                 indices.push(k2);
                 indices.push(k2 + 1);
             }
-
-            k1 += 1;
-            k2 += 1;
         }
     }
 
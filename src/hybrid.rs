@@ -0,0 +1,250 @@
+use crate::physics::{Orbital, QuantumNumbers, Species};
+use crate::superposition::{Superposition, Term};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+
+// equivalent hybrid orbitals built from the real s, p and d functions of one shell
+// all members of a set share n, so they are degenerate and their clouds do not beat
+// radial functions are taken with their outermost lobe positive, which puts the large
+// lobe of every hybrid along its direction instead of behind the nucleus
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HybridSet {
+    // two hybrids along +z and -z, linear
+    Sp,
+    // three hybrids in the xy plane 120 degrees apart, trigonal planar, pz left for pi bonds
+    Sp2,
+    // four hybrids towards alternate corners of a cube, tetrahedral
+    Sp3,
+    // six hybrids along +-x, +-y, +-z from the n = 3 shell, octahedral
+    Sp3d2,
+}
+
+impl HybridSet {
+    pub const ALL: [HybridSet; 4] = [
+        HybridSet::Sp,
+        HybridSet::Sp2,
+        HybridSet::Sp3,
+        HybridSet::Sp3d2,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HybridSet::Sp => "sp",
+            HybridSet::Sp2 => "sp2",
+            HybridSet::Sp3 => "sp3",
+            HybridSet::Sp3d2 => "sp3d2",
+        }
+    }
+
+    // number of hybrids in the set
+    pub fn count(&self) -> usize {
+        self.directions().len()
+    }
+
+    // principal quantum number of the shell the set is built from
+    pub fn shell(&self) -> i32 {
+        match self {
+            HybridSet::Sp3d2 => 3,
+            _ => 2,
+        }
+    }
+
+    // unit vectors along the lobes in the orbital frame, z being the orbital axis
+    pub fn directions(&self) -> Vec<glm::DVec3> {
+        match self {
+            HybridSet::Sp => vec![glm::vec3(0.0, 0.0, 1.0), glm::vec3(0.0, 0.0, -1.0)],
+            HybridSet::Sp2 => (0..3)
+                .map(|k| {
+                    let phi = 2.0 * std::f64::consts::PI * k as f64 / 3.0;
+                    glm::vec3(phi.cos(), phi.sin(), 0.0)
+                })
+                .collect(),
+            HybridSet::Sp3 => [
+                (1.0, 1.0, 1.0),
+                (1.0, -1.0, -1.0),
+                (-1.0, 1.0, -1.0),
+                (-1.0, -1.0, 1.0),
+            ]
+            .iter()
+            .map(|&(x, y, z)| glm::vec3(x, y, z) / 3f64.sqrt())
+            .collect(),
+            HybridSet::Sp3d2 => vec![
+                glm::vec3(0.0, 0.0, 1.0),
+                glm::vec3(0.0, 0.0, -1.0),
+                glm::vec3(1.0, 0.0, 0.0),
+                glm::vec3(-1.0, 0.0, 0.0),
+                glm::vec3(0.0, 1.0, 0.0),
+                glm::vec3(0.0, -1.0, 0.0),
+            ],
+        }
+    }
+
+    // direction of hybrid `index` (from 0) in the sampler frame, where the orbital z
+    // axis points along +y
+    pub fn direction(&self, index: usize) -> glm::DVec3 {
+        let d = self.directions()[index];
+        glm::vec3(d.x, d.z, d.y)
+    }
+
+    // coefficients of the real orbitals in hybrid `index`, e.g. (1/2, 2s) for sp3
+    pub fn coefficients(&self, index: usize) -> Vec<(f64, &'static str)> {
+        let d = self.directions()[index];
+        match self {
+            HybridSet::Sp => vec![(0.5f64.sqrt(), "s"), (0.5f64.sqrt() * d.z, "pz")],
+            HybridSet::Sp2 => {
+                let p = (2.0f64 / 3.0).sqrt();
+                vec![(1.0 / 3f64.sqrt(), "s"), (p * d.x, "px"), (p * d.y, "py")]
+            }
+            HybridSet::Sp3 => {
+                let p = 3f64.sqrt() / 2.0;
+                vec![
+                    (0.5, "s"),
+                    (p * d.x, "px"),
+                    (p * d.y, "py"),
+                    (p * d.z, "pz"),
+                ]
+            }
+            HybridSet::Sp3d2 => {
+                let p = 0.5f64.sqrt();
+                vec![
+                    (1.0 / 6f64.sqrt(), "s"),
+                    (p * d.x, "px"),
+                    (p * d.y, "py"),
+                    (p * d.z, "pz"),
+                    ((3.0 * d.z * d.z - 1.0) / (2.0 * 3f64.sqrt()), "dz2"),
+                    ((d.x * d.x - d.y * d.y) / 2.0, "dx2-y2"),
+                ]
+            }
+        }
+        .into_iter()
+        .filter(|(c, _)| *c != 0.0)
+        .collect()
+    }
+
+    // hybrid `index` as a stationary superposition of the shell's real orbitals
+    pub fn hybrid(&self, index: usize, species: Species) -> Superposition {
+        let n = self.shell();
+        let terms = self
+            .coefficients(index)
+            .into_iter()
+            .map(|(c, name)| {
+                let orbital = Orbital::from_label(&format!("{}{}", n, name))
+                    .expect("hybrid basis labels are valid")
+                    .with_species(species);
+                let outer_sign = outermost_lobe_sign(orbital.quantum_numbers);
+                Term {
+                    coefficient: Complex64::new(c * outer_sign, 0.0),
+                    orbital,
+                }
+            })
+            .collect();
+        Superposition::new(terms).expect("hybrid coefficients form a valid superposition")
+    }
+
+    // e.g. "sp3 #2 of 4"
+    pub fn label(&self, index: usize) -> String {
+        format!("{} #{} of {}", self.name(), index + 1, self.count())
+    }
+}
+
+// parses "sp3" (the first hybrid of the set) or "sp3:2" into the set and a 0-based index
+pub fn parse_hybrid_label(label: &str) -> Result<(HybridSet, usize), String> {
+    let label = label.trim().to_lowercase();
+    let (name, number) = match label.split_once(':') {
+        Some((name, number)) => (name.trim(), Some(number.trim())),
+        None => (label.as_str(), None),
+    };
+
+    let set = HybridSet::ALL
+        .iter()
+        .copied()
+        .find(|set| set.name() == name)
+        .ok_or_else(|| format!("unknown hybrid '{}', expected sp, sp2, sp3 or sp3d2", name))?;
+
+    let index = match number {
+        None => 0,
+        Some(number) => match number.parse::<usize>() {
+            Ok(k) if (1..=set.count()).contains(&k) => k - 1,
+            _ => {
+                return Err(format!(
+                    "{} has hybrids 1 to {}, not '{}'",
+                    set.name(),
+                    set.count(),
+                    number
+                ))
+            }
+        },
+    };
+
+    Ok((set, index))
+}
+
+// sign of R_nl beyond its last node, (-1)^(n - l - 1) from the leading laguerre term
+fn outermost_lobe_sign(qn: QuantumNumbers) -> f64 {
+    if (qn.n() - qn.l() - 1) % 2 == 0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::HYDROGEN;
+
+    #[test]
+    fn test_coefficients_orthonormal() {
+        for set in HybridSet::ALL {
+            for i in 0..set.count() {
+                for j in 0..set.count() {
+                    let a = set.coefficients(i);
+                    let b = set.coefficients(j);
+                    let overlap: f64 = a
+                        .iter()
+                        .map(|(ca, name)| {
+                            b.iter()
+                                .filter(|(_, other)| other == name)
+                                .map(|(cb, _)| ca * cb)
+                                .sum::<f64>()
+                        })
+                        .sum();
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!(
+                        (overlap - expected).abs() < 1e-12,
+                        "{} {} {}: {}",
+                        set.name(),
+                        i,
+                        j,
+                        overlap
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lobes_point_along_directions() {
+        for set in HybridSet::ALL {
+            let r = 2.0 * (set.shell() * set.shell()) as f64;
+            for i in 0..set.count() {
+                let state = set.hybrid(i, HYDROGEN);
+                let along = state.density(&(set.direction(i) * r), 0.0);
+                for j in (0..set.count()).filter(|&j| j != i) {
+                    let other = state.density(&(set.direction(j) * r), 0.0);
+                    assert!(along > 5.0 * other, "{} lobe {}", set.label(i), j);
+                }
+                assert!(along > 5.0 * state.density(&(set.direction(i) * -r), 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_hybrid_label() {
+        assert_eq!(parse_hybrid_label("sp3"), Ok((HybridSet::Sp3, 0)));
+        assert_eq!(parse_hybrid_label(" SP3D2:6 "), Ok((HybridSet::Sp3d2, 5)));
+        assert!(parse_hybrid_label("sp2:4").is_err());
+        assert!(parse_hybrid_label("sp4").is_err());
+    }
+}
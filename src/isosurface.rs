@@ -0,0 +1,499 @@
+use crate::cube::{CubeField, CubeFile};
+use crate::physics::{self, Harmonic, Orbital, Particle};
+use nalgebra_glm as glm;
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+// isosurfaces by marching cubes over a cube file grid
+// real orbitals and signed cube files yield separate positive and negative lobes,
+// complex orbitals a single |psi|^2 surface painted with the phase of psi
+
+// where to place the surface
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IsoLevel {
+    // |psi|^2 (or the stored density of an unsigned cube file) in bohr^-3
+    Density(f64),
+    // the density whose superlevel set holds this fraction of the probability on the grid
+    Enclosed(f64),
+}
+
+impl IsoLevel {
+    fn resolve(self, densities: &[f64]) -> f64 {
+        match self {
+            IsoLevel::Density(level) => level,
+            IsoLevel::Enclosed(fraction) => enclosed_level(densities, fraction),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshVertex {
+    pub position: glm::DVec3,
+    // unit normal pointing out of the enclosed region, towards lower values
+    pub normal: glm::DVec3,
+    pub color: glm::Vec4,
+}
+
+// flat triangle list, three consecutive vertices per triangle like geometry::generate_sphere
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.vertices.len() / 3
+    }
+
+    pub fn append(&mut self, mut other: Mesh) {
+        self.vertices.append(&mut other.vertices);
+    }
+
+    fn paint(mut self, color: impl Fn(&glm::DVec3) -> glm::Vec4) -> Self {
+        for vertex in &mut self.vertices {
+            vertex.color = color(&vertex.position);
+        }
+        self
+    }
+}
+
+// density level whose superlevel set {rho >= level} holds `fraction` of the total,
+// for densities sampled on a uniform grid (the voxel volume cancels)
+pub fn enclosed_level(densities: &[f64], fraction: f64) -> f64 {
+    let mut sorted: Vec<f64> = densities.iter().copied().filter(|d| *d > 0.0).collect();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+
+    let target = fraction.clamp(0.0, 1.0) * sorted.iter().sum::<f64>();
+    let mut enclosed = 0.0;
+    for density in &sorted {
+        enclosed += density;
+        if enclosed >= target {
+            return *density;
+        }
+    }
+    sorted.last().copied().unwrap_or(0.0)
+}
+
+// density threshold whose superlevel set holds `fraction` of the orbital's probability,
+// by integrating |psi|^2 over a cubic grid of `points` per axis
+pub fn density_threshold(orbital: &Orbital, fraction: f64, points: usize) -> f64 {
+    let half_width = CubeFile::default_half_width(orbital);
+    let cube = CubeFile::from_orbital(orbital, CubeField::Density, half_width, points);
+    enclosed_level(&cube.values, fraction)
+}
+
+// the same threshold estimated from particles already drawn from the density:
+// `fraction` of the samples, and so of the probability, lies where the density is at least
+// the matching quantile counted from the top
+pub fn particle_threshold(particles: &[Particle], fraction: f64) -> f64 {
+    if particles.is_empty() {
+        return 0.0;
+    }
+
+    let mut densities: Vec<f64> = particles.iter().map(|p| p.density).collect();
+    let count = (fraction.clamp(0.0, 1.0) * densities.len() as f64).ceil() as usize;
+    let index = count.clamp(1, densities.len()) - 1;
+    *densities
+        .select_nth_unstable_by(index, |a, b| b.total_cmp(a))
+        .1
+}
+
+// keeps the particles inside the surface density = threshold
+pub fn cull_particles(particles: &[Particle], threshold: f64) -> Vec<Particle> {
+    particles
+        .iter()
+        .filter(|p| p.density >= threshold)
+        .copied()
+        .collect()
+}
+
+// isosurface of an orbital evaluated on a cubic grid of `points` per axis
+pub fn orbital_isosurface(orbital: &Orbital, level: IsoLevel, points: usize) -> Mesh {
+    let half_width = CubeFile::default_half_width(orbital);
+
+    match orbital.harmonic {
+        Harmonic::Real => {
+            let cube = CubeFile::from_orbital(orbital, CubeField::Real, half_width, points);
+            cube_isosurface(&cube, level)
+        }
+        Harmonic::Complex => {
+            let cube = CubeFile::from_orbital(orbital, CubeField::Density, half_width, points);
+            let level = level.resolve(&cube.values);
+            marching_cubes(&cube, level)
+                .paint(|p| physics::phase_wheel(orbital.psi_at(p).arg(), 1.0))
+        }
+    }
+}
+
+// isosurface of a cube file: signed cubes are treated as orbitals with density psi^2
+// and give psi = +sqrt(level) and psi = -sqrt(level) lobes, unsigned cubes one surface
+pub fn cube_isosurface(cube: &CubeFile, level: IsoLevel) -> Mesh {
+    if !cube.is_signed() {
+        let level = level.resolve(&cube.values);
+        return marching_cubes(cube, level).paint(|_| physics::heatmap_cool(1.0));
+    }
+
+    let densities: Vec<f64> = cube.values.iter().map(|v| v * v).collect();
+    let amplitude = level.resolve(&densities).sqrt();
+
+    let mut negated = cube.clone();
+    for v in &mut negated.values {
+        *v = -*v;
+    }
+
+    let mut mesh = marching_cubes(cube, amplitude).paint(|_| physics::sign_color(0.0, 1.0));
+    mesh.append(marching_cubes(&negated, amplitude).paint(|_| physics::sign_color(PI, 1.0)));
+    mesh
+}
+
+// corner c of a cell sits at grid offset (c & 1, (c >> 1) & 1, (c >> 2) & 1)
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+fn edge_between(a: usize, b: usize) -> usize {
+    EDGES
+        .iter()
+        .position(|&(p, q)| (p, q) == (a, b) || (q, p) == (a, b))
+        .unwrap()
+}
+
+// the six cell faces as corner cycles
+fn faces() -> Vec<[usize; 4]> {
+    let mut faces = Vec::with_capacity(6);
+    for axis in 0..3 {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        for side in 0..2 {
+            let corner = |du: usize, dv: usize| (side << axis) | (du << u) | (dv << v);
+            faces.push([corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1)]);
+        }
+    }
+    faces
+}
+
+// contour loops, as cycles of cell edges, for each of the 256 inside/outside corner patterns
+// built by tracing the contour across the six faces instead of a hand-typed table:
+// each face contributes segments between its crossed edges, and the segments close into loops
+// a face with two diagonal inside corners cuts each inside corner off separately;
+// the rule only looks at the face itself, so neighbouring cells agree and the mesh stays closed
+// built on first use and shared by every later call
+fn case_table() -> &'static [Vec<Vec<usize>>] {
+    static TABLE: OnceLock<Vec<Vec<Vec<usize>>>> = OnceLock::new();
+    TABLE.get_or_init(build_case_table)
+}
+
+fn build_case_table() -> Vec<Vec<Vec<usize>>> {
+    let faces = faces();
+
+    (0..256usize)
+        .map(|case| {
+            let inside = |corner: usize| (case >> corner) & 1 == 1;
+            let mut links: Vec<Vec<usize>> = vec![Vec::new(); 12];
+
+            for face in &faces {
+                let edge = |k: usize| edge_between(face[k % 4], face[(k + 1) % 4]);
+                let crossed: Vec<usize> = (0..4)
+                    .filter(|&k| inside(face[k]) != inside(face[(k + 1) % 4]))
+                    .collect();
+
+                let segments: Vec<(usize, usize)> = match crossed.len() {
+                    2 => vec![(edge(crossed[0]), edge(crossed[1]))],
+                    4 => (0..4)
+                        .filter(|&k| inside(face[k]))
+                        .map(|k| (edge(k + 3), edge(k)))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                for (a, b) in segments {
+                    links[a].push(b);
+                    links[b].push(a);
+                }
+            }
+
+            let mut contours = Vec::new();
+            let mut visited = [false; 12];
+            for start in 0..12 {
+                if visited[start] || links[start].is_empty() {
+                    continue;
+                }
+
+                let mut contour = vec![start];
+                visited[start] = true;
+                let (mut previous, mut current) = (start, links[start][0]);
+                while current != start {
+                    contour.push(current);
+                    visited[current] = true;
+                    let next = if links[current][0] == previous {
+                        links[current][1]
+                    } else {
+                        links[current][0]
+                    };
+                    previous = current;
+                    current = next;
+                }
+                contours.push(contour);
+            }
+            contours
+        })
+        .collect()
+}
+
+// extracts the surface value = level from a cube grid, with the region value >= level inside
+// vertices are interpolated linearly along cell edges, normals from the interpolated gradient
+// loops longer than a triangle are fanned around their centroid, which keeps every
+// triangle inside its cell even when the loop is far from planar
+// the mesh is returned in the sampler frame, ready to draw next to the particles
+pub fn marching_cubes(cube: &CubeFile, level: f64) -> Mesh {
+    let table = case_table();
+    let [ni, nj, nk] = cube.shape;
+    let mut mesh = Mesh::default();
+    if ni < 2 || nj < 2 || nk < 2 {
+        return mesh;
+    }
+
+    // converts gradients in grid index space to cartesian ones: df/di = grad . axes[i]
+    let axes = glm::mat3(
+        cube.axes[0].x,
+        cube.axes[0].y,
+        cube.axes[0].z,
+        cube.axes[1].x,
+        cube.axes[1].y,
+        cube.axes[1].z,
+        cube.axes[2].x,
+        cube.axes[2].y,
+        cube.axes[2].z,
+    );
+    let to_cartesian = glm::inverse(&axes);
+
+    // central differences, one sided on the grid border
+    let gradient = |idx: [usize; 3]| {
+        let mut g = glm::vec3(0.0, 0.0, 0.0);
+        for axis in 0..3 {
+            let mut lo = idx;
+            let mut hi = idx;
+            lo[axis] = idx[axis].saturating_sub(1);
+            hi[axis] = (idx[axis] + 1).min(cube.shape[axis] - 1);
+            g[axis] = (cube.value(hi[0], hi[1], hi[2]) - cube.value(lo[0], lo[1], lo[2]))
+                / (hi[axis] - lo[axis]) as f64;
+        }
+        to_cartesian * g
+    };
+
+    let white = glm::vec4(1.0, 1.0, 1.0, 1.0);
+    for i in 0..ni - 1 {
+        for j in 0..nj - 1 {
+            for k in 0..nk - 1 {
+                let corner = |c: usize| [i + (c & 1), j + ((c >> 1) & 1), k + ((c >> 2) & 1)];
+                let values: Vec<f64> = (0..8)
+                    .map(|c| {
+                        let [a, b, d] = corner(c);
+                        cube.value(a, b, d)
+                    })
+                    .collect();
+
+                let case = (0..8).fold(0, |acc, c| acc | (usize::from(values[c] >= level) << c));
+                if table[case].is_empty() {
+                    continue;
+                }
+
+                let vertex = |edge: usize| {
+                    let (p, q) = EDGES[edge];
+                    let t = ((level - values[p]) / (values[q] - values[p])).clamp(0.0, 1.0);
+                    let [pi, pj, pk] = corner(p);
+                    let [qi, qj, qk] = corner(q);
+                    let position = glm::lerp(&cube.point(pi, pj, pk), &cube.point(qi, qj, qk), t);
+                    let g = glm::lerp(&gradient(corner(p)), &gradient(corner(q)), t);
+                    let normal = if glm::length(&g) > 0.0 {
+                        -glm::normalize(&g)
+                    } else {
+                        g
+                    };
+                    MeshVertex {
+                        position: physics::swap_yz(&position),
+                        normal: physics::swap_yz(&normal),
+                        color: white,
+                    }
+                };
+
+                for contour in &table[case] {
+                    let ring: Vec<MeshVertex> = contour.iter().map(|e| vertex(*e)).collect();
+                    if ring.len() == 3 {
+                        push_triangle(&mut mesh, [ring[0], ring[1], ring[2]]);
+                        continue;
+                    }
+
+                    let count = ring.len() as f64;
+                    let normal: glm::DVec3 = ring.iter().map(|v| v.normal).sum();
+                    let center = MeshVertex {
+                        position: ring.iter().map(|v| v.position).sum::<glm::DVec3>() / count,
+                        normal: if glm::length(&normal) > 0.0 {
+                            glm::normalize(&normal)
+                        } else {
+                            normal
+                        },
+                        color: white,
+                    };
+                    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                        push_triangle(&mut mesh, [center, *a, *b]);
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+// appends a triangle wound counter-clockwise when seen from outside
+fn push_triangle(mesh: &mut Mesh, mut corners: [MeshVertex; 3]) {
+    let face_normal = glm::cross(
+        &(corners[1].position - corners[0].position),
+        &(corners[2].position - corners[0].position),
+    );
+    let outward = corners[0].normal + corners[1].normal + corners[2].normal;
+    if glm::dot(&face_normal, &outward) < 0.0 {
+        corners.swap(1, 2);
+    }
+    mesh.vertices.extend(corners);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::QuantumNumbers;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    // cube on [-2, 2]^3 holding the given field
+    fn grid(points: usize, mut field: impl FnMut(&glm::DVec3) -> f64) -> CubeFile {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let mut cube = CubeFile::from_orbital(&orbital, CubeField::Density, 2.0, points);
+        for i in 0..points {
+            for j in 0..points {
+                for k in 0..points {
+                    cube.values[(i * points + j) * points + k] = field(&cube.point(i, j, k));
+                }
+            }
+        }
+        cube
+    }
+
+    // every triangle edge must be shared by exactly two triangles
+    fn assert_closed(mesh: &Mesh) {
+        let key = |p: &glm::DVec3| {
+            let q = p * 1e6;
+            (q.x.round() as i64, q.y.round() as i64, q.z.round() as i64)
+        };
+        let mut edges = HashMap::new();
+        for triangle in mesh.vertices.chunks(3) {
+            for e in 0..3 {
+                let a = key(&triangle[e].position);
+                let b = key(&triangle[(e + 1) % 3].position);
+                let edge = if a < b { (a, b) } else { (b, a) };
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        assert!(edges.values().all(|count| *count == 2));
+    }
+
+    #[test]
+    fn test_sphere_surface() {
+        let cube = grid(33, |p| 1.0 - glm::length2(p));
+        // the level sits just off 0 so no vertex lands exactly on a grid corner
+        let mesh = marching_cubes(&cube, 0.013);
+
+        assert!(mesh.triangle_count() > 100);
+        assert_closed(&mesh);
+        for v in &mesh.vertices {
+            assert!((glm::length(&v.position) - 1.0).abs() < 0.02);
+            // normals point out of the sphere
+            assert!(glm::dot(&v.normal, &v.position) > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_random_field_is_closed() {
+        // a noisy field exercises every corner pattern, including the ambiguous faces
+        let mut rng = StdRng::seed_from_u64(11);
+        let cube = grid(12, |p| {
+            if p.iter().any(|c| c.abs() > 1.9) {
+                -1.0
+            } else {
+                rng.gen_range(-1.0..1.0)
+            }
+        });
+        assert_closed(&marching_cubes(&cube, 0.0));
+    }
+
+    #[test]
+    fn test_enclosed_level() {
+        let densities = [4.0, 3.0, 2.0, 1.0];
+        assert_eq!(enclosed_level(&densities, 0.5), 3.0);
+        assert_eq!(enclosed_level(&densities, 0.75), 2.0);
+        assert_eq!(enclosed_level(&densities, 1.0), 1.0);
+    }
+
+    // 1s: P(r < R) = 1 - exp(-2R) (1 + 2R + 2R^2), so the 90% surface sits at R = 2.661
+    // and the density there is exp(-2R) / pi
+    fn hydrogen_1s_threshold() -> f64 {
+        (-2.0 * 2.66116_f64).exp() / std::f64::consts::PI
+    }
+
+    #[test]
+    fn test_grid_threshold_matches_analytic() {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let threshold = density_threshold(&orbital, 0.9, 64);
+        let exact = hydrogen_1s_threshold();
+
+        assert!(
+            (threshold / exact - 1.0).abs() < 0.05,
+            "{} vs {}",
+            threshold,
+            exact
+        );
+    }
+
+    #[test]
+    fn test_particle_threshold_matches_analytic() {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let particles = physics::Sampler::with_seed(5).generate(&orbital, 20_000);
+        let threshold = particle_threshold(&particles, 0.9);
+        let exact = hydrogen_1s_threshold();
+        assert!(
+            (threshold / exact - 1.0).abs() < 0.1,
+            "{} vs {}",
+            threshold,
+            exact
+        );
+
+        // radii come from a tabulated cdf, so a few particles tie with the threshold
+        let kept = cull_particles(&particles, threshold).len();
+        assert!((18_000..18_100).contains(&kept), "{}", kept);
+    }
+
+    #[test]
+    fn test_real_orbital_has_both_lobes() {
+        let orbital = Orbital::from_label("2px").unwrap();
+        let mesh = orbital_isosurface(&orbital, IsoLevel::Enclosed(0.9), 40);
+        let positive = physics::sign_color(0.0, 1.0);
+
+        let (plus, minus): (Vec<&MeshVertex>, Vec<&MeshVertex>) =
+            mesh.vertices.iter().partition(|v| v.color == positive);
+        assert!(!plus.is_empty() && !minus.is_empty());
+        // positive lobe along +x, negative along -x
+        assert!(plus.iter().all(|v| v.position.x > 0.0));
+        assert!(minus.iter().all(|v| v.position.x < 0.0));
+    }
+}
@@ -0,0 +1,22 @@
+// electron cloud library
+// physics (orbitals and monte carlo sampling), geometry and camera math are usable headlessly,
+// the wgpu/winit viewer binary is built only with the "viewer" feature (on by default)
+
+pub mod camera;
+pub mod cube;
+pub mod dirac;
+pub mod export;
+pub mod geometry;
+pub mod hybrid;
+pub mod isosurface;
+pub mod mcmc;
+pub mod molecule;
+pub mod momentum;
+pub mod physics;
+pub mod plot;
+pub mod properties;
+pub mod slater;
+pub mod spin;
+pub mod stark;
+pub mod superposition;
+pub mod transitions;
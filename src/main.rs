@@ -1,6 +1,8 @@
 use nalgebra_glm as glm; //OpenGL-style math library
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::sync::{Arc, Mutex}; // Shared mutable state (camera)
+use std::time::Instant;
 use wgpu::util::DeviceExt; //talks to gpu, i have no clue how this works all hail the mighty AI
 use winit::{
     //window + input system
@@ -10,12 +12,26 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
-mod camera;
-mod geometry; //will try to make i gradient version of it
-mod physics; //this could be better i guess
+mod scene;
 mod texture;
 
-use camera::Camera;
+use atom_simulator::camera::Camera;
+use atom_simulator::cube::CubeFile;
+use atom_simulator::dirac::DiracState;
+use atom_simulator::export::{self, ExportFormat};
+use atom_simulator::geometry; //will try to make i gradient version of it
+use atom_simulator::hybrid::{self, HybridSet};
+use atom_simulator::isosurface::{self, IsoLevel, Mesh};
+use atom_simulator::molecule::MolecularOrbital;
+use atom_simulator::momentum::MomentumOrbital;
+use atom_simulator::physics; //this could be better i guess
+use atom_simulator::plot::{Figure, PlotFormat};
+use atom_simulator::slater::{self, Atom};
+use atom_simulator::spin::{self, SpinOrbital};
+use atom_simulator::stark::{self, StarkState};
+use atom_simulator::superposition::Superposition;
+use atom_simulator::transitions;
+use scene::{AtomView, Marker, Scene};
 
 #[repr(C)] //just like C,coz gpu is a bitch need to be fed binary
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -45,8 +61,29 @@ struct InstanceRaw {
     //coz me no job and science have no money so is PhD, me have old cpu and shitty intgrated gpu cant afford 100,000 seprate spheres
     position: [f32; 3],
     color: [f32; 4],
+    // sphere radius relative to a particle, larger for atom markers
+    scale: f32,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct MeshVertexRaw {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 4],
+}
+
+// what gets drawn: the particle cloud, the isosurface, or both
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DisplayMode {
+    Particles,
+    Both,
+    Surface,
+}
+
+// isosurface grid resolution for analytic orbitals, points per axis
+const SURFACE_GRID: usize = 64;
+
 struct State<'a> {
     //defining state and its lifetime parametes
     surface: wgpu::Surface<'a>,
@@ -63,7 +100,25 @@ struct State<'a> {
     sphere_vertex_buffer: wgpu::Buffer, //ai is behind all this
     num_sphere_vertices: u32,
     instance_buffer: wgpu::Buffer,
+    // instances the buffer holds room for; it only grows
+    instance_capacity: usize,
     num_instances: u32,
+    num_particles: usize,
+    scene: Scene,
+    sampler: physics::Sampler,
+    particles: Vec<physics::Particle>,
+    mesh_pipeline: wgpu::RenderPipeline,
+    mesh_buffer: Option<wgpu::Buffer>,
+    num_mesh_vertices: u32,
+    display_mode: DisplayMode,
+    // probability enclosed by the isosurface, and kept by particle culling
+    enclosed_fraction: f64,
+    // density at the enclosed_fraction surface, estimated from the current particles
+    threshold: f64,
+    cull: bool,
+    // superpositions evolve in time while playing
+    playing: bool,
+    last_frame: Instant,
     depth_view: wgpu::TextureView,
 }
 
@@ -71,7 +126,7 @@ impl<'a> State<'a> {
     //i hoped that ai would do this part own its own, but it sure like to
     //hallucinate when things gets actually tough
     //its been 15 days i havent able to make it work
-    async fn new(window: &'a Window, num_particles: usize) -> Self {
+    async fn new(window: &'a Window, mut scene: Scene, num_particles: usize) -> Self {
         let size = window.inner_size();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -184,7 +239,7 @@ impl<'a> State<'a> {
                     wgpu::VertexBufferLayout {
                         array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
                         step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![1 => Float32x3, 2 => Float32x4],
+                        attributes: &wgpu::vertex_attr_array![1 => Float32x3, 2 => Float32x4, 3 => Float32],
                     },
                 ],
             },
@@ -220,6 +275,7 @@ impl<'a> State<'a> {
             },
             multiview: None,
         });
+        let mesh_pipeline = create_mesh_pipeline(&device, &render_pipeline_layout, config.format);
         //man fuck this shit if youre reading this FUCK YOU TOOOOOOOOOO
         let sphere_vertices_glm = geometry::generate_sphere(1.0, 10, 10);
         let sphere_vertices: Vec<[f32; 3]> = sphere_vertices_glm
@@ -234,26 +290,17 @@ impl<'a> State<'a> {
         let num_sphere_vertices = sphere_vertices.len() as u32;
 
         println!("\nGenerating particle set...");
-        let particles = physics::generate_particles(num_particles);
+        let mut sampler = physics::Sampler::new();
+        let particles = scene.sample(&mut sampler, num_particles);
         println!("Done.");
+        scene.print_summary();
+        scene.print_properties(&particles);
 
-        let instance_data = particles
-            .iter()
-            .map(|p| InstanceRaw {
-                position: [
-                    p.position.x as f32,
-                    p.position.y as f32,
-                    p.position.z as f32,
-                ],
-                color: [p.color.x, p.color.y, p.color.z, p.color.w],
-            })
-            .collect::<Vec<_>>();
-
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let instances = instance_data(&scene, &particles, &scene.markers());
+        let instance_capacity = instances.len().max(1);
+        let instance_buffer = create_instance_buffer(&device, instance_capacity);
+        queue.write_buffer(&instance_buffer, 0, bytemuck::cast_slice(&instances));
+        let threshold = isosurface::particle_threshold(&particles, 0.9);
 
         Self {
             window,
@@ -270,7 +317,21 @@ impl<'a> State<'a> {
             sphere_vertex_buffer,
             num_sphere_vertices,
             instance_buffer,
-            num_instances: particles.len() as u32,
+            instance_capacity,
+            num_instances: instances.len() as u32,
+            num_particles,
+            scene,
+            sampler,
+            particles,
+            mesh_pipeline,
+            mesh_buffer: None,
+            num_mesh_vertices: 0,
+            display_mode: DisplayMode::Particles,
+            enclosed_fraction: 0.9,
+            threshold,
+            cull: false,
+            playing: true,
+            last_frame: Instant::now(),
             depth_view,
         }
     }
@@ -290,6 +351,294 @@ impl<'a> State<'a> {
         }
     }
 
+    // validates the requested orbital, then resamples and re-uploads the instances
+    // invalid requests are rejected with the same messages as the startup prompt
+    fn set_orbital(&mut self, n: i32, l: i32, m: i32) {
+        let Some(mut orbital) = self.scene.orbital() else {
+            return;
+        };
+        match physics::QuantumNumbers::new(n, l, m) {
+            Ok(qn) => orbital.quantum_numbers = qn,
+            Err(e) => {
+                println!("\nError: {}", e);
+                return;
+            }
+        }
+        self.scene.replace_orbital(orbital);
+        self.resample();
+    }
+
+    fn resample(&mut self) {
+        match &self.scene {
+            Scene::Orbital(orbital) => {
+                let qn = orbital.quantum_numbers;
+                println!(
+                    "\nGenerating particle set for n={}, l={}, m={}...",
+                    qn.n(),
+                    qn.l(),
+                    qn.m()
+                );
+            }
+            Scene::Momentum(mo) => {
+                let qn = mo.orbital().quantum_numbers;
+                println!(
+                    "\nGenerating momentum-space particle set for n={}, l={}, m={}...",
+                    qn.n(),
+                    qn.l(),
+                    qn.m()
+                );
+            }
+            Scene::Superposition { state, .. } => {
+                println!("\nGenerating particle set for {}...", state.label())
+            }
+            Scene::Hybrid { set, index, .. } => {
+                println!("\nGenerating particle set for {}...", set.label(*index))
+            }
+            Scene::Molecule(mo) => {
+                println!("\nGenerating particle set for {}...", mo.label())
+            }
+            Scene::Atom { .. } => {
+                println!(
+                    "\nGenerating particle set for {}...",
+                    self.scene.file_stem()
+                )
+            }
+            Scene::Stark(state) => {
+                println!("\nGenerating particle set for Stark {}...", state.label())
+            }
+            Scene::Spin(state) => {
+                println!("\nGenerating particle set for {}...", state.label())
+            }
+            Scene::Dirac(state) => {
+                println!("\nGenerating particle sets for Dirac {}...", state.label())
+            }
+            Scene::Cube { name, .. } => println!("\nGenerating particle set for {}...", name),
+        }
+        let particles = self.scene.sample(&mut self.sampler, self.num_particles);
+        println!("Done.");
+        self.scene.print_summary();
+        self.scene.print_properties(&particles);
+
+        self.particles = particles;
+        self.window.set_title(&self.scene.title());
+        self.update_threshold();
+    }
+
+    // re-derives the enclosed-probability density from the particles, which moves both
+    // the isosurface and the culling cut
+    fn update_threshold(&mut self) {
+        self.threshold = isosurface::particle_threshold(&self.particles, self.enclosed_fraction);
+        println!(
+            "{:.0}% of the probability lies where the density is above {:.4e} bohr^-3",
+            self.enclosed_fraction * 100.0,
+            self.threshold
+        );
+        self.upload_particles();
+        self.rebuild_surface();
+    }
+
+    fn upload_particles(&mut self) {
+        let culled;
+        let particles = if self.cull {
+            culled = isosurface::cull_particles(&self.particles, self.threshold);
+            &culled
+        } else {
+            &self.particles
+        };
+
+        // animated scenes upload every frame, so the buffer is refilled in place and only
+        // reallocated when the cloud outgrows it
+        let instances = instance_data(&self.scene, particles, &self.scene.markers());
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len();
+            self.instance_buffer = create_instance_buffer(&self.device, self.instance_capacity);
+        }
+        self.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        self.num_instances = instances.len() as u32;
+    }
+
+    // re-extracts the isosurface when it is on screen, drops it otherwise
+    // animated scenes only show their surface while paused
+    fn rebuild_surface(&mut self) {
+        let animating = self.playing && self.scene.is_animated();
+        if self.display_mode == DisplayMode::Particles || animating {
+            if animating && self.display_mode != DisplayMode::Particles {
+                println!("\nThe isosurface is shown while the animation is paused (space).");
+            }
+            self.mesh_buffer = None;
+            self.num_mesh_vertices = 0;
+            return;
+        }
+
+        println!(
+            "\nExtracting {:.0}% isosurface...",
+            self.enclosed_fraction * 100.0
+        );
+        let mesh = self
+            .scene
+            .isosurface(IsoLevel::Density(self.threshold), SURFACE_GRID);
+        println!("Done, {} triangles.", mesh.triangle_count());
+
+        self.num_mesh_vertices = mesh.vertices.len() as u32;
+        self.mesh_buffer = (!mesh.vertices.is_empty())
+            .then(|| create_mesh_buffer(&self.device, &self.scene, &mesh));
+    }
+
+    // writes the current cloud next to the executable's working directory
+    fn export(&self, format: ExportFormat) {
+        let path = format!("cloud_{}.{}", self.scene.file_stem(), format.extension());
+        let comment = self.scene.description();
+
+        let result = File::create(&path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            export::write_particles(&mut out, &self.particles, format, &comment)?;
+            out.flush()
+        });
+
+        match result {
+            Ok(()) => println!("\nExported {} particles to {}", self.particles.len(), path),
+            Err(e) => println!("\nError: could not write {}: {}", path, e),
+        }
+    }
+
+    // radial and angular distribution plots of an orbital over histograms of the cloud,
+    // written as both svg and png
+    fn plot(&mut self) {
+        let Scene::Orbital(orbital) = &self.scene else {
+            println!("\nPlots are only available for single orbitals");
+            return;
+        };
+        let figure = Figure::orbital(orbital, &self.particles, &mut self.sampler);
+        for format in [PlotFormat::Svg, PlotFormat::Png] {
+            let path = format!("plot_{}.{}", self.scene.file_stem(), format.extension());
+            let result = File::create(&path).and_then(|file| {
+                let mut out = BufWriter::new(file);
+                figure.write(&mut out, format)?;
+                out.flush()
+            });
+            match result {
+                Ok(()) => println!("\nWrote {}", path),
+                Err(e) => println!("\nError: could not write {}: {}", path, e),
+            }
+        }
+    }
+
+    // the bindings listed under Controls in the readme; a scene ignores keys that do not
+    // apply to it, e.g. the n, l and m keys outside the eigenstate pictures
+    fn orbital_key(&mut self, key: &Key) {
+        match key {
+            Key::Named(NamedKey::Space) if self.scene.is_animated() => {
+                self.playing = !self.playing;
+                if !self.playing {
+                    println!();
+                    self.scene.print_summary();
+                }
+                self.rebuild_surface();
+                return;
+            }
+            Key::Character(c) if c.as_str() == "i" => {
+                self.display_mode = match self.display_mode {
+                    DisplayMode::Particles => DisplayMode::Both,
+                    DisplayMode::Both => DisplayMode::Surface,
+                    DisplayMode::Surface => DisplayMode::Particles,
+                };
+                self.rebuild_surface();
+                return;
+            }
+            Key::Character(c) if c.as_str() == "=" || c.as_str() == "-" => {
+                let step = if c.as_str() == "=" { 0.05 } else { -0.05 };
+                let fraction = (self.enclosed_fraction + step).clamp(0.05, 0.95);
+                if (fraction - self.enclosed_fraction).abs() > 1e-9 {
+                    self.enclosed_fraction = fraction;
+                    self.update_threshold();
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "k" => {
+                self.cull = !self.cull;
+                self.upload_particles();
+                return;
+            }
+            Key::Character(c) if c.as_str() == "c" => {
+                self.sampler.color_mode = match self.sampler.color_mode {
+                    physics::ColorMode::Density => physics::ColorMode::Phase,
+                    physics::ColorMode::Phase => physics::ColorMode::Density,
+                };
+                self.resample();
+                return;
+            }
+            Key::Character(c) if c.as_str() == "p" => return self.export(ExportFormat::PlyBinary),
+            Key::Character(c) if c.as_str() == "x" => return self.export(ExportFormat::Csv),
+            Key::Character(c) if c.as_str() == "g" => return self.plot(),
+            Key::Character(c) if c.as_str() == "t" => {
+                if let Some(orbital) = self.scene.orbital() {
+                    println!();
+                    for line in transitions::decay_report(&orbital) {
+                        println!("{}", line);
+                    }
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "m" => {
+                if self.scene.toggle_momentum() {
+                    self.resample();
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "h" => {
+                if self.scene.next_hybrid() {
+                    self.resample();
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "v" => {
+                if self.scene.next_atom_view() {
+                    self.resample();
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "j" => {
+                if self.scene.other_j() {
+                    self.resample();
+                }
+                return;
+            }
+            Key::Character(c) if c.as_str() == "]" || c.as_str() == "[" => {
+                let delta = if c.as_str() == "]" { 1 } else { -1 };
+                if self.scene.step_sublevel(delta) {
+                    self.resample();
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        let Some(orbital) = self.scene.orbital() else {
+            return;
+        };
+        let qn = orbital.quantum_numbers;
+        let (n, l, m) = (qn.n(), qn.l(), qn.m());
+
+        match key {
+            Key::Named(NamedKey::ArrowUp) => self.set_orbital(n + 1, l, m),
+            Key::Named(NamedKey::ArrowDown) => self.set_orbital(n - 1, l, m),
+            Key::Named(NamedKey::ArrowRight) => self.set_orbital(n, l + 1, m),
+            Key::Named(NamedKey::ArrowLeft) => self.set_orbital(n, l - 1, m),
+            Key::Character(c) if c.as_str() == "]" => self.set_orbital(n, l, m + 1),
+            Key::Character(c) if c.as_str() == "[" => self.set_orbital(n, l, m - 1),
+            Key::Character(c) if c.as_str() == "r" => {
+                let harmonic = match orbital.harmonic {
+                    physics::Harmonic::Complex => physics::Harmonic::Real,
+                    physics::Harmonic::Real => physics::Harmonic::Complex,
+                };
+                self.scene.replace_orbital(orbital.with_harmonic(harmonic));
+                self.resample();
+            }
+            _ => {}
+        }
+    }
+
     fn input(&mut self, event: &WindowEvent) -> bool {
         match event {
             WindowEvent::KeyboardInput {
@@ -298,6 +647,10 @@ impl<'a> State<'a> {
                 if key_event.logical_key == Key::Named(NamedKey::Escape) {
                     return true;
                 }
+                // ignore auto-repeat so holding a key does not resample every frame
+                if !key_event.repeat {
+                    self.orbital_key(&key_event.logical_key);
+                }
             }
             WindowEvent::MouseWheel { delta, .. } => {
                 let y_offset = match delta {
@@ -324,6 +677,17 @@ impl<'a> State<'a> {
     }
 
     fn update(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f64();
+        self.last_frame = now;
+        if self.playing {
+            if let Some(particles) = self.scene.advance(dt, self.sampler.color_mode) {
+                self.particles = particles;
+                self.upload_particles();
+                self.window.set_title(&self.scene.title());
+            }
+        }
+
         //this mfking mut, forgetting to mutate this everytime!!!!!
         let projection = glm::perspective_zo(
             self.size.width as f32 / self.size.height as f32,
@@ -379,11 +743,20 @@ impl<'a> State<'a> {
                 occlusion_query_set: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.sphere_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
-            render_pass.draw(0..self.num_sphere_vertices, 0..self.num_instances);
+
+            if self.display_mode != DisplayMode::Surface {
+                render_pass.set_pipeline(&self.render_pipeline);
+                render_pass.set_vertex_buffer(0, self.sphere_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+                render_pass.draw(0..self.num_sphere_vertices, 0..self.num_instances);
+            }
+
+            if let Some(mesh_buffer) = &self.mesh_buffer {
+                render_pass.set_pipeline(&self.mesh_pipeline);
+                render_pass.set_vertex_buffer(0, mesh_buffer.slice(..));
+                render_pass.draw(0..self.num_mesh_vertices, 0..1);
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -395,6 +768,123 @@ impl<'a> State<'a> {
 
 // finally it worked, imma sleep for now
 
+// packs sampled particles and scene markers into the per-instance layout expected by the shader
+// positions are drawn relative to the scene frame: orbitals in units of the species length
+// scale a0 (m_e / mu) / Z, so muonic hydrogen and positronium fill the view just like
+// ordinary hydrogen, cube files in bohr around the centre of their grid
+fn instance_data(
+    scene: &Scene,
+    particles: &[physics::Particle],
+    markers: &[Marker],
+) -> Vec<InstanceRaw> {
+    let (center, scale) = scene.frame();
+    let to_display = |p: &glm::DVec3| {
+        let d = (p - center) / scale;
+        [d.x as f32, d.y as f32, d.z as f32]
+    };
+
+    particles
+        .iter()
+        .map(|p| InstanceRaw {
+            position: to_display(&p.position),
+            color: [p.color.x, p.color.y, p.color.z, p.color.w],
+            scale: 1.0,
+        })
+        .chain(markers.iter().map(|m| InstanceRaw {
+            position: to_display(&m.position),
+            color: [m.color.x, m.color.y, m.color.z, m.color.w],
+            scale: m.size,
+        }))
+        .collect()
+}
+
+// instance buffer with room for `capacity` instances, filled with queue.write_buffer
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+// isosurface pipeline: plain triangle list with per-vertex normals and a directional light
+fn create_mesh_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("mesh.wgsl"));
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Mesh Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<MeshVertexRaw>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x4],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+// uploads an isosurface in the same display frame as the particles
+fn create_mesh_buffer(device: &wgpu::Device, scene: &Scene, mesh: &Mesh) -> wgpu::Buffer {
+    let (center, scale) = scene.frame();
+    let vertex_data = mesh
+        .vertices
+        .iter()
+        .map(|v| {
+            let p = (v.position - center) / scale;
+            MeshVertexRaw {
+                position: [p.x as f32, p.y as f32, p.z as f32],
+                normal: [v.normal.x as f32, v.normal.y as f32, v.normal.z as f32],
+                color: [v.color.x, v.color.y, v.color.z, v.color.w],
+            }
+        })
+        .collect::<Vec<_>>();
+
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Mesh Buffer"),
+        contents: bytemuck::cast_slice(&vertex_data),
+        usage: wgpu::BufferUsages::VERTEX,
+    })
+}
+
 fn get_quantum_number(prompt: &str, default: i32) -> i32 {
     //simple input prompt
     loop {
@@ -419,6 +909,179 @@ fn get_quantum_number(prompt: &str, default: i32) -> i32 {
     }
 }
 
+// orbital picked by label at the startup prompt, before the species is chosen
+enum Selection {
+    Orbital(physics::Orbital),
+    Hybrid(HybridSet, usize),
+    Atom(Atom, AtomView),
+    // n, k, m of a parabolic state, field along z
+    Stark(i32, i32, i32),
+    // validated for hydrogen, the species is applied afterwards
+    Spin(SpinOrbital),
+    // carries its own nuclear charge
+    Dirac(DiracState),
+}
+
+impl Selection {
+    // hydrogen-like choices ask for the species, atoms carry their own nuclear charge
+    fn into_scene(self, species: impl FnOnce() -> physics::Species) -> Scene {
+        match self {
+            Selection::Orbital(orbital) => Scene::Orbital(orbital.with_species(species())),
+            Selection::Hybrid(set, index) => Scene::Hybrid {
+                set,
+                index,
+                state: set.hybrid(index, species()),
+            },
+            Selection::Atom(atom, view) => Scene::Atom { atom, view },
+            Selection::Stark(n, k, m) => {
+                Scene::Stark(StarkState::new(n, k, m, species()).expect("validated at the prompt"))
+            }
+            Selection::Spin(state) => Scene::Spin(state.with_species(species())),
+            Selection::Dirac(state) => Scene::Dirac(state),
+        }
+    }
+}
+
+// "C", "C 2px", "O valence" or "Ne all": an element filled by aufbau and the slater-type
+// orbital, valence density or whole-configuration density to draw, valence when no orbital
+// is given
+fn parse_atom_selection(text: &str) -> Result<Selection, String> {
+    let mut words = text.split_whitespace();
+    let z = slater::parse_element(words.next().unwrap_or_default())?;
+    let atom = Atom::aufbau(z)?;
+    let view = match words.next() {
+        None | Some("valence") => AtomView::Valence,
+        Some("all") => AtomView::Configuration,
+        Some(label) => {
+            let orbital = atom.orbital_from_label(label)?;
+            if atom.electrons_in(orbital.subshell) == 0 {
+                println!(
+                    "Note: {} is empty in {}, its Z_eff is for one added electron.",
+                    orbital.subshell.label(),
+                    atom.configuration()
+                );
+            }
+            AtomView::Orbital(orbital)
+        }
+    };
+    match words.next() {
+        None => Ok(Selection::Atom(atom, view)),
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+    }
+}
+
+// optional shortcut for real, hybrid and slater-type orbitals, empty input falls back to the
+// n, l, m prompts
+fn get_orbital_label() -> Option<Selection> {
+    loop {
+        print!(
+            "Orbital label, e.g. 2px, 3dxy, sp3, sp3d2:4, an element such as O, C 2px or Ne all, \
+             a configuration such as [Ne] 3s1, a stark state such as stark 2,1,0, a j state \
+             such as 2p3/2,-1/2, or a dirac state such as dirac 1s1/2 92 (Enter to type n, l, m): "
+        );
+        io::stdout().flush().unwrap();
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        if let Some(numbers) = trimmed.to_lowercase().strip_prefix("stark") {
+            match StarkState::parse(numbers, physics::HYDROGEN) {
+                Ok(state) => return Some(Selection::Stark(state.n(), state.k(), state.m())),
+                Err(e) => println!("Invalid Stark state: {}.", e),
+            }
+            continue;
+        }
+
+        // "dirac <state> [Z]", hydrogen when Z is left out
+        if let Some(rest) = trimmed.to_lowercase().strip_prefix("dirac") {
+            let mut words = rest.split_whitespace();
+            let state = words.next().unwrap_or_default();
+            let parsed = match words.next().map(str::parse::<i32>) {
+                None => DiracState::parse(state, 1),
+                Some(Ok(z)) => DiracState::parse(state, z),
+                Some(Err(_)) => Err("Z must be an integer".to_string()),
+            };
+            match parsed {
+                Ok(state) => return Some(Selection::Dirac(state)),
+                Err(e) => println!("Invalid Dirac state: {}.", e),
+            }
+            continue;
+        }
+
+        // j labels such as 2p3/2 carry a fraction
+        if trimmed.contains('/') {
+            match SpinOrbital::parse(trimmed, physics::HYDROGEN) {
+                Ok(state) => return Some(Selection::Spin(state)),
+                Err(e) => println!("Invalid j state: {}.", e),
+            }
+            continue;
+        }
+
+        if trimmed.to_lowercase().starts_with("sp") {
+            match hybrid::parse_hybrid_label(trimmed) {
+                Ok((set, index)) => return Some(Selection::Hybrid(set, index)),
+                Err(e) => println!("Invalid label: {}.", e),
+            }
+            continue;
+        }
+
+        if trimmed.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            match parse_atom_selection(trimmed) {
+                Ok(selection) => return Some(selection),
+                Err(e) => println!("Invalid element: {}.", e),
+            }
+            continue;
+        }
+
+        let error = match physics::Orbital::from_label(trimmed) {
+            Ok(orbital) => return Some(Selection::Orbital(orbital)),
+            Err(e) => e,
+        };
+        // "1s2 2s1" or "[Ne] 3s1" are configurations of the neutral atom they fill
+        match Atom::with_configuration(None, trimmed) {
+            Ok(atom) => return Some(Selection::Atom(atom, AtomView::Configuration)),
+            Err(_) => println!("Invalid label: {}.", error),
+        }
+    }
+}
+
+fn get_species() -> physics::Species {
+    loop {
+        println!("\nSelect hydrogen-like system:");
+        for (i, species) in physics::SPECIES_PRESETS.iter().enumerate() {
+            println!("  {}. {}", i + 1, species.name);
+        }
+        print!("Enter choice (default: 1): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        let trimmed = input.trim();
+
+        if trimmed.is_empty() {
+            return physics::SPECIES_PRESETS[0];
+        }
+
+        match trimmed.parse::<usize>() {
+            Ok(c) if (1..=physics::SPECIES_PRESETS.len()).contains(&c) => {
+                return physics::SPECIES_PRESETS[c - 1]
+            }
+            _ => println!(
+                "\nInvalid choice. Please enter a number from 1 to {}.",
+                physics::SPECIES_PRESETS.len()
+            ),
+        }
+    }
+}
+
 // in need of function which ask for custom number of particle 5s orbital is almost invisible
 
 fn get_particle_count() -> usize {
@@ -473,45 +1136,213 @@ fn get_particle_count() -> usize {
     }
 }
 
-pub fn main() {
-    //TODO---maybe get some tkinter-type dialogue box
-    env_logger::init();
-    println!("Enter initial quantum numbers for the simulation.");
-    let (n, l, m) = loop {
-        let n = get_quantum_number("Principal quantum number (n)", 2);
-        let l = get_quantum_number("Azimuthal quantum number (l)", 1);
-        let m = get_quantum_number("Magnetic quantum number (m)", 0);
-
-        if n <= 0 {
-            println!("\nError: Principal quantum number (n) must be positive.");
-            continue;
+// command line scenes: `--cube <path>` views a cube file and `--superposition <state>`
+// animates a superposition such as "1s + 2pz"; without arguments the orbital is prompted for
+fn scene_from_args() -> Option<Scene> {
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next()) {
+        (Some("--cube"), Some(path)) => match CubeFile::open(&path) {
+            Ok(cube) => {
+                let name = std::path::Path::new(&path)
+                    .file_stem()
+                    .map_or_else(|| "cube".to_string(), |s| s.to_string_lossy().into_owned());
+                println!("Loaded {}", path);
+                Some(Scene::Cube { name, cube })
+            }
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        (Some("--superposition"), Some(text)) => match Superposition::parse(&text, get_species()) {
+            Ok(state) => Some(Scene::Superposition {
+                state,
+                ensemble: None,
+                time: 0.0,
+            }),
+            Err(e) => {
+                eprintln!("Error: invalid superposition: {}", e);
+                std::process::exit(2);
+            }
+        },
+        (Some("--momentum"), Some(label)) => match physics::Orbital::from_label(&label) {
+            Ok(orbital) => Some(Scene::Momentum(MomentumOrbital::new(
+                orbital.with_species(get_species()),
+            ))),
+            Err(e) => {
+                eprintln!("Error: invalid orbital: {}", e);
+                std::process::exit(2);
+            }
+        },
+        (Some("--hybrid"), Some(label)) => match hybrid::parse_hybrid_label(&label) {
+            Ok((set, index)) => Some(Selection::Hybrid(set, index).into_scene(get_species)),
+            Err(e) => {
+                eprintln!("Error: invalid hybrid: {}", e);
+                std::process::exit(2);
+            }
+        },
+        (Some("--molecule"), Some(text)) => {
+            let bond_length = match (args.next().as_deref(), args.next()) {
+                (None, _) => None,
+                (Some("--bond"), Some(value)) => match value.parse::<f64>() {
+                    Ok(r) => Some(r),
+                    Err(_) => {
+                        eprintln!("Error: invalid bond length '{}'", value);
+                        std::process::exit(2);
+                    }
+                },
+                _ => usage(),
+            };
+            match MolecularOrbital::from_spec(&text, bond_length, get_species()) {
+                Ok(mo) => Some(Scene::Molecule(mo)),
+                Err(e) => {
+                    eprintln!("Error: invalid molecular orbital: {}", e);
+                    std::process::exit(2);
+                }
+            }
         }
-        if l < 0 || l >= n {
-            println!("\nError: Azimuthal quantum number (l) must be in the range [0, n-1].");
-            continue;
+        (Some("--element"), Some(element)) => {
+            let text = match (args.next().as_deref(), args.next()) {
+                (None, _) => element,
+                (Some("--orbital"), Some(label)) => format!("{} {}", element, label),
+                (Some("--configuration"), Some(text)) => {
+                    let z = slater::parse_element(&element).unwrap_or_else(|e| {
+                        eprintln!("Error: invalid element: {}", e);
+                        std::process::exit(2);
+                    });
+                    return Some(configuration_scene(Some(z), &text));
+                }
+                _ => usage(),
+            };
+            match parse_atom_selection(&text) {
+                Ok(selection) => Some(selection.into_scene(get_species)),
+                Err(e) => {
+                    eprintln!("Error: invalid element: {}", e);
+                    std::process::exit(2);
+                }
+            }
         }
-        if m.abs() > l {
-            println!("\nError: Magnetic quantum number (m) must be in the range [-l, l].");
-            continue;
+        (Some("--configuration"), Some(text)) => Some(configuration_scene(None, &text)),
+        (Some("--spin"), Some(text)) => {
+            let field = match (args.next().as_deref(), args.next()) {
+                (None, _) => spin::DEFAULT_FIELD_T,
+                (Some("--bfield"), Some(value)) => value.parse::<f64>().unwrap_or_else(|_| {
+                    eprintln!("Error: invalid field strength '{}'", value);
+                    std::process::exit(2);
+                }),
+                _ => usage(),
+            };
+            match SpinOrbital::parse(&text, get_species()) {
+                Ok(state) => Some(Scene::Spin(state.with_field(field))),
+                Err(e) => {
+                    eprintln!("Error: invalid j state: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        (Some("--dirac"), Some(text)) => {
+            let z = match (args.next().as_deref(), args.next()) {
+                (None, _) => 1,
+                (Some("--z"), Some(value)) => value.parse::<i32>().unwrap_or_else(|_| {
+                    eprintln!("Error: invalid nuclear charge '{}'", value);
+                    std::process::exit(2);
+                }),
+                _ => usage(),
+            };
+            match DiracState::parse(&text, z) {
+                Ok(state) => Some(Scene::Dirac(state)),
+                Err(e) => {
+                    eprintln!("Error: invalid Dirac state: {}", e);
+                    std::process::exit(2);
+                }
+            }
         }
+        (Some("--stark"), Some(text)) => {
+            let mut field = stark::DEFAULT_FIELD;
+            let mut axis = glm::vec3(0.0, 0.0, 1.0);
+            while let Some(flag) = args.next() {
+                let value = args.next().unwrap_or_else(|| usage());
+                let parsed = match flag.as_str() {
+                    "--efield" => value
+                        .parse::<f64>()
+                        .map(|f| field = f)
+                        .map_err(|_| format!("invalid field strength '{}'", value)),
+                    "--axis" => stark::parse_axis(&value).map(|a| axis = a),
+                    _ => usage(),
+                };
+                if let Err(e) = parsed {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(2);
+                }
+            }
+            match StarkState::parse(&text, get_species()).and_then(|s| s.with_axis(axis)) {
+                Ok(state) => Some(Scene::Stark(state.with_field(field))),
+                Err(e) => {
+                    eprintln!("Error: invalid Stark state: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        (None, _) => None,
+        _ => usage(),
+    }
+}
 
-        break (n, l, m);
-    };
+// whole-configuration density of an atom given its electrons, the neutral atom when z is None
+fn configuration_scene(z: Option<i32>, text: &str) -> Scene {
+    match Atom::with_configuration(z, text) {
+        Ok(atom) => Scene::Atom {
+            atom,
+            view: AtomView::Configuration,
+        },
+        Err(e) => {
+            eprintln!("Error: invalid configuration: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: Atom_Simulator [--cube <path> | --momentum <orbital> | --superposition <state> \
+         | --hybrid <set[:k]> \
+         | --molecule <orbital> [--bond <R>] \
+         | --element <symbol> [--orbital <label> | --configuration <text>] \
+         | --configuration <text> | --stark <n,k,m> [--efield <F>] [--axis <x|y|z|x,y,z>] \
+         | --spin <nlj[,mj]> [--bfield <T>] | --dirac <nlj[,mj]|n,kappa,mj> [--z <Z>]]"
+    );
+    std::process::exit(2);
+}
+
+pub fn main() {
+    //TODO---maybe get some tkinter-type dialogue box
+    env_logger::init();
+    let scene = scene_from_args().unwrap_or_else(|| {
+        println!("Enter initial quantum numbers for the simulation.");
+        let selection = get_orbital_label().unwrap_or_else(|| loop {
+            let n = get_quantum_number("Principal quantum number (n)", 2);
+            let l = get_quantum_number("Azimuthal quantum number (l)", 1);
+            let m = get_quantum_number("Magnetic quantum number (m)", 0);
+
+            match physics::QuantumNumbers::new(n, l, m) {
+                Ok(qn) => break Selection::Orbital(physics::Orbital::new(qn)),
+                Err(e) => println!("\nError: {}", e),
+            }
+        });
 
-    *physics::N.lock().unwrap() = n;
-    *physics::L.lock().unwrap() = l;
-    *physics::M.lock().unwrap() = m;
+        selection.into_scene(get_species)
+    });
 
     let num_particles = get_particle_count();
 
     let event_loop = EventLoop::new().unwrap();
     let window = WindowBuilder::new()
-        .with_title("Atom Simulator")
+        .with_title(scene.title())
         .with_inner_size(winit::dpi::LogicalSize::new(1280, 720))
         .build(&event_loop)
         .unwrap();
 
-    let mut state = pollster::block_on(State::new(&window, num_particles));
+    let mut state = pollster::block_on(State::new(&window, scene, num_particles));
 
     event_loop
         .run(move |event, elwt| match event {
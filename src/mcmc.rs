@@ -0,0 +1,238 @@
+use crate::physics::{self, ColorMode, Particle, Sampler};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+use std::f64::consts::PI;
+
+// random-walk metropolis sampling of an arbitrary, unnormalized density over space
+// needs nothing but point evaluations, so it covers superpositions, hybrids, molecular
+// orbitals and interpolated grids that the separable inverse-cdf sampler cannot handle
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetropolisOptions {
+    // standard deviation of the gaussian proposal on each axis, in bohr
+    // the burn-in adapts it towards an acceptance rate of about one half
+    pub step: f64,
+    // steps discarded before the first sample, while the chain forgets its start
+    pub burn_in: usize,
+    // steps between kept samples, to thin out correlated neighbours
+    pub thinning: usize,
+    pub start: glm::DVec3,
+}
+
+impl MetropolisOptions {
+    // settings for a density spread over roughly `length` bohr
+    // the start sits off the axes and nodal planes that many orbitals share
+    pub fn for_length(length: f64) -> Self {
+        Self {
+            step: length,
+            burn_in: 2000,
+            thinning: 10,
+            start: glm::vec3(0.31, 0.57, 0.43) * length,
+        }
+    }
+}
+
+// samples kept from one chain, with the bookkeeping needed to judge it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MarkovChain {
+    pub positions: Vec<glm::DVec3>,
+    // density at each kept position, as returned by the closure
+    pub densities: Vec<f64>,
+    // step size after burn-in adaptation
+    pub step: f64,
+    // moves accepted and proposed after burn-in
+    pub accepted: usize,
+    pub proposed: usize,
+}
+
+impl MarkovChain {
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.proposed == 0 {
+            0.0
+        } else {
+            self.accepted as f64 / self.proposed as f64
+        }
+    }
+
+    // particles colored by density relative to the densest sample, like cube file clouds
+    // phase mode colors by psi where `psi` gives one: a real psi shows its sign like the
+    // real orbitals, a complex one its phase on the wheel; densities without a single
+    // wavefunction (None) keep the density colors
+    pub fn particles(
+        &self,
+        color_mode: ColorMode,
+        psi: impl Fn(&glm::DVec3) -> Option<Complex64>,
+    ) -> Vec<Particle> {
+        let max_density = self.densities.iter().copied().fold(0.0, f64::max);
+        self.positions
+            .iter()
+            .zip(&self.densities)
+            .map(|(position, density)| {
+                let relative = if max_density > 0.0 {
+                    density / max_density
+                } else {
+                    0.0
+                };
+                let intensity = (1000.0 * relative).ln_1p() / 1001.0f64.ln();
+                let phase = match color_mode {
+                    ColorMode::Phase => psi(position),
+                    ColorMode::Density => None,
+                };
+                let color = match phase {
+                    Some(psi) if psi.im == 0.0 => physics::sign_color(psi.arg().abs(), intensity),
+                    Some(psi) => physics::phase_wheel(psi.arg(), intensity),
+                    None => physics::heatmap_cool(intensity),
+                };
+                Particle {
+                    position: *position,
+                    color,
+                    density: *density,
+                }
+            })
+            .collect()
+    }
+}
+
+// runs one chain and keeps `count` samples of `density`, which need not be normalized
+// the walk uses symmetric gaussian moves, accepted with probability min(1, p_new / p_old)
+// during burn-in the step is scaled up after acceptances and down after rejections;
+// it is frozen afterwards, so the kept samples come from a proper markov chain
+pub fn metropolis(
+    sampler: &mut Sampler,
+    density: impl Fn(&glm::DVec3) -> f64,
+    count: usize,
+    options: &MetropolisOptions,
+) -> MarkovChain {
+    const TARGET_ACCEPTANCE: f64 = 0.5;
+
+    let rng = sampler.rng();
+    let mut position = options.start;
+    let mut current = density(&position);
+    let mut step = options.step;
+
+    let mut chain = MarkovChain {
+        positions: Vec::with_capacity(count),
+        densities: Vec::with_capacity(count),
+        ..Default::default()
+    };
+
+    let thinning = options.thinning.max(1);
+    let total = options.burn_in + count * thinning;
+    for i in 0..total {
+        let burning = i < options.burn_in;
+        let proposal = position + glm::vec3(gaussian(rng), gaussian(rng), gaussian(rng)) * step;
+        let proposed = density(&proposal);
+
+        // u * p_old < p_new, which also leaves a zero-density start at the first chance
+        let accept = rng.gen::<f64>() * current < proposed || current <= 0.0;
+        if accept {
+            position = proposal;
+            current = proposed;
+        }
+
+        if burning {
+            // robbins-monro style: the multipliers balance at the target acceptance
+            let rate = 0.02;
+            step *= if accept {
+                1.0 + rate * (1.0 - TARGET_ACCEPTANCE)
+            } else {
+                1.0 - rate * TARGET_ACCEPTANCE
+            };
+            continue;
+        }
+
+        chain.proposed += 1;
+        chain.accepted += usize::from(accept);
+        if (i - options.burn_in + 1).is_multiple_of(thinning) {
+            chain.positions.push(position);
+            chain.densities.push(current);
+        }
+    }
+
+    chain.step = step;
+    chain
+}
+
+// standard normal deviate by the box-muller transform
+fn gaussian(rng: &mut impl Rng) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{Orbital, QuantumNumbers};
+    use crate::superposition::Superposition;
+
+    #[test]
+    fn test_hydrogen_1s_mean_radius() {
+        let orbital = Orbital::new(QuantumNumbers::new(1, 0, 0).unwrap());
+        let mut sampler = Sampler::with_seed(4);
+        let options = MetropolisOptions::for_length(1.0);
+        let chain = metropolis(
+            &mut sampler,
+            |p| orbital.psi_at(p).norm_sqr(),
+            20_000,
+            &options,
+        );
+
+        assert_eq!(chain.positions.len(), 20_000);
+        assert_eq!(chain.proposed, 20_000 * options.thinning);
+        let rate = chain.acceptance_rate();
+        assert!((0.35..0.65).contains(&rate), "acceptance {}", rate);
+
+        // <r> = 3/2 bohr for 1s
+        let mean_r = chain.positions.iter().map(glm::length).sum::<f64>() / 20_000.0;
+        assert!((mean_r - 1.5).abs() < 0.05, "<r> = {}", mean_r);
+    }
+
+    #[test]
+    fn test_unnormalized_superposition() {
+        // scaling the density must not change the chain, only the reported densities
+        let state = Superposition::parse("1s + 2pz", physics::HYDROGEN).unwrap();
+        let options = MetropolisOptions::for_length(2.0);
+        let a = metropolis(
+            &mut Sampler::with_seed(1),
+            |p| state.density(p, 0.0),
+            20_000,
+            &options,
+        );
+        let b = metropolis(
+            &mut Sampler::with_seed(1),
+            |p| 7.0 * state.density(p, 0.0),
+            20_000,
+            &options,
+        );
+        assert_eq!(a.positions, b.positions);
+
+        // <z> = 0.745 at t = 0, along +y in the sampler frame
+        let mean_z = a.positions.iter().map(|p| p.y).sum::<f64>() / 20_000.0;
+        assert!((mean_z - 0.745).abs() < 0.1, "<z> = {}", mean_z);
+    }
+
+    #[test]
+    fn test_phase_colors() {
+        let orbital = Orbital::from_label("2pz").unwrap();
+        let options = MetropolisOptions::for_length(4.0);
+        let chain = metropolis(
+            &mut Sampler::with_seed(2),
+            |p| orbital.psi_at(p).norm_sqr(),
+            2000,
+            &options,
+        );
+
+        // the lobes of 2pz take opposite sign colors, warm above the nodal plane
+        let particles = chain.particles(ColorMode::Phase, |p| Some(orbital.psi_at(p)));
+        for p in &particles {
+            assert_eq!(p.color.x > p.color.z, p.position.y > 0.0);
+        }
+
+        // without a wavefunction the density colors stay
+        let plain = chain.particles(ColorMode::Phase, |_| None);
+        let density = chain.particles(ColorMode::Density, |p| Some(orbital.psi_at(p)));
+        assert_eq!(plain, density);
+    }
+}
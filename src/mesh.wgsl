@@ -0,0 +1,44 @@
+// lit pipeline for isosurface meshes
+// shares the camera uniform with the particle shader
+struct Camera {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+
+@vertex
+fn vs_main(
+    // surface vertex in display units
+    @location(0) position: vec3<f32>,
+
+    // outward unit normal from the field gradient
+    @location(1) normal: vec3<f32>,
+
+    // phase color of the lobe
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(position, 1.0);
+    out.normal = normal;
+    out.color = color;
+    return out;
+}
+
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // fixed directional light plus an ambient term, so lobe shapes read without particles
+    let light = normalize(vec3<f32>(0.4, 0.8, 0.6));
+    let diffuse = max(dot(normalize(in.normal), light), 0.0);
+    let shade = 0.25 + 0.75 * diffuse;
+    return vec4<f32>(in.color.rgb * shade, 1.0);
+}
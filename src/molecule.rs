@@ -0,0 +1,542 @@
+use crate::physics::{
+    self, spherical_to_cartesian, ColorMode, Harmonic, Orbital, Particle, Sampler, Species,
+};
+use crate::superposition::{format_terms, parse_coefficient, parse_state, split_terms};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+use std::f64::consts::PI;
+
+// molecular orbitals as linear combinations of hydrogenic orbitals on several nuclei (lcao)
+// psi(x) = sum_i c_i phi_i(x - R_i), with every centre carrying the species' nuclear charge
+// the atomic functions on different centres are not orthogonal, so the norm needs the
+// overlap matrix S_ij = <phi_i|phi_j>, integrated by quadrature at construction
+// positions are in the sampler frame, orbital z along +y, as for single-centre orbitals
+
+// quadrature points for the overlap integrals: per spheroidal coordinate u and t, and
+// around the bond, where 16 points are exact up to l = 7 on both centres
+const OVERLAP_NODES: usize = 64;
+const OVERLAP_PHI_NODES: usize = 16;
+
+// one atomic orbital of the expansion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtomicTerm {
+    pub coefficient: Complex64,
+    pub orbital: Orbital,
+    // nucleus the orbital is centred on, bohr
+    pub centre: glm::DVec3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Nucleus {
+    pub position: glm::DVec3,
+    pub z: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MolecularOrbital {
+    name: Option<String>,
+    terms: Vec<AtomicTerm>,
+    overlap: Vec<Vec<Complex64>>,
+    // sum_ij conj(c_i) c_j S_ij, the squared norm of the unnormalized expansion
+    norm_sqr: f64,
+}
+
+// homonuclear diatomic combinations along the orbital z axis, nucleus A at -R/2 and B at +R/2
+// (name, atomic orbital, sign of the B coefficient)
+// pz on A points at B while pz on B points away from A, so sigma2p bonds with opposite signs
+const DIATOMIC_PRESETS: [(&str, &str, f64); 8] = [
+    ("sigma1s", "s", 1.0),
+    ("sigma*1s", "s", -1.0),
+    ("sigma2s", "s", 1.0),
+    ("sigma*2s", "s", -1.0),
+    ("sigma2p", "pz", -1.0),
+    ("sigma*2p", "pz", 1.0),
+    ("pi2p", "px", 1.0),
+    ("pi*2p", "px", -1.0),
+];
+
+impl MolecularOrbital {
+    pub fn new(terms: Vec<AtomicTerm>) -> Result<Self, String> {
+        let species = terms
+            .first()
+            .ok_or("empty molecular orbital")?
+            .orbital
+            .species;
+
+        for (i, a) in terms.iter().enumerate() {
+            if a.orbital.species != species {
+                return Err("all centres must belong to the same species".to_string());
+            }
+            for b in &terms[..i] {
+                if a.orbital == b.orbital && same_point(&a.centre, &b.centre) {
+                    return Err("an orbital appears twice on the same centre".to_string());
+                }
+            }
+        }
+
+        let overlap = overlap_matrix(&terms);
+        let mut norm_sqr = 0.0;
+        for (i, a) in terms.iter().enumerate() {
+            for (j, b) in terms.iter().enumerate() {
+                norm_sqr += (a.coefficient.conj() * b.coefficient * overlap[i][j]).re;
+            }
+        }
+        if norm_sqr <= 1e-12 {
+            return Err("the combination vanishes everywhere".to_string());
+        }
+
+        Ok(Self {
+            name: None,
+            terms,
+            overlap,
+            norm_sqr,
+        })
+    }
+
+    // preset such as "sigma1s", "sigma*2p" or "pi2p" (greek letters work too) for two
+    // nuclei `bond_length` bohr apart; "h2+" is sigma1s
+    // the bond length defaults to 2 n^2 a, the h2+ equilibrium distance for 1s
+    pub fn diatomic(
+        preset: &str,
+        bond_length: Option<f64>,
+        species: Species,
+    ) -> Result<Self, String> {
+        let key = preset
+            .trim()
+            .to_lowercase()
+            .replace('σ', "sigma")
+            .replace('π', "pi");
+        let key = if key == "h2+" {
+            "sigma1s"
+        } else {
+            key.as_str()
+        };
+
+        let (name, orbital, sign) = DIATOMIC_PRESETS
+            .iter()
+            .find(|(name, _, _)| *name == key)
+            .ok_or_else(|| {
+                let names: Vec<&str> = DIATOMIC_PRESETS.iter().map(|p| p.0).collect();
+                format!(
+                    "unknown molecular orbital '{}', expected {}",
+                    preset,
+                    names.join(", ")
+                )
+            })?;
+        let shell = name.trim_end_matches(char::is_alphabetic);
+        let shell = shell.chars().last().expect("preset names end in a shell");
+        let orbital = Orbital::from_label(&format!("{}{}", shell, orbital))?.with_species(species);
+
+        let n = orbital.quantum_numbers.n() as f64;
+        let bond_length = bond_length.unwrap_or(2.0 * n * n * species.length_scale());
+        if bond_length <= 0.0 {
+            return Err("the bond length must be positive".to_string());
+        }
+        let half = bond_length / 2.0;
+        let terms = vec![
+            AtomicTerm {
+                coefficient: Complex64::new(1.0, 0.0),
+                orbital,
+                centre: glm::vec3(0.0, -half, 0.0),
+            },
+            AtomicTerm {
+                coefficient: Complex64::new(*sign, 0.0),
+                orbital,
+                centre: glm::vec3(0.0, half, 0.0),
+            },
+        ];
+
+        let mut mo = Self::new(terms)?;
+        mo.name = Some(name.replace("sigma", "σ").replace("pi", "π").to_string());
+        Ok(mo)
+    }
+
+    // parses e.g. "1s@(0,0,-1) - 1s@(0,0,1)" or "2px@(0,0,-2) + 0.5i*2px@(0,0,2)"
+    // states and coefficients follow Superposition::parse, each followed by @(x,y,z), the
+    // centre in bohr in orbital axes (z the molecular axis); a missing centre is the origin
+    pub fn parse(text: &str, species: Species) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for (sign, term) in split_terms(text)? {
+            let (coefficient, state) = match term.split_once('*') {
+                Some((c, s)) => (parse_coefficient(c)?, s),
+                None => (Complex64::new(1.0, 0.0), term.as_str()),
+            };
+            let (state, centre) = match state.split_once('@') {
+                Some((state, centre)) => (state, parse_centre(centre)?),
+                None => (state, glm::vec3(0.0, 0.0, 0.0)),
+            };
+            terms.push(AtomicTerm {
+                coefficient: coefficient * sign,
+                orbital: parse_state(state)?.with_species(species),
+                centre,
+            });
+        }
+        Self::new(terms)
+    }
+
+    // a preset name, or an expansion when the text places orbitals with '@'
+    pub fn from_spec(
+        text: &str,
+        bond_length: Option<f64>,
+        species: Species,
+    ) -> Result<Self, String> {
+        if !text.contains('@') {
+            return Self::diatomic(text, bond_length, species);
+        }
+        if bond_length.is_some() {
+            return Err("a bond length only applies to the diatomic presets".to_string());
+        }
+        Self::parse(text, species)
+    }
+
+    pub fn terms(&self) -> &[AtomicTerm] {
+        &self.terms
+    }
+
+    pub fn species(&self) -> Species {
+        self.terms[0].orbital.species
+    }
+
+    // <phi_i|phi_j> between the atomic orbitals of terms i and j
+    pub fn overlap(&self, i: usize, j: usize) -> Complex64 {
+        self.overlap[i][j]
+    }
+
+    // distinct centres, in the order they first appear
+    pub fn nuclei(&self) -> Vec<Nucleus> {
+        let mut nuclei: Vec<Nucleus> = Vec::new();
+        for term in &self.terms {
+            if !nuclei.iter().any(|n| same_point(&n.position, &term.centre)) {
+                nuclei.push(Nucleus {
+                    position: term.centre,
+                    z: self.species().z,
+                });
+            }
+        }
+        nuclei
+    }
+
+    // mean position of the nuclei
+    pub fn centroid(&self) -> glm::DVec3 {
+        let nuclei = self.nuclei();
+        nuclei
+            .iter()
+            .fold(glm::vec3(0.0, 0.0, 0.0), |sum, n| sum + n.position)
+            / nuclei.len() as f64
+    }
+
+    // distance from the origin that contains the whole cloud, for grids and cube files
+    pub fn extent(&self) -> f64 {
+        self.terms
+            .iter()
+            .map(|t| glm::length(&t.centre) + 0.5 * t.orbital.r_max())
+            .fold(0.0, f64::max)
+    }
+
+    // normalized wavefunction at a point
+    pub fn psi(&self, pos: &glm::DVec3) -> Complex64 {
+        let amplitudes = self.amplitudes(pos);
+        let psi: Complex64 = self
+            .terms
+            .iter()
+            .zip(&amplitudes)
+            .map(|(t, a)| t.coefficient * a)
+            .sum();
+        psi / self.norm_sqr.sqrt()
+    }
+
+    pub fn density(&self, pos: &glm::DVec3) -> f64 {
+        self.psi(pos).norm_sqr()
+    }
+
+    // exact samples of |psi|^2 by rejection from the mixture sum |c_i|^2 |phi_i|^2, as for
+    // superpositions; cauchy-schwarz bounds |sum c_i phi_i|^2 by K times the mixture,
+    // so on average norm / (K sum |c_i|^2) proposals are kept
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        let bound = self.terms.len() as f64;
+        let weight: f64 = self.terms.iter().map(|t| t.coefficient.norm_sqr()).sum();
+        let mut particles = Vec::with_capacity(num_particles);
+
+        while particles.len() < num_particles {
+            let position = self.propose(sampler, weight);
+            let amplitudes = self.amplitudes(&position);
+            let psi: Complex64 = self
+                .terms
+                .iter()
+                .zip(&amplitudes)
+                .map(|(t, a)| t.coefficient * a)
+                .sum();
+            let mixture: f64 = self
+                .terms
+                .iter()
+                .zip(&amplitudes)
+                .map(|(t, a)| t.coefficient.norm_sqr() * a.norm_sqr())
+                .sum();
+            let u: f64 = sampler.rng().gen();
+
+            if u * bound * mixture < psi.norm_sqr() {
+                let psi = psi / self.norm_sqr.sqrt();
+                particles.push(self.particle(position, psi, sampler.color_mode));
+            }
+        }
+
+        particles
+    }
+
+    // "σ*1s" for presets, the expansion otherwise
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        format_terms(self.terms.iter().map(|t| {
+            let qn = t.orbital.quantum_numbers;
+            let state = t
+                .orbital
+                .label()
+                .unwrap_or_else(|| format!("[{},{},{}]", qn.n(), qn.l(), qn.m()));
+            // back to orbital axes for display
+            let c = t.centre;
+            (
+                t.coefficient,
+                format!("{}@({:.2},{:.2},{:.2})", state, c.x, c.z, c.y),
+            )
+        }))
+    }
+
+    // atomic orbitals on their own centres, before mixing
+    fn amplitudes(&self, pos: &glm::DVec3) -> Vec<Complex64> {
+        self.terms
+            .iter()
+            .map(|t| t.orbital.psi_at(&(pos - t.centre)))
+            .collect()
+    }
+
+    // one draw from the mixture: pick a term by |c_i|^2, then sample its orbital around
+    // its centre
+    fn propose(&self, sampler: &mut Sampler, weight: f64) -> glm::DVec3 {
+        let mut u: f64 = sampler.rng().gen::<f64>() * weight;
+        let mut index = self.terms.len() - 1;
+        for (i, term) in self.terms.iter().enumerate() {
+            u -= term.coefficient.norm_sqr();
+            if u < 0.0 {
+                index = i;
+                break;
+            }
+        }
+        let term = &self.terms[index];
+        let (r, theta, phi) = sampler.sample_spherical(&term.orbital);
+        term.centre + spherical_to_cartesian(r, theta, phi)
+    }
+
+    // real combinations are colored by sign like real orbitals, complex ones by phase
+    fn particle(&self, position: glm::DVec3, psi: Complex64, color_mode: ColorMode) -> Particle {
+        let density = psi.norm_sqr();
+        let intensity = physics::density_intensity(density * self.species().length_scale().powi(3));
+        let real = self.terms.iter().all(|t| {
+            t.coefficient.im == 0.0
+                && (t.orbital.harmonic == Harmonic::Real || t.orbital.quantum_numbers.l() == 0)
+        });
+        let color = match color_mode {
+            ColorMode::Density => physics::heatmap_cool(intensity),
+            ColorMode::Phase if real => physics::sign_color(psi.arg().abs(), intensity),
+            ColorMode::Phase => physics::phase_wheel(psi.arg(), intensity),
+        };
+
+        Particle {
+            position,
+            color,
+            density,
+        }
+    }
+}
+
+// overlap integrals; pairs on one centre are orthonormal eigenstates and are set exactly
+fn overlap_matrix(terms: &[AtomicTerm]) -> Vec<Vec<Complex64>> {
+    let k = terms.len();
+    let mut overlap = vec![vec![Complex64::new(0.0, 0.0); k]; k];
+    for i in 0..k {
+        overlap[i][i] = Complex64::new(1.0, 0.0);
+        for j in 0..i {
+            if same_point(&terms[i].centre, &terms[j].centre) {
+                continue;
+            }
+            overlap[i][j] = two_centre_overlap(&terms[i], &terms[j]);
+            overlap[j][i] = overlap[i][j].conj();
+        }
+    }
+    overlap
+}
+
+// <phi_a|phi_b> for orbitals on two centres, by gauss-legendre quadrature in prolate
+// spheroidal coordinates: r_a = R/2 (mu + nu), r_b = R/2 (mu - nu) and phi about the bond
+// with mu = cosh u and nu = cos t the transverse distance R/2 sinh u sin t is smooth, so
+// the product rule converges like a polynomial fit; in phi the integrand is a low-order
+// trigonometric polynomial, which the trapezoid rule integrates exactly
+fn two_centre_overlap(a: &AtomicTerm, b: &AtomicTerm) -> Complex64 {
+    let bond = b.centre - a.centre;
+    let half = glm::length(&bond) / 2.0;
+    let midpoint = (a.centre + b.centre) / 2.0;
+    let e3 = bond / (2.0 * half);
+    let helper = if e3.x.abs() < 0.9 {
+        glm::vec3(1.0, 0.0, 0.0)
+    } else {
+        glm::vec3(0.0, 1.0, 0.0)
+    };
+    let e1 = glm::normalize(&glm::cross(&e3, &helper));
+    let e2 = glm::cross(&e3, &e1);
+
+    // the product decays at least like exp(-kappa R mu) for the slower exponent
+    // kappa = 1 / (n a); past the cutoff it and its polynomial factors are negligible
+    let kappa = |o: &Orbital| 1.0 / (o.quantum_numbers.n() as f64 * o.species.length_scale());
+    let slowest = kappa(&a.orbital).min(kappa(&b.orbital));
+    let degree = (a.orbital.quantum_numbers.n() + b.orbital.quantum_numbers.n()) as f64;
+    let mu_max = 1.0 + (50.0 + 4.0 * degree) / (slowest * 2.0 * half);
+
+    let u_nodes = gauss_legendre(OVERLAP_NODES, 0.0, mu_max.acosh());
+    let t_nodes = gauss_legendre(OVERLAP_NODES, 0.0, PI);
+    let phi_step = 2.0 * PI / OVERLAP_PHI_NODES as f64;
+
+    let mut sum = Complex64::new(0.0, 0.0);
+    for (u, wu) in &u_nodes {
+        let (mu, sinh_u) = (u.cosh(), u.sinh());
+        for (t, wt) in &t_nodes {
+            let (nu, sin_t) = (t.cos(), t.sin());
+            let along = midpoint + e3 * (half * mu * nu);
+            let rho = half * sinh_u * sin_t;
+            let jacobian = half.powi(3) * (mu * mu - nu * nu) * sinh_u * sin_t;
+            for s in 0..OVERLAP_PHI_NODES {
+                let phi = s as f64 * phi_step;
+                let position = along + (e1 * phi.cos() + e2 * phi.sin()) * rho;
+                let phi_a = a.orbital.psi_at(&(position - a.centre));
+                let phi_b = b.orbital.psi_at(&(position - b.centre));
+                sum += phi_a.conj() * phi_b * (wu * wt * jacobian * phi_step);
+            }
+        }
+    }
+    sum
+}
+
+// nodes and weights of the n-point gauss-legendre rule on [a, b]
+// roots of P_n by newton's method from the chebyshev guesses
+fn gauss_legendre(n: usize, a: f64, b: f64) -> Vec<(f64, f64)> {
+    let (mid, half) = ((a + b) / 2.0, (b - a) / 2.0);
+    (0..n)
+        .map(|i| {
+            let mut x = (PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+            let mut derivative = 0.0;
+            for _ in 0..100 {
+                // P_n(x) and P_n'(x) by the three-term recurrence
+                let (mut p0, mut p1) = (1.0, x);
+                for k in 2..=n {
+                    let k = k as f64;
+                    (p0, p1) = (p1, ((2.0 * k - 1.0) * x * p1 - (k - 1.0) * p0) / k);
+                }
+                derivative = n as f64 * (x * p1 - p0) / (x * x - 1.0);
+                let dx = p1 / derivative;
+                x -= dx;
+                if dx.abs() < 1e-15 {
+                    break;
+                }
+            }
+            let weight = 2.0 / ((1.0 - x * x) * derivative * derivative);
+            (mid + half * x, half * weight)
+        })
+        .collect()
+}
+
+fn same_point(a: &glm::DVec3, b: &glm::DVec3) -> bool {
+    glm::distance(a, b) < 1e-9
+}
+
+// "(x,y,z)" in orbital axes to a sampler-frame position
+fn parse_centre(text: &str) -> Result<glm::DVec3, String> {
+    let invalid = || format!("invalid centre '{}', expected (x,y,z) in bohr", text);
+    let inner = text
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+    let values = inner
+        .split(',')
+        .map(|s| s.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    match values.as_slice() {
+        [x, y, z] => Ok(glm::vec3(*x, *z, *y)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::HYDROGEN;
+
+    #[test]
+    fn test_1s_overlap() {
+        // S(R) = exp(-R) (1 + R + R^2 / 3) for two hydrogen 1s functions
+        let mo = MolecularOrbital::diatomic("sigma1s", Some(2.0), HYDROGEN).unwrap();
+        let exact = (-2.0f64).exp() * (1.0 + 2.0 + 4.0 / 3.0);
+        let s = mo.overlap(0, 1);
+        assert!((s.re - exact).abs() < 1e-9, "S = {}", s);
+        assert!(s.im.abs() < 1e-12);
+        assert_eq!(mo.nuclei().len(), 2);
+        assert_eq!(mo.label(), "σ1s");
+    }
+
+    #[test]
+    fn test_overlap_follows_the_bond() {
+        // S(R) for 1s at several distances, along an arbitrary direction
+        for r in [0.5f64, 2.0, 6.0] {
+            let text = format!("1s@(0,0,0) + 1s@({},{},0)", 0.6 * r, 0.8 * r);
+            let s = MolecularOrbital::parse(&text, HYDROGEN)
+                .unwrap()
+                .overlap(0, 1);
+            let exact = (-r).exp() * (1.0 + r + r * r / 3.0);
+            assert!((s.re - exact).abs() < 1e-9, "S({}) = {} vs {}", r, s, exact);
+        }
+
+        // parallel 2p sigma functions, on any axis: with p = R / 2 and the lobes facing,
+        // S = exp(-p) (-1 - p - p^2 / 5 + 2 p^3 / 15 + p^4 / 15), so 2 / e at R = 2
+        let along_z = MolecularOrbital::parse("2pz@(0,0,-1) - 2pz@(0,0,1)", HYDROGEN).unwrap();
+        let along_x = MolecularOrbital::parse("2px@(-1,0,0) - 2px@(1,0,0)", HYDROGEN).unwrap();
+        let exact = 2.0 * (-1.0f64).exp();
+        assert!((along_z.overlap(0, 1).re - exact).abs() < 1e-9);
+        assert!((along_z.overlap(0, 1) - along_x.overlap(0, 1)).norm() < 1e-10);
+    }
+
+    #[test]
+    fn test_bonding_and_antibonding() {
+        let bonding = MolecularOrbital::diatomic("h2+", None, HYDROGEN).unwrap();
+        let antibonding = MolecularOrbital::diatomic("σ*1s", Some(2.0), HYDROGEN).unwrap();
+        let midpoint = glm::vec3(0.0, 0.0, 0.0);
+        assert!(antibonding.density(&midpoint) < 1e-20);
+        // 2 phi_1s(1)^2 / (1 + S) with S = 0.5865
+        let expected = 2.0 * (-2.0f64).exp() / std::f64::consts::PI / 1.5865;
+        assert!((bonding.density(&midpoint) - expected).abs() < 1e-3 * expected);
+
+        // the bond region between the nuclei holds more of the bonding cloud
+        let between = |mo: &MolecularOrbital| {
+            let particles = mo.sample(&mut Sampler::with_seed(2), 20_000);
+            particles
+                .iter()
+                .filter(|p| p.position.y.abs() < 1.0)
+                .count()
+        };
+        assert!(between(&bonding) > between(&antibonding) + 2000);
+    }
+
+    #[test]
+    fn test_parse_matches_preset() {
+        let preset = MolecularOrbital::diatomic("pi*2p", Some(3.0), HYDROGEN).unwrap();
+        let parsed = MolecularOrbital::parse("2px@(0, 0, -1.5) - 2px@(0,0,1.5)", HYDROGEN).unwrap();
+        let p = glm::vec3(0.7, 0.4, -0.2);
+        assert!((preset.density(&p) - parsed.density(&p)).abs() < 1e-12);
+        assert_eq!(
+            parsed.label(),
+            "1.000 2px@(0.00,0.00,-1.50) - 1.000 2px@(0.00,0.00,1.50)"
+        );
+        assert!(MolecularOrbital::parse("1s@(0,0) + 1s", HYDROGEN).is_err());
+        assert!(MolecularOrbital::parse("1s@(0,0,1) + 1s@(0,0,1)", HYDROGEN).is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use crate::physics::{
+    self, build_cdf, cartesian_to_spherical, invert_cdf, spherical_harmonic,
+    spherical_to_cartesian, ColorMode, Harmonic, Orbital, Particle, Sampler,
+};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+use statrs::function::gamma;
+use std::f64::consts::PI;
+
+// hydrogen-like orbitals in momentum space
+// the fourier transform keeps the harmonic, phi(p) = (-i)^l F_nl(p) Y_lm(p hat), and in units
+// of p0 = hbar / a, a being the species length scale, podolsky and pauling's closed form is
+//   F_nl(p) = sqrt(2/pi (n-l-1)! / (n+l)!) n^2 2^(2l+2) l! (n p)^l / (n^2 p^2 + 1)^(l+2)
+//             * C^(l+1)_(n-l-1)((n^2 p^2 - 1) / (n^2 p^2 + 1))
+// with C the gegenbauer polynomials; momenta are in hbar / bohr, densities in (bohr / hbar)^3
+
+// points in the radial table, which spans [0, P_MAX p0 / n]
+const N_RADIAL: usize = 8192;
+
+// the momentum density falls as p^-(2l+6), so past 20 p0 / n less than 1e-6 is left
+const P_MAX: f64 = 20.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MomentumOrbital {
+    orbital: Orbital,
+    dp: f64,
+    // inverse-cdf table of p^2 F_nl(p)^2
+    radial_cdf: Vec<f64>,
+}
+
+impl MomentumOrbital {
+    pub fn new(orbital: Orbital) -> Self {
+        let n = orbital.quantum_numbers.n() as f64;
+        let dp = P_MAX / n * (1.0 / orbital.species.length_scale()) / (N_RADIAL - 1) as f64;
+        let mut state = Self {
+            orbital,
+            dp,
+            radial_cdf: Vec::new(),
+        };
+        state.radial_cdf = build_cdf(N_RADIAL, |i| {
+            let p = i as f64 * dp;
+            let radial = state.radial(p);
+            p * p * radial * radial
+        });
+        state
+    }
+
+    // the position-space orbital with the same n, l, m, harmonic and species
+    pub fn orbital(&self) -> &Orbital {
+        &self.orbital
+    }
+
+    // p0 = hbar / a in hbar / bohr, Z for hydrogen with a fixed nucleus
+    pub fn momentum_scale(&self) -> f64 {
+        1.0 / self.orbital.species.length_scale()
+    }
+
+    // normalized radial function F_nl(p), p in hbar / bohr
+    pub fn radial(&self, p: f64) -> f64 {
+        let qn = self.orbital.quantum_numbers;
+        let (n, l) = (qn.n(), qn.l());
+        let p0 = self.momentum_scale();
+        let x = n as f64 * p / p0;
+        let norm = (2.0 / PI * gamma::gamma((n - l) as f64) / gamma::gamma((n + l + 1) as f64))
+            .sqrt()
+            * (n * n) as f64
+            * 2f64.powi(2 * l + 2)
+            * gamma::gamma((l + 1) as f64);
+        let t = x * x + 1.0;
+        let shape = norm * x.powi(l) / t.powi(l + 2)
+            * gegenbauer(n - l - 1, (l + 1) as f64, (x * x - 1.0) / t);
+        shape / p0.powf(1.5)
+    }
+
+    // normalized momentum wavefunction at a point of the sampler frame
+    pub fn phi(&self, p: &glm::DVec3) -> Complex64 {
+        let qn = self.orbital.quantum_numbers;
+        let (p, theta, phi) = cartesian_to_spherical(p);
+        let minus_i_to_l = Complex64::new(0.0, -1.0).powi(qn.l());
+        minus_i_to_l
+            * self.radial(p)
+            * spherical_harmonic(qn.l(), qn.m(), theta, phi, self.orbital.harmonic)
+    }
+
+    // |phi(p)|^2 in (bohr / hbar)^3
+    pub fn density(&self, p: &glm::DVec3) -> f64 {
+        self.phi(p).norm_sqr()
+    }
+
+    // <p^2> = (p0 / n)^2 exactly, so <p^2> / 2 mu is minus the bound-state energy
+    pub fn mean_square_momentum(&self) -> f64 {
+        (self.momentum_scale() / self.orbital.quantum_numbers.n() as f64).powi(2)
+    }
+
+    // <p> from the radial table
+    pub fn mean_momentum(&self) -> f64 {
+        self.radial_integral(0.0, |p| p)
+    }
+
+    // isotropic compton profile J(q) = 1/2 integral from |q| of p F_nl(p)^2 dp, in bohr / hbar,
+    // the spherical average of the electron's momentum distribution projected on one axis
+    pub fn compton_profile(&self, q: f64) -> f64 {
+        0.5 * self.radial_integral(q.abs(), |p| 1.0 / p)
+    }
+
+    // integral from p_min to the end of the table of weight(p) p^2 F_nl(p)^2 dp, by simpson's rule
+    fn radial_integral(&self, p_min: f64, weight: impl Fn(f64) -> f64) -> f64 {
+        let p_max = self.dp * (N_RADIAL - 1) as f64;
+        if p_min >= p_max {
+            return 0.0;
+        }
+        let steps = 4096;
+        let h = (p_max - p_min) / steps as f64;
+        let f = |p: f64| {
+            if p == 0.0 {
+                return 0.0;
+            }
+            let radial = self.radial(p);
+            weight(p) * p * p * radial * radial
+        };
+        let inner: f64 = (1..steps)
+            .map(|i| {
+                let coefficient = if i % 2 == 1 { 4.0 } else { 2.0 };
+                coefficient * f(p_min + i as f64 * h)
+            })
+            .sum();
+        (f(p_min) + inner + f(p_max)) * h / 3.0
+    }
+
+    // half-width of a box holding essentially all of the momentum density
+    pub fn p_max(&self) -> f64 {
+        5.0 * self.momentum_scale() / self.orbital.quantum_numbers.n() as f64
+    }
+
+    // momentum per display unit, p0 / 4n so every shell fills the view alike
+    pub fn display_unit(&self) -> f64 {
+        0.25 * self.momentum_scale() / self.orbital.quantum_numbers.n() as f64
+    }
+
+    // exact samples: p from the radial table, the angles from |Y_lm|^2 like the position cloud
+    // phase coloring drops the global (-i)^l, so real orbitals keep their two-tone lobes
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        let qn = self.orbital.quantum_numbers;
+        let harmonic = self.orbital.harmonic;
+        let p0 = self.momentum_scale();
+        (0..num_particles)
+            .map(|_| {
+                let p = invert_cdf(&self.radial_cdf, sampler.rng().gen()) as f64 * self.dp;
+                let (theta, phi) = sampler.sample_angles(qn.l(), qn.m(), harmonic);
+                let position = spherical_to_cartesian(p, theta, phi);
+                let value =
+                    self.radial(p) * spherical_harmonic(qn.l(), qn.m(), theta, phi, harmonic);
+                let density = value.norm_sqr();
+                let intensity = physics::density_intensity(density * p0.powi(3));
+                let color = match (sampler.color_mode, harmonic) {
+                    (ColorMode::Density, _) => physics::heatmap_cool(intensity),
+                    (ColorMode::Phase, Harmonic::Real) => {
+                        physics::sign_color(value.arg().rem_euclid(2.0 * PI), intensity)
+                    }
+                    (ColorMode::Phase, Harmonic::Complex) => {
+                        physics::phase_wheel(value.arg(), intensity)
+                    }
+                };
+                Particle {
+                    position,
+                    color,
+                    density,
+                }
+            })
+            .collect()
+    }
+}
+
+// gegenbauer polynomial C^alpha_k(x) via upward recurrence
+pub fn gegenbauer(k: i32, alpha: f64, x: f64) -> f64 {
+    if k == 0 {
+        return 1.0;
+    }
+
+    let mut c_prev = 1.0;
+    let mut c = 2.0 * alpha * x;
+    for j in 2..=k {
+        let j = j as f64;
+        let next = (2.0 * x * (j + alpha - 1.0) * c - (j + 2.0 * alpha - 2.0) * c_prev) / j;
+        c_prev = c;
+        c = next;
+    }
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::QuantumNumbers;
+
+    fn orbital(n: i32, l: i32, m: i32) -> Orbital {
+        Orbital::new(QuantumNumbers::new(n, l, m).unwrap())
+    }
+
+    fn momentum(n: i32, l: i32, m: i32) -> MomentumOrbital {
+        MomentumOrbital::new(orbital(n, l, m))
+    }
+
+    #[test]
+    fn test_gegenbauer() {
+        // C^1_k are the chebyshev polynomials of the second kind, C^a_2 = 2a(a+1)x^2 - a
+        let x = 0.3;
+        assert!((gegenbauer(3, 1.0, x) - (8.0 * x.powi(3) - 4.0 * x)).abs() < 1e-12);
+        assert!((gegenbauer(2, 2.5, x) - (2.0 * 2.5 * 3.5 * x * x - 2.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_normalization_and_virial() {
+        for (n, l) in [(1, 0), (2, 0), (2, 1), (3, 1), (4, 2), (5, 3)] {
+            let state = momentum(n, l, 0);
+            let norm = state.radial_integral(0.0, |_| 1.0);
+            assert!((norm - 1.0).abs() < 1e-5, "n={} l={}: {}", n, l, norm);
+            // the table stops at 20 p0 / n, which cuts the slow p^-4 tail of s states
+            let p2 = state.radial_integral(0.0, |p| p * p);
+            let expected = state.mean_square_momentum();
+            assert!((p2 - expected).abs() < 5e-3 * expected, "n={} l={}", n, l);
+        }
+    }
+
+    #[test]
+    fn test_fourier_transform_and_compton_profile() {
+        // 1s of a helium ion: phi = 2 sqrt(2) / pi Z^(-3/2) / (1 + (p/Z)^2)^2,
+        // J(q) = 8 / (3 pi Z (1 + (q/Z)^2)^3)
+        let species = physics::SPECIES_PRESETS
+            .iter()
+            .copied()
+            .find(|s| s.z == 2)
+            .unwrap();
+        let state = MomentumOrbital::new(orbital(1, 0, 0).with_species(species));
+        let z = state.momentum_scale();
+        let p = glm::vec3(0.4, -1.1, 0.7);
+        let t = glm::length2(&p) / (z * z);
+        let expected = 2.0 * 2f64.sqrt() / PI / z.powf(1.5) / (1.0 + t).powi(2);
+        assert!((state.phi(&p).re - expected).abs() < 1e-12 * expected);
+
+        for q in [0.0, 0.5, 2.0] {
+            let expected = 8.0 / (3.0 * PI * z * (1.0 + (q / z).powi(2)).powi(3));
+            let j = state.compton_profile(q);
+            assert!((j - expected).abs() < 1e-4 * expected, "q={}: {}", q, j);
+        }
+
+        // 2p0 is real in position space and purely imaginary in momentum space
+        let state = momentum(2, 1, 0);
+        let p = glm::vec3(0.2, 0.1, -0.3);
+        let phi = state.phi(&p);
+        assert!(phi.re.abs() < 1e-15 && phi.norm() > 0.0);
+    }
+
+    #[test]
+    fn test_sampling() {
+        let state = momentum(2, 1, 0);
+        let mut sampler = Sampler::with_seed(4);
+        let particles = state.sample(&mut sampler, 40_000);
+        let mean_p2 = particles
+            .iter()
+            .map(|p| glm::length2(&p.position))
+            .sum::<f64>()
+            / particles.len() as f64;
+        assert!((mean_p2 - 0.25).abs() < 0.02, "<p^2> = {}", mean_p2);
+        let p = &particles[7];
+        assert!((p.density - state.density(&p.position)).abs() < 1e-9 * p.density);
+    }
+}
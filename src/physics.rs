@@ -1,208 +1,666 @@
-use lazy_static::lazy_static;
 use nalgebra_glm as glm;
-use rand::Rng;
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use statrs::function::gamma;
+use std::collections::HashMap;
 use std::f64::consts::PI;
-use std::sync::Mutex;
+use std::fmt;
 
 // data structures and constants
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Particle {
-    // public so main.rs can construct and read sampled particles
+    // public so library users and the viewer can read sampled particles
     pub position: glm::DVec3,
     pub color: glm::Vec4,
+    // |psi|^2 at the particle position, in bohr^-3
+    pub density: f64,
 }
 
 const A0: f64 = 1.0; // bohr radius set to 1 (atomic units for simplicity)
 
-// global quantum numbers defining the current hydrogenic orbital
-// wrapped in mutex because they are set at runtime before sampling
-// lazy_static is used since mutex cannot be const-initialized
-lazy_static! {
-    pub static ref N: Mutex<i32> = Mutex::new(2);
-    pub static ref L: Mutex<i32> = Mutex::new(1);
-    pub static ref M: Mutex<i32> = Mutex::new(0);
+// bohr in angstrom, for formats that expect angstrom
+pub const BOHR_ANGSTROM: f64 = 0.529177210903;
+
+// hartree in electron volts, for reporting energies
+pub const HARTREE_EV: f64 = 27.211386245988;
+
+// hydrogen-like two-body system
+// z is the nuclear charge, masses are in electron masses
+// nuclear_mass may be f64::INFINITY for the fixed-nucleus limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Species {
+    pub name: &'static str,
+    pub z: i32,
+    pub particle_mass: f64,
+    pub nuclear_mass: f64,
 }
 
-// particle generation
+impl Species {
+    // reduced mass mu = m M / (m + M), in electron masses
+    pub fn reduced_mass(&self) -> f64 {
+        if self.nuclear_mass.is_infinite() {
+            self.particle_mass
+        } else {
+            self.particle_mass * self.nuclear_mass / (self.particle_mass + self.nuclear_mass)
+        }
+    }
 
-// generates monte carlo samples of the hydrogenic orbital defined by (n, l, m)
-// radial and angular parts are sampled independently and then converted to cartesian space
-pub fn generate_particles(num_particles: usize) -> Vec<Particle> {
-    // read currently selected quantum numbers
-    let n = *N.lock().unwrap();
-    let l = *L.lock().unwrap();
-    let m = *M.lock().unwrap();
+    // characteristic orbital size a = a0 (m_e / mu) / Z, in bohr
+    // every hydrogenic length scales with this
+    pub fn length_scale(&self) -> f64 {
+        A0 / (self.reduced_mass() * self.z as f64)
+    }
 
-    // preallocate memory to avoid repeated reallocations
-    let mut particles = Vec::with_capacity(num_particles);
+    // bound state energy E_n = -mu Z^2 / (2 n^2), in hartree
+    pub fn energy(&self, n: i32) -> f64 {
+        -self.reduced_mass() * (self.z * self.z) as f64 / (2.0 * (n * n) as f64)
+    }
+}
 
-    for _ in 0..num_particles {
-        // sample radial and angular coordinates
-        let r = sample_r(n, l);
-        let theta = sample_theta(l, m);
-        let phi = sample_phi();
+// fixed-nucleus hydrogen, the classic textbook model
+pub const HYDROGEN: Species = Species {
+    name: "H (infinite nuclear mass)",
+    z: 1,
+    particle_mass: 1.0,
+    nuclear_mass: f64::INFINITY,
+};
+
+// fixed-nucleus ion with one electron and nuclear charge z
+pub const fn hydrogen_like(z: i32) -> Species {
+    Species {
+        name: "hydrogen-like ion (infinite nuclear mass)",
+        z,
+        particle_mass: 1.0,
+        nuclear_mass: f64::INFINITY,
+    }
+}
 
-        // convert spherical sample to cartesian for rendering
-        let pos = spherical_to_cartesian(r, theta, phi);
+pub const SPECIES_PRESETS: [Species; 6] = [
+    HYDROGEN,
+    Species {
+        name: "H (proton)",
+        z: 1,
+        particle_mass: 1.0,
+        nuclear_mass: 1836.15267343,
+    },
+    Species {
+        name: "He+",
+        z: 2,
+        particle_mass: 1.0,
+        nuclear_mass: 7294.29954142,
+    },
+    Species {
+        name: "Li2+ (7Li)",
+        z: 3,
+        particle_mass: 1.0,
+        nuclear_mass: 12786.3933,
+    },
+    Species {
+        name: "muonic hydrogen",
+        z: 1,
+        particle_mass: 206.768283,
+        nuclear_mass: 1836.15267343,
+    },
+    Species {
+        name: "positronium",
+        z: 1,
+        particle_mass: 1.0,
+        nuclear_mass: 1.0,
+    },
+];
+
+// how particle colors are derived from the wavefunction
+// density maps |psi|^2 onto a heatmap
+// phase tints by the sign of psi (real harmonics) or arg(psi) on a hue wheel (complex)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    Density,
+    Phase,
+}
 
-        // compute probability density and map to color
-        let color = get_particle_color(r, theta, phi, n, l, m);
+// which set of angular functions the orbital is built from
+// complex harmonics Y_lm have |Y|^2 independent of phi (tori for m != 0)
+// real harmonics use cos(m*phi) for m > 0 and sin(|m|*phi) for m < 0,
+// giving the textbook px, py, dxy, ... lobes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Harmonic {
+    Complex,
+    Real,
+}
 
-        particles.push(Particle {
-            position: pos,
-            color,
-        });
-    }
+// chemist's names for the real harmonics, indexed by (l, m)
+// axes refer to the orbital frame, whose z (polar) axis is drawn vertically
+const REAL_LABELS: [(i32, i32, &str); 16] = [
+    (0, 0, "s"),
+    (1, 0, "pz"),
+    (1, 1, "px"),
+    (1, -1, "py"),
+    (2, 0, "dz2"),
+    (2, 1, "dxz"),
+    (2, -1, "dyz"),
+    (2, 2, "dx2-y2"),
+    (2, -2, "dxy"),
+    (3, 0, "fz3"),
+    (3, 1, "fxz2"),
+    (3, -1, "fyz2"),
+    (3, 2, "fz(x2-y2)"),
+    (3, -2, "fxyz"),
+    (3, 3, "fx(x2-3y2)"),
+    (3, -3, "fy(3x2-y2)"),
+];
+
+// quantum numbers and orbitals
+
+// reasons a (n, l, m) triple does not describe a bound hydrogenic state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantumNumberError {
+    NotPositive { n: i32 },
+    LOutOfRange { n: i32, l: i32 },
+    MOutOfRange { l: i32, m: i32 },
+}
 
-    particles
+impl fmt::Display for QuantumNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantumNumberError::NotPositive { .. } => {
+                write!(f, "Principal quantum number (n) must be positive.")
+            }
+            QuantumNumberError::LOutOfRange { .. } => {
+                write!(
+                    f,
+                    "Azimuthal quantum number (l) must be in the range [0, n-1]."
+                )
+            }
+            QuantumNumberError::MOutOfRange { .. } => {
+                write!(
+                    f,
+                    "Magnetic quantum number (m) must be in the range [-l, l]."
+                )
+            }
+        }
+    }
 }
 
-// converts spherical coordinates (r, theta, phi) to cartesian
-// theta is measured from +y axis, phi rotates around y axis
-pub fn spherical_to_cartesian(r: f64, theta: f64, phi: f64) -> glm::DVec3 {
-    let x = r * theta.sin() * phi.cos();
-    let y = r * theta.cos();
-    let z = r * theta.sin() * phi.sin();
-    glm::vec3(x, y, z)
+impl std::error::Error for QuantumNumberError {}
+
+// validated (n, l, m) triple
+// fields are private so every value in circulation satisfies n > 0, 0 <= l < n, |m| <= l
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuantumNumbers {
+    n: i32,
+    l: i32,
+    m: i32,
 }
 
-// physics calculations and sampling
+impl QuantumNumbers {
+    pub fn new(n: i32, l: i32, m: i32) -> Result<Self, QuantumNumberError> {
+        if n <= 0 {
+            return Err(QuantumNumberError::NotPositive { n });
+        }
+        if l < 0 || l >= n {
+            return Err(QuantumNumberError::LOutOfRange { n, l });
+        }
+        if m.abs() > l {
+            return Err(QuantumNumberError::MOutOfRange { l, m });
+        }
+        Ok(Self { n, l, m })
+    }
+
+    pub fn n(&self) -> i32 {
+        self.n
+    }
 
-// sample phi uniformly in [0, 2*pi]
-// probability density is independent of phi
-fn sample_phi() -> f64 {
-    let mut rng = rand::thread_rng();
-    rng.gen_range(0.0..2.0 * PI)
+    pub fn l(&self) -> i32 {
+        self.l
+    }
+
+    pub fn m(&self) -> i32 {
+        self.m
+    }
 }
 
-// sample radial coordinate using inverse transform sampling
-// builds and caches a cdf for each (n, l) pair
-fn sample_r(n: i32, l: i32) -> f64 {
-    // cache stores precomputed radial cdfs keyed by (n, l)
-    lazy_static! {
-        static ref CDF_CACHE: Mutex<Vec<(i32, i32, Vec<f64>)>> = Mutex::new(Vec::new());
+// a single one-electron state: quantum numbers, angular basis and the system it lives in
+// everything needed to evaluate or sample the orbital, with no global state involved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbital {
+    pub quantum_numbers: QuantumNumbers,
+    pub harmonic: Harmonic,
+    pub species: Species,
+}
+
+impl Orbital {
+    // complex-harmonic orbital of fixed-nucleus hydrogen
+    pub fn new(quantum_numbers: QuantumNumbers) -> Self {
+        Self {
+            quantum_numbers,
+            harmonic: Harmonic::Complex,
+            species: HYDROGEN,
+        }
     }
 
-    let mut cache = CDF_CACHE.lock().unwrap();
+    // real-harmonic orbital from a label such as "2px" or "3dxy"
+    pub fn from_label(label: &str) -> Result<Self, String> {
+        Ok(Self::new(parse_orbital_label(label)?).with_harmonic(Harmonic::Real))
+    }
 
-    // reuse cached cdf if available
-    if let Some(entry) = cache.iter().find(|(cn, cl, _)| *cn == n && *cl == l) {
-        let cdf = &entry.2;
-        let u: f64 = rand::thread_rng().gen();
+    pub fn with_harmonic(mut self, harmonic: Harmonic) -> Self {
+        self.harmonic = harmonic;
+        self
+    }
 
-        // inverse transform sampling via binary search
-        let idx = match cdf.binary_search_by(|v| v.partial_cmp(&u).unwrap()) {
-            Ok(i) => i,
-            Err(i) => i,
+    pub fn with_species(mut self, species: Species) -> Self {
+        self.species = species;
+        self
+    }
+
+    // chemist's label for real orbitals with l <= 3, None otherwise
+    pub fn label(&self) -> Option<String> {
+        let qn = self.quantum_numbers;
+        match self.harmonic {
+            Harmonic::Real => orbital_label(qn.n, qn.l, qn.m),
+            Harmonic::Complex if qn.l == 0 => orbital_label(qn.n, 0, 0),
+            Harmonic::Complex => None,
+        }
+    }
+
+    // normalized radial function R_nl(r), r in bohr
+    pub fn radial(&self, r: f64) -> f64 {
+        let qn = self.quantum_numbers;
+        radial_wavefunction(qn.n, qn.l, r, self.species.length_scale())
+    }
+
+    // normalized probability density |psi|^2 at (r, theta, phi), in bohr^-3
+    pub fn density(&self, r: f64, theta: f64, phi: f64) -> f64 {
+        let qn = self.quantum_numbers;
+        let r_wave = self.radial(r);
+        let (angular, _) = angular_wavefunction(qn.l, qn.m, theta, phi, self.harmonic);
+        let norm = angular_norm(qn.l, qn.m);
+
+        r_wave * r_wave * angular * angular * norm * norm
+    }
+
+    // normalized wavefunction psi(r, theta, phi), in bohr^-3/2
+    // purely real for real harmonics
+    pub fn psi(&self, r: f64, theta: f64, phi: f64) -> Complex64 {
+        let qn = self.quantum_numbers;
+        self.radial(r) * spherical_harmonic(qn.l, qn.m, theta, phi, self.harmonic)
+    }
+
+    // psi at a cartesian point of the sampler frame
+    pub fn psi_at(&self, pos: &glm::DVec3) -> Complex64 {
+        let (r, theta, phi) = cartesian_to_spherical(pos);
+        self.psi(r, theta, phi)
+    }
+
+    // complex argument of psi in [0, 2*pi)
+    // real orbitals only ever give 0 (positive lobe) or pi (negative lobe)
+    pub fn phase(&self, r: f64, theta: f64, phi: f64) -> f64 {
+        let qn = self.quantum_numbers;
+        let (angular, azimuthal_phase) =
+            angular_wavefunction(qn.l, qn.m, theta, phi, self.harmonic);
+        let sign_phase = if self.radial(r) * angular < 0.0 {
+            PI
+        } else {
+            0.0
         };
 
-        let r_max = 10.0 * (n * n) as f64 * A0;
-        return idx as f64 * (r_max / (cdf.len() - 1) as f64);
+        (azimuthal_phase + sign_phase).rem_euclid(2.0 * PI)
     }
 
-    const N_CDF: usize = 4096;
-    let r_max = 10.0 * (n * n) as f64 * A0;
-    let mut cdf = vec![0.0; N_CDF];
-    let dr = r_max / (N_CDF - 1) as f64;
+    // radius enclosing essentially all of the probability, used for cdf tables and grids
+    pub fn r_max(&self) -> f64 {
+        let n = self.quantum_numbers.n;
+        10.0 * (n * n) as f64 * self.species.length_scale()
+    }
+}
 
-    let mut sum = 0.0;
+// parses labels such as "1s", "2px", "3dxy" or "4fz3" into quantum numbers
+// the returned m indexes the real harmonic, so the label implies Harmonic::Real
+pub fn parse_orbital_label(label: &str) -> Result<QuantumNumbers, String> {
+    let label = label.trim().to_lowercase();
+    let digits = label.chars().take_while(|c| c.is_ascii_digit()).count();
+
+    let n = label[..digits]
+        .parse::<i32>()
+        .map_err(|_| format!("'{}' does not start with a principal quantum number", label))?;
+    let name = &label[digits..];
+
+    let (l, m) = REAL_LABELS
+        .iter()
+        .find(|(_, _, s)| *s == name)
+        .map(|(l, m, _)| (*l, *m))
+        .ok_or_else(|| format!("unknown orbital '{}'", name))?;
+
+    QuantumNumbers::new(n, l, m)
+        .map_err(|_| format!("{}{} does not exist: l must be below n", n, name))
+}
 
-    for i in 0..N_CDF {
-        let r = i as f64 * dr;
+// inverse of parse_orbital_label, None for l > 3 where no common name exists
+pub fn orbital_label(n: i32, l: i32, m: i32) -> Option<String> {
+    REAL_LABELS
+        .iter()
+        .find(|(cl, cm, _)| *cl == l && *cm == m)
+        .map(|(_, _, name)| format!("{}{}", n, name))
+}
 
-        // scaled radial coordinate
-        let rho = 2.0 * r / (n as f64 * A0);
+// spectroscopic letters for l = 0, 1, 2, ..., skipping j as is customary
+const SUBSHELL_LETTERS: &str = "spdfghiklmnoqrtuv";
 
-        // radial polynomial component
-        let laguerre_l = associated_laguerre(n - l - 1, 2 * l + 1, rho);
+// "s" for l = 0, "p" for l = 1, ..., and "(l=20)" once the letters run out
+pub fn subshell_letter(l: i32) -> String {
+    match SUBSHELL_LETTERS.chars().nth(l as usize) {
+        Some(letter) if l >= 0 => letter.to_string(),
+        _ => format!("(l={})", l),
+    }
+}
 
-        // normalization factors
-        let norm_part1 = (2.0 / (n as f64 * A0)).powi(3);
-        let norm_part2 =
-            gamma::gamma((n - l) as f64) / (2.0 * n as f64 * gamma::gamma((n + l + 1) as f64));
+// inverse of subshell_letter for a single letter, case-insensitive
+pub fn parse_subshell_letter(letter: char) -> Option<i32> {
+    SUBSHELL_LETTERS
+        .find(letter.to_ascii_lowercase())
+        .map(|l| l as i32)
+}
 
-        let norm = (norm_part1 * norm_part2).sqrt();
+// particle generation
 
-        let r_wave = norm * (-rho / 2.0).exp() * rho.powi(l) * laguerre_l;
+// monte carlo sampler for hydrogenic orbitals
+// owns its random number generator and the inverse-cdf tables it builds,
+// so independent samplers can run on separate threads without sharing state
+pub struct Sampler {
+    pub color_mode: ColorMode,
+    rng: StdRng,
+    // radial cdfs keyed by (n, l, length scale), angular ones by (l, |m|) and m
+    radial_cdfs: HashMap<RadialKey, Vec<f64>>,
+    theta_cdfs: HashMap<(i32, i32), Vec<f64>>,
+    phi_cdfs: HashMap<i32, Vec<f64>>,
+}
 
-        // include r^2 jacobian term
-        sum += r * r * r_wave * r_wave;
-        cdf[i] = sum;
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    // normalize cdf to [0, 1]
-    for val in cdf.iter_mut() {
-        *val /= sum;
+impl Sampler {
+    // sampler seeded from system entropy
+    pub fn new() -> Self {
+        Self::from_rng(StdRng::from_entropy())
     }
 
-    let cdf_clone = cdf.clone();
-    cache.push((n, l, cdf));
+    // reproducible sampler, the same seed always yields the same particles
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(StdRng::seed_from_u64(seed))
+    }
 
-    let u: f64 = rand::thread_rng().gen();
-    let idx = match cdf_clone.binary_search_by(|v| v.partial_cmp(&u).unwrap()) {
-        Ok(i) => i,
-        Err(i) => i,
-    };
+    fn from_rng(rng: StdRng) -> Self {
+        Self {
+            color_mode: ColorMode::Density,
+            rng,
+            radial_cdfs: HashMap::new(),
+            theta_cdfs: HashMap::new(),
+            phi_cdfs: HashMap::new(),
+        }
+    }
 
-    idx as f64 * (r_max / (N_CDF - 1) as f64)
-}
+    // the underlying random source, for samplers of other densities (cube files, ...)
+    // that should share the seed and stream of this one
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    // generates monte carlo samples of the given orbital
+    // radial and angular parts are sampled independently and then converted to cartesian space
+    pub fn generate(&mut self, orbital: &Orbital, num_particles: usize) -> Vec<Particle> {
+        // preallocate memory to avoid repeated reallocations
+        let mut particles = Vec::with_capacity(num_particles);
+
+        for _ in 0..num_particles {
+            // sample radial and angular coordinates
+            let (r, theta, phi) = self.sample_spherical(orbital);
+
+            // convert spherical sample to cartesian for rendering
+            let pos = spherical_to_cartesian(r, theta, phi);
 
-// sample theta from angular probability distribution
-// includes sin(theta) from spherical volume element
-fn sample_theta(l: i32, m: i32) -> f64 {
-    lazy_static! {
-        static ref CDF_CACHE: Mutex<Vec<(i32, i32, Vec<f64>)>> = Mutex::new(Vec::new());
+            // compute probability density and map to color
+            let color = get_particle_color(orbital, r, theta, phi, self.color_mode);
+
+            particles.push(Particle {
+                position: pos,
+                color,
+                density: orbital.density(r, theta, phi),
+            });
+        }
+
+        particles
     }
 
-    let m_abs = m.abs();
-    let mut cache = CDF_CACHE.lock().unwrap();
+    // draws one (r, theta, phi) sample from |psi|^2 of the orbital
+    pub fn sample_spherical(&mut self, orbital: &Orbital) -> (f64, f64, f64) {
+        let qn = orbital.quantum_numbers;
+        let r = self.sample_r(qn.n, qn.l, orbital.species.length_scale());
+        let (theta, phi) = self.sample_angles(qn.l, qn.m, orbital.harmonic);
+        (r, theta, phi)
+    }
 
-    if let Some(entry) = cache.iter().find(|(cl, cm, _)| *cl == l && *cm == m_abs) {
-        let cdf = &entry.2;
-        let u: f64 = rand::thread_rng().gen();
+    // draws (theta, phi) from |Y_lm|^2 sin(theta), for radial functions sampled elsewhere
+    pub fn sample_angles(&mut self, l: i32, m: i32, harmonic: Harmonic) -> (f64, f64) {
+        let theta = self.sample_theta(l, m);
+        let phi = self.sample_phi(m, harmonic);
+        (theta, phi)
+    }
 
-        let idx = match cdf.binary_search_by(|v| v.partial_cmp(&u).unwrap()) {
-            Ok(i) => i,
-            Err(i) => i,
-        };
+    // the radial cdf that sample_r inverts for this orbital and its spacing in bohr,
+    // entry i being the probability inside r = i * dr
+    pub fn radial_cdf(&mut self, orbital: &Orbital) -> (&[f64], f64) {
+        let qn = orbital.quantum_numbers;
+        self.radial_table(qn.n, qn.l, orbital.species.length_scale())
+    }
 
-        return idx as f64 * (PI / (cdf.len() - 1) as f64);
+    // builds and caches a cdf for each (n, l, a) triple, a being the species length scale
+    fn radial_table(&mut self, n: i32, l: i32, a: f64) -> (&[f64], f64) {
+        const N_CDF: usize = 4096;
+
+        // the cdf extends well past the outermost lobe, which grows as n^2 a
+        let r_max = 10.0 * (n * n) as f64 * a;
+        let dr = r_max / (N_CDF - 1) as f64;
+
+        let cdf = self
+            .radial_cdfs
+            .entry((n, l, a.to_bits()))
+            .or_insert_with(|| {
+                build_cdf(N_CDF, |i| {
+                    let r = i as f64 * dr;
+                    let r_wave = radial_wavefunction(n, l, r, a);
+
+                    // include r^2 jacobian term
+                    r * r * r_wave * r_wave
+                })
+            });
+        (cdf, dr)
     }
 
-    const N_CDF: usize = 2048;
-    let mut cdf = vec![0.0; N_CDF];
-    let d_theta = PI / (N_CDF - 1) as f64;
+    // sample radial coordinate using inverse transform sampling
+    fn sample_r(&mut self, n: i32, l: i32, a: f64) -> f64 {
+        let u = self.rng.gen();
+        let (cdf, dr) = self.radial_table(n, l, a);
 
-    let mut sum = 0.0;
+        // inverse transform sampling via binary search
+        invert_cdf(cdf, u) as f64 * dr
+    }
+
+    // sample theta from angular probability distribution
+    // includes sin(theta) from spherical volume element
+    fn sample_theta(&mut self, l: i32, m: i32) -> f64 {
+        const N_CDF: usize = 2048;
+        let d_theta = PI / (N_CDF - 1) as f64;
+        let m_abs = m.abs();
+
+        let cdf = self.theta_cdfs.entry((l, m_abs)).or_insert_with(|| {
+            build_cdf(N_CDF, |i| {
+                let theta = i as f64 * d_theta;
+                let plm = associated_legendre(l, m_abs, theta.cos());
+                theta.sin() * plm * plm
+            })
+        });
+
+        invert_cdf(cdf, self.rng.gen()) as f64 * d_theta
+    }
+
+    // sample phi in [0, 2*pi]
+    // complex harmonics (and m = 0) are independent of phi, so phi is uniform
+    // real harmonics follow cos^2(m*phi) or sin^2(|m|*phi), sampled from a cached cdf
+    fn sample_phi(&mut self, m: i32, harmonic: Harmonic) -> f64 {
+        if harmonic == Harmonic::Complex || m == 0 {
+            return self.rng.gen_range(0.0..2.0 * PI);
+        }
+
+        const N_CDF: usize = 2048;
+        let d_phi = 2.0 * PI / (N_CDF - 1) as f64;
+
+        let cdf = self.phi_cdfs.entry(m).or_insert_with(|| {
+            build_cdf(N_CDF, |i| {
+                let az = real_azimuthal(m, i as f64 * d_phi);
+                az * az
+            })
+        });
+
+        invert_cdf(cdf, self.rng.gen()) as f64 * d_phi
+    }
+}
+
+// convenience wrapper: samples one orbital with a fresh, entropy-seeded sampler
+pub fn generate_particles(
+    orbital: &Orbital,
+    num_particles: usize,
+    color_mode: ColorMode,
+) -> Vec<Particle> {
+    let mut sampler = Sampler::new();
+    sampler.color_mode = color_mode;
+    sampler.generate(orbital, num_particles)
+}
 
-    for i in 0..N_CDF {
-        let theta = i as f64 * d_theta;
-        let plm = associated_legendre(l, m_abs, theta.cos());
+// converts spherical coordinates (r, theta, phi) to cartesian
+// theta is measured from +y axis, phi rotates around y axis
+pub fn spherical_to_cartesian(r: f64, theta: f64, phi: f64) -> glm::DVec3 {
+    let x = r * theta.sin() * phi.cos();
+    let y = r * theta.cos();
+    let z = r * theta.sin() * phi.sin();
+    glm::vec3(x, y, z)
+}
+
+// converts between the sampler frame, which draws the orbital z axis as +y, and the
+// orbital frame used by files: orbital (x, y, z) is sampler (x, z, y), so the same
+// swap maps either way
+pub fn swap_yz(pos: &glm::DVec3) -> glm::DVec3 {
+    glm::vec3(pos.x, pos.z, pos.y)
+}
+
+// inverse of spherical_to_cartesian, returns (r, theta, phi) with phi in [0, 2*pi)
+pub fn cartesian_to_spherical(pos: &glm::DVec3) -> (f64, f64, f64) {
+    let r = glm::length(pos);
+    if r == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let theta = (pos.y / r).clamp(-1.0, 1.0).acos();
+    let phi = pos.z.atan2(pos.x).rem_euclid(2.0 * PI);
+    (r, theta, phi)
+}
 
-        sum += theta.sin() * plm * plm;
-        cdf[i] = sum;
+// physics calculations and sampling
+
+// (n, l, bit pattern of the length scale a)
+type RadialKey = (i32, i32, u64);
+
+// running sum of the weights produced by f(0..len), normalized to [0, 1]
+pub(crate) fn build_cdf(len: usize, f: impl Fn(usize) -> f64) -> Vec<f64> {
+    let mut cdf = vec![0.0; len];
+    let mut sum = 0.0;
+
+    for (i, c) in cdf.iter_mut().enumerate() {
+        sum += f(i);
+        *c = sum;
     }
 
+    // normalize cdf to [0, 1]
     for val in cdf.iter_mut() {
         *val /= sum;
     }
 
-    let cdf_clone = cdf.clone();
-    cache.push((l, m_abs, cdf));
+    cdf
+}
 
-    let u: f64 = rand::thread_rng().gen();
-    let idx = match cdf_clone.binary_search_by(|v| v.partial_cmp(&u).unwrap()) {
+// index of the first cdf entry not below u, via binary search
+pub(crate) fn invert_cdf(cdf: &[f64], u: f64) -> usize {
+    match cdf.binary_search_by(|v| v.partial_cmp(&u).unwrap()) {
         Ok(i) => i,
         Err(i) => i,
-    };
+    }
+}
+
+// azimuthal factor of a real harmonic, normalized so its square averages to 1 over phi
+// this keeps real and complex densities on the same scale for coloring
+fn real_azimuthal(m: i32, phi: f64) -> f64 {
+    if m > 0 {
+        2f64.sqrt() * (m as f64 * phi).cos()
+    } else if m < 0 {
+        2f64.sqrt() * (-m as f64 * phi).sin()
+    } else {
+        1.0
+    }
+}
 
-    idx as f64 * (PI / (N_CDF - 1) as f64)
+// normalized hydrogenic radial function R_nl(r)
+// a = a0 (m_e / mu) / Z absorbs both the nuclear charge and the reduced mass
+fn radial_wavefunction(n: i32, l: i32, r: f64, a: f64) -> f64 {
+    // scaled radial coordinate
+    let rho = 2.0 * r / (n as f64 * a);
+
+    // radial polynomial component
+    let laguerre = associated_laguerre(n - l - 1, 2 * l + 1, rho);
+
+    // normalization factors
+    let norm_part1 = (2.0 / (n as f64 * a)).powi(3);
+    let norm_part2 =
+        gamma::gamma((n - l) as f64) / (2.0 * n as f64 * gamma::gamma((n + l + 1) as f64));
+
+    let norm = (norm_part1 * norm_part2).sqrt();
+
+    norm * (-rho / 2.0).exp() * rho.powi(l) * laguerre
+}
+
+// signed angular factor (up to normalization) and azimuthal phase of psi
+// complex harmonics carry e^(i*m*phi) and follow the condon-shortley convention,
+// real harmonics drop that (-1)^m so that px points along +x
+fn angular_wavefunction(l: i32, m: i32, theta: f64, phi: f64, harmonic: Harmonic) -> (f64, f64) {
+    let plm = associated_legendre(l, m.abs(), theta.cos());
+    let parity = if m % 2 == 0 { 1.0 } else { -1.0 };
+
+    match harmonic {
+        Harmonic::Complex if m < 0 => (parity * plm, m as f64 * phi),
+        Harmonic::Complex => (plm, m as f64 * phi),
+        Harmonic::Real => (parity * plm * real_azimuthal(m, phi), 0.0),
+    }
+}
+
+// normalized spherical harmonic Y_lm(theta, phi), purely real for real harmonics
+pub fn spherical_harmonic(l: i32, m: i32, theta: f64, phi: f64, harmonic: Harmonic) -> Complex64 {
+    let (angular, azimuthal_phase) = angular_wavefunction(l, m, theta, phi, harmonic);
+    Complex64::from_polar(angular * angular_norm(l, m), azimuthal_phase)
+}
+
+// spherical harmonic normalization sqrt((2l+1)/(4 pi) (l-|m|)!/(l+|m|)!)
+fn angular_norm(l: i32, m: i32) -> f64 {
+    let m_abs = m.abs();
+    ((2 * l + 1) as f64 / (4.0 * PI) * gamma::gamma((l - m_abs + 1) as f64)
+        / gamma::gamma((l + m_abs + 1) as f64))
+    .sqrt()
 }
 
 // associated laguerre polynomial via recurrence
 // used in radial hydrogen wavefunction
-fn associated_laguerre(k: i32, alpha: i32, x: f64) -> f64 {
+pub fn associated_laguerre(k: i32, alpha: i32, x: f64) -> f64 {
     if k == 0 {
         return 1.0;
     }
@@ -229,7 +687,7 @@ fn associated_laguerre(k: i32, alpha: i32, x: f64) -> f64 {
 
 // associated legendre polynomial via upward recurrence
 // used in angular part of hydrogen wavefunction
-fn associated_legendre(l: i32, m: i32, x: f64) -> f64 {
+pub fn associated_legendre(l: i32, m: i32, x: f64) -> f64 {
     let m_abs = m.abs();
     let mut pmm = 1.0;
 
@@ -265,33 +723,47 @@ fn associated_legendre(l: i32, m: i32, x: f64) -> f64 {
     pm1m
 }
 
-// compute probability density at (r, theta)
-// and map it to a visual intensity
-fn get_particle_color(r: f64, theta: f64, _phi: f64, n: i32, l: i32, m: i32) -> glm::Vec4 {
-    let rho = 2.0 * r / (n as f64 * A0);
-
-    let laguerre = associated_laguerre(n - l - 1, 2 * l + 1, rho);
-
-    let norm_part1 = (2.0 / (n as f64 * A0)).powi(3);
-
-    let norm_part2 =
-        gamma::gamma((n - l) as f64) / (2.0 * n as f64 * gamma::gamma((n + l + 1) as f64));
-
-    let r_wave = (norm_part1 * norm_part2).sqrt() * (-rho / 2.0).exp() * rho.powi(l) * laguerre;
-
-    let angular = associated_legendre(l, m.abs(), theta.cos());
-
-    let raw = r_wave * r_wave * angular * angular;
+// compute probability density at (r, theta, phi)
+// and map it to a visual intensity, optionally tinted by the phase of psi
+fn get_particle_color(
+    orbital: &Orbital,
+    r: f64,
+    theta: f64,
+    phi: f64,
+    color_mode: ColorMode,
+) -> glm::Vec4 {
+    let qn = orbital.quantum_numbers;
+    let a = orbital.species.length_scale();
+    let r_wave = orbital.radial(r);
+    let (angular, _) = angular_wavefunction(qn.l, qn.m, theta, phi, orbital.harmonic);
+
+    // express the density in units of a^-3 so the color scale is species independent
+    let raw = r_wave * r_wave * angular * angular * a.powi(3);
+
+    let intensity = density_intensity(raw);
+
+    match color_mode {
+        ColorMode::Density => heatmap_cool(intensity),
+        ColorMode::Phase => {
+            let phase = orbital.phase(r, theta, phi);
+            match orbital.harmonic {
+                Harmonic::Real => sign_color(phase, intensity),
+                Harmonic::Complex => phase_wheel(phase, intensity),
+            }
+        }
+    }
+}
 
+// maps a density in units of a^-3 to a color intensity in [0, 1]
+pub(crate) fn density_intensity(scaled_density: f64) -> f64 {
     // logarithmic compression (BEST choice)
-    let intensity = (raw * 100.0).ln_1p().min(1.0);
-    heatmap_cool(intensity)
+    (scaled_density * 100.0).ln_1p().min(1.0)
 }
 
 // simple linear heatmap from black to white
 // purely for visual contrast, not physical meaning
-fn heatmap_cool(value: f64) -> glm::Vec4 {
-    let v = value.max(0.0).min(1.0) as f32;
+pub(crate) fn heatmap_cool(value: f64) -> glm::Vec4 {
+    let v = value.clamp(0.0, 1.0) as f32;
 
     // smooth gradient: dark → blue → cyan (NO WHITE)
     let r = 0.0;
@@ -300,3 +772,243 @@ fn heatmap_cool(value: f64) -> glm::Vec4 {
 
     glm::vec4(r, g, b, 0.6)
 }
+
+// two-tone map for real orbitals: positive lobes warm, negative lobes blue
+// brightness still follows the density so nodal regions stay dark
+pub(crate) fn sign_color(phase: f64, value: f64) -> glm::Vec4 {
+    let v = (0.3 + 0.7 * value.clamp(0.0, 1.0)) as f32;
+
+    if phase < PI / 2.0 {
+        glm::vec4(v, 0.45 * v, 0.1 * v, 0.6)
+    } else {
+        glm::vec4(0.1 * v, 0.45 * v, v, 0.6)
+    }
+}
+
+// cyclic hue wheel for arg(psi): red at 0, green at 2pi/3, blue at 4pi/3
+pub(crate) fn phase_wheel(phase: f64, value: f64) -> glm::Vec4 {
+    let v = (0.3 + 0.7 * value.clamp(0.0, 1.0)) as f32;
+    let h = (phase.rem_euclid(2.0 * PI) / (2.0 * PI) * 6.0) as f32;
+
+    // standard hsv -> rgb with full saturation
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    glm::vec4(r * v, g * v, b * v, 0.6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbital(n: i32, l: i32, m: i32, harmonic: Harmonic) -> Orbital {
+        Orbital::new(QuantumNumbers::new(n, l, m).unwrap()).with_harmonic(harmonic)
+    }
+
+    #[test]
+    fn test_quantum_numbers_validation() {
+        assert!(QuantumNumbers::new(3, 2, -2).is_ok());
+        assert_eq!(
+            QuantumNumbers::new(0, 0, 0),
+            Err(QuantumNumberError::NotPositive { n: 0 })
+        );
+        assert_eq!(
+            QuantumNumbers::new(2, 2, 0),
+            Err(QuantumNumberError::LOutOfRange { n: 2, l: 2 })
+        );
+        assert_eq!(
+            QuantumNumbers::new(3, 1, 2),
+            Err(QuantumNumberError::MOutOfRange { l: 1, m: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_orbital_label() {
+        let qn = |label| parse_orbital_label(label).map(|q| (q.n(), q.l(), q.m()));
+
+        assert_eq!(qn("1s"), Ok((1, 0, 0)));
+        assert_eq!(qn("2px"), Ok((2, 1, 1)));
+        assert_eq!(qn("3dxy"), Ok((3, 2, -2)));
+        assert_eq!(qn("4fz3"), Ok((4, 3, 0)));
+
+        assert!(qn("2dxy").is_err());
+        assert!(qn("px").is_err());
+        assert!(qn("3q").is_err());
+    }
+
+    #[test]
+    fn test_orbital_label_round_trip() {
+        for (l, m, _) in REAL_LABELS {
+            let label = orbital_label(4, l, m).unwrap();
+            assert_eq!(
+                Orbital::from_label(&label),
+                Ok(orbital(4, l, m, Harmonic::Real))
+            );
+        }
+        assert_eq!(orbital_label(5, 4, 0), None);
+    }
+
+    #[test]
+    fn test_subshell_letters() {
+        assert_eq!(subshell_letter(3), "f");
+        assert_eq!(subshell_letter(7), "k");
+        assert_eq!(subshell_letter(40), "(l=40)");
+        assert_eq!(parse_subshell_letter('G'), Some(4));
+        assert_eq!(parse_subshell_letter('j'), None);
+    }
+
+    #[test]
+    fn test_real_phi_sampling_follows_lobes() {
+        // px lobes lie along phi = 0 and pi, py lobes along pi/2 and 3pi/2
+        let along_x = |phi: f64| phi.cos().abs() > phi.sin().abs();
+        let mut sampler = Sampler::with_seed(7);
+
+        let px = (0..2000)
+            .filter(|_| along_x(sampler.sample_phi(1, Harmonic::Real)))
+            .count();
+        let py = (0..2000)
+            .filter(|_| along_x(sampler.sample_phi(-1, Harmonic::Real)))
+            .count();
+
+        assert!(px > 1500, "px samples should concentrate along x");
+        assert!(py < 500, "py samples should avoid the x axis");
+    }
+
+    #[test]
+    fn test_real_phase_follows_lobe_sign() {
+        let half_pi = PI / 2.0;
+        let px = orbital(2, 1, 1, Harmonic::Real);
+        let s2 = orbital(2, 0, 0, Harmonic::Real);
+
+        // 2px is positive along +x and negative along -x
+        assert_eq!(px.phase(2.0, half_pi, 0.0), 0.0);
+        assert_eq!(px.phase(2.0, half_pi, PI), PI);
+
+        // 2s changes sign across its radial node at r = 2
+        assert_eq!(s2.phase(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(s2.phase(3.0, 0.0, 0.0), PI);
+    }
+
+    #[test]
+    fn test_complex_phase_winds_with_m() {
+        let half_pi = PI / 2.0;
+        let d = orbital(3, 2, 2, Harmonic::Complex);
+        let a = d.phase(2.0, half_pi, 0.1);
+        let b = d.phase(2.0, half_pi, 0.4);
+
+        // arg(psi) advances by m * delta phi around the polar axis
+        assert!((b - a - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_spherical_round_trip() {
+        let (r, theta, phi) = (2.5, 1.1, 4.0);
+        let (r2, theta2, phi2) = cartesian_to_spherical(&spherical_to_cartesian(r, theta, phi));
+
+        assert!((r - r2).abs() < 1e-12);
+        assert!((theta - theta2).abs() < 1e-12);
+        assert!((phi - phi2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_species_length_scales() {
+        let he = SPECIES_PRESETS[2];
+        let muonic = SPECIES_PRESETS[4];
+        let positronium = SPECIES_PRESETS[5];
+
+        assert_eq!(HYDROGEN.length_scale(), A0);
+        assert!((he.length_scale() - 0.5 * A0).abs() < 1e-3);
+        assert!((positronium.length_scale() - 2.0 * A0).abs() < 1e-12);
+        assert!((1.0 / muonic.length_scale() - 185.84).abs() < 0.01);
+
+        // He+ ground state: -Z^2 / 2 hartree, slightly raised by the finite nuclear mass
+        assert!(he.energy(1) > -2.0 && he.energy(1) < -1.999);
+    }
+
+    #[test]
+    fn test_radial_wavefunction_normalized_for_any_scale() {
+        for a in [A0, 0.5, 1.0 / 185.84, 2.0] {
+            let r_max = 60.0 * a;
+            let steps = 20_000;
+            let dr = r_max / steps as f64;
+            let norm: f64 = (0..steps)
+                .map(|i| {
+                    let r = (i as f64 + 0.5) * dr;
+                    let rw = radial_wavefunction(3, 1, r, a);
+                    r * r * rw * rw * dr
+                })
+                .sum();
+
+            assert!((norm - 1.0).abs() < 1e-6, "norm {} for a = {}", norm, a);
+        }
+    }
+
+    #[test]
+    fn test_density_integrates_to_one() {
+        for orb in [
+            orbital(3, 2, 1, Harmonic::Complex),
+            orbital(3, 2, -2, Harmonic::Real),
+        ] {
+            // midpoint rule over a spherical grid
+            let (nr, nt, np) = (200, 60, 60);
+            let dr = orb.r_max() / nr as f64;
+            let dt = PI / nt as f64;
+            let dp = 2.0 * PI / np as f64;
+            let mut total = 0.0;
+
+            for i in 0..nr {
+                let r = (i as f64 + 0.5) * dr;
+                for j in 0..nt {
+                    let t = (j as f64 + 0.5) * dt;
+                    for k in 0..np {
+                        let p = (k as f64 + 0.5) * dp;
+                        total += orb.density(r, t, p) * r * r * t.sin() * dr * dt * dp;
+                    }
+                }
+            }
+
+            assert!((total - 1.0).abs() < 1e-2, "integral {}", total);
+        }
+    }
+
+    #[test]
+    fn test_psi_squares_to_density() {
+        let orb = orbital(4, 3, -2, Harmonic::Complex);
+        let psi = orb.psi(5.0, 0.7, 2.1);
+
+        assert!((psi.norm_sqr() - orb.density(5.0, 0.7, 2.1)).abs() < 1e-15);
+        assert_eq!(orbital(2, 1, -1, Harmonic::Real).psi(2.0, 1.0, 1.0).im, 0.0);
+    }
+
+    #[test]
+    fn test_seeded_samplers_are_reproducible_across_threads() {
+        let a = orbital(2, 1, 1, Harmonic::Real);
+        let b = orbital(3, 2, 0, Harmonic::Complex);
+
+        let handles: Vec<_> = [a, b, a]
+            .into_iter()
+            .map(|orb| {
+                std::thread::spawn(move || {
+                    let mut sampler = Sampler::with_seed(42);
+                    sampler
+                        .generate(&orb, 500)
+                        .iter()
+                        .map(|p| p.position)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(results[0], results[2]);
+        assert_ne!(results[0], results[1]);
+    }
+}
@@ -0,0 +1,695 @@
+use crate::physics::{associated_legendre, cartesian_to_spherical, invert_cdf};
+use crate::physics::{Orbital, Particle, Sampler};
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+// two-dimensional plots of an orbital's radial and angular distributions with histograms of a
+// sampled cloud on top, written as svg or png without a window or gpu
+// a figure is laid out once against the Canvas trait; the svg backend writes elements and the
+// png backend rasterizes them with a built-in 5x7 bitmap font
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlotFormat {
+    Svg,
+    Png,
+}
+
+impl PlotFormat {
+    // guesses the format from a file name
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1;
+        match ext.to_lowercase().as_str() {
+            "svg" => Some(PlotFormat::Svg),
+            "png" => Some(PlotFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PlotFormat::Svg => "svg",
+            PlotFormat::Png => "png",
+        }
+    }
+}
+
+type Rgb = [u8; 3];
+
+const CURVE_COLOR: Rgb = [20, 60, 170];
+const BAR_COLOR: Rgb = [150, 205, 235];
+const AXIS_COLOR: Rgb = [40, 40, 40];
+const GRID_COLOR: Rgb = [225, 225, 225];
+
+// panel size in pixels and the margins around its plot area
+const PANEL_WIDTH: f64 = 420.0;
+const PANEL_HEIGHT: f64 = 380.0;
+const TITLE_HEIGHT: f64 = 40.0;
+const MARGIN: [f64; 4] = [40.0, 20.0, 55.0, 80.0]; // top, right, bottom, left
+
+// an exact curve over histogram bars of the samples, both from x = 0 and y = 0
+pub struct Panel {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub x_max: f64,
+    // tick spacing along x, None for automatic ticks
+    pub x_step: Option<f64>,
+    pub curve: Vec<(f64, f64)>,
+    // bar i spans [i * bar_width, (i + 1) * bar_width]
+    pub bars: Vec<f64>,
+    pub bar_width: f64,
+}
+
+pub struct Figure {
+    pub title: String,
+    pub panels: Vec<Panel>,
+}
+
+impl Figure {
+    // P(r) = r^2 R_nl^2, the radial cdf that the sampler inverts and the polar distribution
+    // |P_l^m(cos theta)|^2 sin theta, each over a normalized histogram of the particles
+    pub fn orbital(orbital: &Orbital, particles: &[Particle], sampler: &mut Sampler) -> Self {
+        const BINS: usize = 60;
+        const POINTS: usize = 400;
+
+        let qn = orbital.quantum_numbers;
+        let (l, m) = (qn.l(), qn.m());
+        let (cdf, dr) = sampler.radial_cdf(orbital);
+        let cdf = cdf.to_vec();
+        let count = particles.len().max(1) as f64;
+        let (radii, thetas): (Vec<f64>, Vec<f64>) = particles
+            .iter()
+            .map(|p| {
+                let (r, theta, _) = cartesian_to_spherical(&p.position);
+                (r, theta)
+            })
+            .unzip();
+
+        // out to where the table holds 99.9% of the probability
+        let r_max = 1.05 * (invert_cdf(&cdf, 0.999) + 1) as f64 * dr;
+        let r_width = r_max / BINS as f64;
+        let radial_bars = histogram(&radii, r_width, BINS, count);
+        let radial_curve = (0..=POINTS)
+            .map(|i| {
+                let r = r_max * i as f64 / POINTS as f64;
+                (r, r * r * orbital.radial(r).powi(2))
+            })
+            .collect();
+
+        let mut running = 0.0;
+        let cumulative_bars = radial_bars
+            .iter()
+            .map(|density| {
+                running += density * r_width;
+                running
+            })
+            .collect();
+        let stride = (cdf.len() / POINTS).max(1);
+        let cdf_curve = cdf
+            .iter()
+            .enumerate()
+            .step_by(stride)
+            .map(|(i, &c)| (i as f64 * dr, c))
+            .take_while(|&(r, _)| r <= r_max)
+            .collect();
+
+        // per degree, normalized numerically over [0, 180]
+        let polar = |theta: f64| associated_legendre(l, m.abs(), theta.cos()).powi(2) * theta.sin();
+        let norm: f64 = (0..POINTS)
+            .map(|i| polar((i as f64 + 0.5) * PI / POINTS as f64) * 180.0 / POINTS as f64)
+            .sum();
+        let degrees: Vec<f64> = thetas.iter().map(|t| t.to_degrees()).collect();
+        let angular_curve = (0..=POINTS)
+            .map(|i| {
+                let degree = 180.0 * i as f64 / POINTS as f64;
+                (degree, polar(degree.to_radians()) / norm)
+            })
+            .collect();
+
+        let label = orbital
+            .label()
+            .map_or_else(String::new, |label| format!(" ({})", label));
+        Self {
+            title: format!(
+                "n={}, l={}, m={}{}, {}, {} samples",
+                qn.n(),
+                l,
+                m,
+                label,
+                orbital.species.name,
+                particles.len()
+            ),
+            panels: vec![
+                Panel {
+                    title: "P(r) = r^2 R_nl(r)^2".to_string(),
+                    x_label: "r (bohr)".to_string(),
+                    y_label: "P(r) (1/bohr)".to_string(),
+                    x_max: r_max,
+                    x_step: None,
+                    curve: radial_curve,
+                    bars: radial_bars,
+                    bar_width: r_width,
+                },
+                Panel {
+                    title: "Radial CDF of the sampler".to_string(),
+                    x_label: "r (bohr)".to_string(),
+                    y_label: "probability inside r".to_string(),
+                    x_max: r_max,
+                    x_step: None,
+                    curve: cdf_curve,
+                    bars: cumulative_bars,
+                    bar_width: r_width,
+                },
+                Panel {
+                    title: "|P_l^m(cos theta)|^2 sin theta".to_string(),
+                    x_label: "theta from the z axis (deg)".to_string(),
+                    y_label: "probability per degree".to_string(),
+                    x_max: 180.0,
+                    x_step: Some(30.0),
+                    curve: angular_curve,
+                    bars: histogram(&degrees, 180.0 / BINS as f64, BINS, count),
+                    bar_width: 180.0 / BINS as f64,
+                },
+            ],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        (PANEL_WIDTH * self.panels.len().max(1) as f64) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (TITLE_HEIGHT + PANEL_HEIGHT) as u32
+    }
+
+    pub fn write(&self, out: &mut impl Write, format: PlotFormat) -> io::Result<()> {
+        match format {
+            PlotFormat::Svg => {
+                let mut canvas = SvgCanvas::default();
+                self.draw(&mut canvas);
+                canvas.finish(out, self.width(), self.height())
+            }
+            PlotFormat::Png => {
+                let mut canvas = PngCanvas::new(self.width(), self.height());
+                self.draw(&mut canvas);
+                canvas.finish(out)
+            }
+        }
+    }
+
+    fn draw(&self, canvas: &mut impl Canvas) {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        canvas.rect(0.0, 0.0, width, height, [255, 255, 255]);
+        canvas.text(
+            width / 2.0,
+            TITLE_HEIGHT / 2.0,
+            &self.title,
+            16.0,
+            Anchor::Middle,
+            false,
+        );
+        for (i, panel) in self.panels.iter().enumerate() {
+            panel.draw(canvas, i as f64 * PANEL_WIDTH, TITLE_HEIGHT);
+        }
+    }
+}
+
+impl Panel {
+    fn draw(&self, canvas: &mut impl Canvas, left: f64, top: f64) {
+        let [margin_top, margin_right, margin_bottom, margin_left] = MARGIN;
+        let x0 = left + margin_left;
+        let y0 = top + margin_top;
+        let w = PANEL_WIDTH - margin_left - margin_right;
+        let h = PANEL_HEIGHT - margin_top - margin_bottom;
+
+        let y_peak = self
+            .curve
+            .iter()
+            .map(|&(_, y)| y)
+            .chain(self.bars.iter().copied())
+            .fold(0.0, f64::max);
+        let y_step = nice_step(y_peak);
+        let y_max = (1.05 * y_peak / y_step).ceil() * y_step;
+        let x_step = self.x_step.unwrap_or_else(|| nice_step(self.x_max));
+        let to_pixel = |x: f64, y: f64| (x0 + x / self.x_max * w, y0 + h - y / y_max * h);
+
+        canvas.text(
+            left + PANEL_WIDTH / 2.0,
+            top + 18.0,
+            &self.title,
+            14.0,
+            Anchor::Middle,
+            false,
+        );
+        for (i, value) in ticks(self.x_max, x_step).enumerate() {
+            let (x, _) = to_pixel(value, 0.0);
+            canvas.line(&[(x, y0), (x, y0 + h)], GRID_COLOR, 1.0);
+            canvas.text(
+                x,
+                y0 + h + 14.0,
+                &tick_label(value, x_step, i),
+                12.0,
+                Anchor::Middle,
+                false,
+            );
+        }
+        for (i, value) in ticks(y_max, y_step).enumerate() {
+            let (_, y) = to_pixel(0.0, value);
+            canvas.line(&[(x0, y), (x0 + w, y)], GRID_COLOR, 1.0);
+            canvas.text(
+                x0 - 6.0,
+                y,
+                &tick_label(value, y_step, i),
+                12.0,
+                Anchor::End,
+                false,
+            );
+        }
+
+        for (i, &height) in self.bars.iter().enumerate() {
+            let (bar_left, bar_top) = to_pixel(i as f64 * self.bar_width, height.min(y_max));
+            let (bar_right, _) = to_pixel(((i + 1) as f64 * self.bar_width).min(self.x_max), 0.0);
+            canvas.rect(
+                bar_left,
+                bar_top,
+                bar_right - bar_left,
+                y0 + h - bar_top,
+                BAR_COLOR,
+            );
+        }
+        let curve: Vec<(f64, f64)> = self.curve.iter().map(|&(x, y)| to_pixel(x, y)).collect();
+        canvas.line(&curve, CURVE_COLOR, 2.0);
+
+        canvas.line(&[(x0, y0), (x0, y0 + h), (x0 + w, y0 + h)], AXIS_COLOR, 1.0);
+        canvas.text(
+            x0 + w / 2.0,
+            y0 + h + 36.0,
+            &self.x_label,
+            13.0,
+            Anchor::Middle,
+            false,
+        );
+        canvas.text(
+            left + 16.0,
+            y0 + h / 2.0,
+            &self.y_label,
+            13.0,
+            Anchor::Middle,
+            true,
+        );
+
+        // legend in the top-right corner
+        let (lx, ly) = (x0 + w - 110.0, y0 + 12.0);
+        canvas.line(&[(lx, ly), (lx + 18.0, ly)], CURVE_COLOR, 2.0);
+        canvas.text(lx + 24.0, ly, "exact", 12.0, Anchor::Start, false);
+        canvas.rect(lx, ly + 12.0, 18.0, 10.0, BAR_COLOR);
+        canvas.text(lx + 24.0, ly + 17.0, "samples", 12.0, Anchor::Start, false);
+    }
+}
+
+// fraction of all samples per unit of x in each bin, so the bars integrate to the sampled
+// probability like the exact curves
+fn histogram(values: &[f64], width: f64, bins: usize, count: f64) -> Vec<f64> {
+    let mut heights = vec![0.0; bins];
+    for value in values {
+        if let Some(height) = heights.get_mut((value / width) as usize) {
+            *height += 1.0 / (count * width);
+        }
+    }
+    heights
+}
+
+// 1, 2 or 5 times a power of ten, giving about five ticks up to max
+fn nice_step(max: f64) -> f64 {
+    if max <= 0.0 || !max.is_finite() {
+        return 1.0;
+    }
+    let raw = max / 5.0;
+    let power = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|f| f * power)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * power)
+}
+
+fn ticks(max: f64, step: f64) -> impl Iterator<Item = f64> {
+    (0..)
+        .map(move |i| i as f64 * step)
+        .take_while(move |&v| v <= max * (1.0 + 1e-9))
+}
+
+// as many decimals as the step needs, the first tick always as "0"
+fn tick_label(value: f64, step: f64, index: usize) -> String {
+    if index == 0 {
+        return "0".to_string();
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+// the drawing primitives a figure needs, in pixels from the top-left corner
+// text is centred vertically on y, and vertical text reads bottom to top
+trait Canvas {
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Rgb);
+    fn line(&mut self, points: &[(f64, f64)], color: Rgb, width: f64);
+    fn text(&mut self, x: f64, y: f64, text: &str, size: f64, anchor: Anchor, vertical: bool);
+}
+
+#[derive(Default)]
+struct SvgCanvas {
+    elements: Vec<String>,
+}
+
+impl SvgCanvas {
+    fn finish(&self, out: &mut impl Write, width: u32, height: u32) -> io::Result<()> {
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\">",
+            width, height
+        )?;
+        for element in &self.elements {
+            writeln!(out, "  {}", element)?;
+        }
+        writeln!(out, "</svg>")
+    }
+}
+
+fn svg_color(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+impl Canvas for SvgCanvas {
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Rgb) {
+        self.elements.push(format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+            x,
+            y,
+            w.max(0.0),
+            h.max(0.0),
+            svg_color(color)
+        ));
+    }
+
+    fn line(&mut self, points: &[(f64, f64)], color: Rgb, width: f64) {
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+            .collect();
+        self.elements.push(format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            points.join(" "),
+            svg_color(color),
+            width
+        ));
+    }
+
+    fn text(&mut self, x: f64, y: f64, text: &str, size: f64, anchor: Anchor, vertical: bool) {
+        let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+        let transform = if vertical {
+            format!(" transform=\"rotate(-90 {:.2} {:.2})\"", x, y)
+        } else {
+            String::new()
+        };
+        let escaped = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        self.elements.push(format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"{}\" text-anchor=\"{}\" \
+             dominant-baseline=\"middle\"{}>{}</text>",
+            x, y, size, anchor, transform, escaped
+        ));
+    }
+}
+
+// rgb raster, 8 bits per channel
+struct PngCanvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl PngCanvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![255; (width * height * 3) as usize],
+        }
+    }
+
+    fn put(&mut self, x: i64, y: i64, color: Rgb) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = ((y as u32 * self.width + x as u32) * 3) as usize;
+        self.pixels[i..i + 3].copy_from_slice(&color);
+    }
+
+    fn finish(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(io::Error::other)
+    }
+}
+
+impl Canvas for PngCanvas {
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Rgb) {
+        let (x_start, y_start) = (x.round() as i64, y.round() as i64);
+        let (x_end, y_end) = ((x + w).round() as i64, (y + h).round() as i64);
+        for py in y_start..y_end {
+            for px in x_start..x_end {
+                self.put(px, py, color);
+            }
+        }
+    }
+
+    // each segment is stamped with a square brush every pixel along its length
+    fn line(&mut self, points: &[(f64, f64)], color: Rgb, width: f64) {
+        let brush = width.round().max(1.0) as i64;
+        for pair in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            let steps = (x2 - x1).abs().max((y2 - y1).abs()).ceil().max(1.0) as usize;
+            for s in 0..=steps {
+                let t = s as f64 / steps as f64;
+                let x = (x1 + t * (x2 - x1) - 0.5 * (brush - 1) as f64).round() as i64;
+                let y = (y1 + t * (y2 - y1) - 0.5 * (brush - 1) as f64).round() as i64;
+                for dy in 0..brush {
+                    for dx in 0..brush {
+                        self.put(x + dx, y + dy, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // glyphs are scaled to whole pixels, 2 for the usual sizes
+    fn text(&mut self, x: f64, y: f64, text: &str, size: f64, anchor: Anchor, vertical: bool) {
+        let scale = (size / 7.0).floor().max(1.0) as i64;
+        let advance = 6 * scale;
+        let length = advance * text.chars().count() as i64 - scale;
+        let offset = match anchor {
+            Anchor::Start => 0,
+            Anchor::Middle => length / 2,
+            Anchor::End => length,
+        };
+        let (x, y) = (x.round() as i64, y.round() as i64);
+        for (i, c) in text.chars().enumerate() {
+            let rows = glyph(c);
+            let along = i as i64 * advance - offset;
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..5 {
+                    if bits & (0b10000 >> col) == 0 {
+                        continue;
+                    }
+                    // (u, v) in the glyph's own frame, v downwards from the text's centre line
+                    let u = along + col * scale;
+                    let v = (row as i64 - 3) * scale - scale / 2;
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let (px, py) = if vertical {
+                                (x + v + dy, y - u - dx)
+                            } else {
+                                (x + u + dx, y + v + dy)
+                            };
+                            self.put(px, py, AXIS_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 5x7 bitmap of a printable ascii character, a question mark for anything else
+fn glyph(c: char) -> [u8; 7] {
+    FONT.iter()
+        .find(|(g, _)| *g == c)
+        .map_or(FONT[FONT.len() - 1].1, |(_, rows)| *rows)
+}
+
+const FONT: [(char, [u8; 7]); 88] = [
+    (' ', [0, 0, 0, 0, 0, 0, 0]),
+    ('0', [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e]),
+    ('1', [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e]),
+    ('2', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f]),
+    ('3', [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e]),
+    ('4', [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02]),
+    ('5', [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e]),
+    ('6', [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e]),
+    ('7', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e]),
+    ('9', [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c]),
+    ('A', [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11]),
+    ('B', [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e]),
+    ('C', [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e]),
+    ('D', [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c]),
+    ('E', [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f]),
+    ('F', [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10]),
+    ('G', [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f]),
+    ('H', [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11]),
+    ('I', [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f]),
+    ('M', [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e]),
+    ('P', [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10]),
+    ('Q', [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d]),
+    ('R', [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11]),
+    ('S', [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e]),
+    ('T', [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a]),
+    ('X', [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04]),
+    ('Z', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f]),
+    ('a', [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f]),
+    ('b', [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e]),
+    ('c', [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e]),
+    ('d', [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f]),
+    ('e', [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e]),
+    ('f', [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08]),
+    ('g', [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e]),
+    ('h', [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11]),
+    ('i', [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e]),
+    ('j', [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c]),
+    ('k', [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12]),
+    ('l', [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e]),
+    ('m', [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11]),
+    ('n', [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11]),
+    ('o', [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e]),
+    ('p', [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10]),
+    ('q', [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01]),
+    ('r', [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10]),
+    ('s', [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e]),
+    ('t', [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06]),
+    ('u', [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d]),
+    ('v', [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04]),
+    ('w', [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a]),
+    ('x', [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11]),
+    ('y', [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e]),
+    ('z', [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08]),
+    ('-', [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('[', [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e]),
+    (']', [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('=', [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00]),
+    ('^', [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f]),
+    (':', [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00]),
+    ('|', [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('*', [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+    ('\'', [0x0c, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('"', [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    (';', [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08]),
+    ('#', [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a]),
+    ('&', [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d]),
+    ('?', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::QuantumNumbers;
+
+    fn figure() -> Figure {
+        let orbital = Orbital::new(QuantumNumbers::new(3, 1, 0).unwrap());
+        let mut sampler = Sampler::with_seed(5);
+        let particles = sampler.generate(&orbital, 20_000);
+        Figure::orbital(&orbital, &particles, &mut sampler)
+    }
+
+    #[test]
+    fn test_histograms_follow_the_exact_curves() {
+        let figure = figure();
+        let [radial, cdf, angular] = &figure.panels[..] else {
+            panic!("expected three panels");
+        };
+
+        // both the curve and the bars of a density integrate to about one
+        let area: f64 = radial.bars.iter().sum::<f64>() * radial.bar_width;
+        assert!((area - 1.0).abs() < 0.01, "{}", area);
+        let curve_area: f64 = radial
+            .curve
+            .windows(2)
+            .map(|w| 0.5 * (w[0].1 + w[1].1) * (w[1].0 - w[0].0))
+            .sum();
+        assert!((curve_area - 1.0).abs() < 0.01, "{}", curve_area);
+
+        // the cumulative bars end near one and the cdf table rises monotonically
+        assert!((cdf.bars.last().unwrap() - 1.0).abs() < 0.01);
+        assert!(cdf.curve.windows(2).all(|w| w[1].1 >= w[0].1));
+
+        // 3pz peaks along the axis: little weight near the equator, theta = 90
+        let equator = angular.bars[angular.bars.len() / 2];
+        assert!(equator < 0.2 * angular.bars.iter().fold(0.0, |a: f64, &b| a.max(b)));
+    }
+
+    #[test]
+    fn test_svg_and_png_output() {
+        let figure = figure();
+        let mut svg = Vec::new();
+        figure.write(&mut svg, PlotFormat::Svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.matches("<polyline").count() > 3);
+
+        let mut png = Vec::new();
+        figure.write(&mut png, PlotFormat::Png).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+
+        assert_eq!(PlotFormat::from_path("plot.PNG"), Some(PlotFormat::Png));
+        assert_eq!(PlotFormat::from_path("plot.pdf"), None);
+        assert_eq!(tick_label(0.25, 0.05, 5), "0.25");
+        assert_eq!(nice_step(9.0), 2.0);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::physics::{associated_legendre, Harmonic, Orbital, Particle};
+use nalgebra_glm as glm;
+use std::f64::consts::PI;
+
+// closed-form expectation values and nodes of a hydrogen-like orbital, with monte carlo
+// estimates of the same quantities from a sampled cloud as a check
+// with a = a0 (m_e / mu) / Z the textbook results are
+//   <r> = a/2 (3n^2 - l(l+1)),  <r^2> = a^2 n^2/2 (5n^2 + 1 - 3l(l+1)),  <1/r> = 1 / (n^2 a)
+// and the virial theorem gives E_n = <V>/2 = -Z <1/r> / 2 for the coulomb potential
+
+// grid points scanned for sign changes and maxima of the radial and angular functions
+const N_SCAN: usize = 4000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Properties {
+    pub mean_radius: f64,
+    pub mean_square_radius: f64,
+    pub mean_inverse_radius: f64,
+    // maximum of P(r) = r^2 R_nl^2
+    pub most_probable_radius: f64,
+    pub energy: f64,
+    // zeros of R_nl, n - l - 1 of them, in bohr
+    pub node_radii: Vec<f64>,
+    // polar angles of the l - |m| nodal cones (the xy plane for theta = pi/2), in radians
+    pub node_angles: Vec<f64>,
+    // |m| nodal planes through the z axis, present for real harmonics only; complex
+    // harmonics keep |psi|^2 independent of phi and only vanish on the z axis
+    pub node_planes: i32,
+}
+
+impl Properties {
+    pub fn analytic(orbital: &Orbital) -> Self {
+        let qn = orbital.quantum_numbers;
+        let (l, m_abs) = (qn.l(), qn.m().abs());
+        let n = qn.n() as f64;
+        let l_term = (l * (l + 1)) as f64;
+        let a = orbital.species.length_scale();
+        let r_max = orbital.r_max();
+        let dr = r_max / N_SCAN as f64;
+
+        let p = |r: f64| r * r * orbital.radial(r).powi(2);
+        let peak =
+            (1..N_SCAN)
+                .map(|i| i as f64 * dr)
+                .fold(dr, |best, r| if p(r) > p(best) { r } else { best });
+        let most_probable_radius = golden_maximum(p, peak - dr, peak + dr);
+
+        let node_radii = sign_changes(|r| orbital.radial(r), 0.0, r_max);
+        let node_angles = sign_changes(|t| associated_legendre(l, m_abs, t.cos()), 0.0, PI);
+
+        Self {
+            mean_radius: a / 2.0 * (3.0 * n * n - l_term),
+            mean_square_radius: a * a * n * n / 2.0 * (5.0 * n * n + 1.0 - 3.0 * l_term),
+            mean_inverse_radius: 1.0 / (n * n * a),
+            most_probable_radius,
+            energy: orbital.species.energy(qn.n()),
+            node_radii,
+            node_angles,
+            node_planes: match orbital.harmonic {
+                Harmonic::Real => m_abs,
+                Harmonic::Complex => 0,
+            },
+        }
+    }
+}
+
+// mean and standard error of the mean
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    pub error: f64,
+}
+
+impl Estimate {
+    fn from_values(values: impl Iterator<Item = f64>) -> Self {
+        let (count, sum, sum_sq) =
+            values.fold((0usize, 0.0, 0.0), |(c, s, q), v| (c + 1, s + v, q + v * v));
+        let count = count.max(1) as f64;
+        let mean = sum / count;
+        let variance = (sum_sq / count - mean * mean).max(0.0);
+        Self {
+            mean,
+            error: (variance / (count - 1.0).max(1.0)).sqrt(),
+        }
+    }
+
+    // distance from an exact value in standard errors
+    pub fn deviation(&self, exact: f64) -> f64 {
+        if self.error > 0.0 {
+            (self.mean - exact) / self.error
+        } else {
+            0.0
+        }
+    }
+}
+
+// the same quantities measured on a sampled cloud centred on the nucleus
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleProperties {
+    pub count: usize,
+    pub mean_radius: Estimate,
+    pub mean_square_radius: Estimate,
+    pub mean_inverse_radius: Estimate,
+    // centre of the fullest bin of a radial histogram out to r_max
+    pub most_probable_radius: f64,
+    // -Z <1/r> / 2 by the virial theorem
+    pub energy: Estimate,
+}
+
+impl SampleProperties {
+    pub fn from_particles(particles: &[Particle], z: i32, r_max: f64) -> Self {
+        let radii: Vec<f64> = particles.iter().map(|p| glm::length(&p.position)).collect();
+        let mean_inverse_radius =
+            Estimate::from_values(radii.iter().filter(|&&r| r > 0.0).map(|r| 1.0 / r));
+
+        // a bin holds about a hundred samples on average, but no fewer than 20 bins are used
+        let bins = (radii.len() / 100).clamp(20, 400);
+        let width = r_max / bins as f64;
+        let mut histogram = vec![0usize; bins];
+        for r in &radii {
+            if let Some(count) = histogram.get_mut((r / width) as usize) {
+                *count += 1;
+            }
+        }
+        let fullest = (0..bins).max_by_key(|&i| histogram[i]).unwrap_or(0);
+
+        let half_z = 0.5 * z as f64;
+        Self {
+            count: radii.len(),
+            mean_radius: Estimate::from_values(radii.iter().copied()),
+            mean_square_radius: Estimate::from_values(radii.iter().map(|r| r * r)),
+            mean_inverse_radius,
+            most_probable_radius: (fullest as f64 + 0.5) * width,
+            energy: Estimate {
+                mean: -half_z * mean_inverse_radius.mean,
+                error: half_z * mean_inverse_radius.error,
+            },
+        }
+    }
+}
+
+// console panel comparing the closed forms with the sample, one line per quantity
+pub fn report(orbital: &Orbital, particles: &[Particle]) -> Vec<String> {
+    let exact = Properties::analytic(orbital);
+    let sample = SampleProperties::from_particles(particles, orbital.species.z, orbital.r_max());
+    let qn = orbital.quantum_numbers;
+
+    let row = |name: &str, unit: &str, exact: f64, estimate: Estimate| {
+        format!(
+            "  {:<8} {:>14.6} {:>14.6} ± {:<10.2e} {:+6.2} sigma  {}",
+            name,
+            exact,
+            estimate.mean,
+            estimate.error,
+            estimate.deviation(exact),
+            unit
+        )
+    };
+    let plural = |count: usize, noun: &str| match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    };
+    // " at r = 2.0000 bohr", or nothing when there are no nodes to place
+    let at = |values: &[f64], name: &str, scale: f64, unit: &str| {
+        if values.is_empty() {
+            return String::new();
+        }
+        let values = values
+            .iter()
+            .map(|v| format!("{:.4}", v * scale))
+            .collect::<Vec<_>>();
+        format!(" at {} = {} {}", name, values.join(", "), unit)
+    };
+
+    let mut lines = vec![
+        format!(
+            "Properties of n={}, l={}, m={}: closed form vs {} samples",
+            qn.n(),
+            qn.l(),
+            qn.m(),
+            sample.count
+        ),
+        format!(
+            "  {:<8} {:>14} {:>14}   {:<10} {:>12}",
+            "", "closed form", "monte carlo", "std error", "deviation"
+        ),
+        row("<r>", "bohr", exact.mean_radius, sample.mean_radius),
+        row(
+            "<r^2>",
+            "bohr^2",
+            exact.mean_square_radius,
+            sample.mean_square_radius,
+        ),
+        row(
+            "<1/r>",
+            "1/bohr",
+            exact.mean_inverse_radius,
+            sample.mean_inverse_radius,
+        ),
+        row("E_n", "hartree", exact.energy, sample.energy),
+        format!(
+            "  {:<8} {:>14.6} {:>14.6} (histogram peak)  bohr",
+            "r_mp", exact.most_probable_radius, sample.most_probable_radius
+        ),
+        format!(
+            "  Radial nodes: {}{}",
+            exact.node_radii.len(),
+            at(&exact.node_radii, "r", 1.0, "bohr")
+        ),
+        format!(
+            "  Angular nodes: {} ({}{}, {} through z)",
+            qn.l(),
+            plural(exact.node_angles.len(), "cone"),
+            at(&exact.node_angles, "theta", 180.0 / PI, "deg"),
+            plural(qn.m().unsigned_abs() as usize, "plane")
+        ),
+    ];
+    if orbital.harmonic == Harmonic::Complex && qn.m() != 0 {
+        lines.push(
+            "  The planes belong to the real orbitals; with e^(i m phi) |psi|^2 only vanishes \
+             on the z axis."
+                .to_string(),
+        );
+    }
+    lines
+}
+
+// zeros of f strictly inside (start, end), located by bisection of the sign changes on a
+// grid that skips the endpoints, where r^l and sin^|m| theta vanish
+fn sign_changes(f: impl Fn(f64) -> f64, start: f64, end: f64) -> Vec<f64> {
+    let step = (end - start) / N_SCAN as f64;
+    let mut roots = Vec::new();
+    let mut previous = (start + 0.5 * step, f(start + 0.5 * step));
+    for i in 1..N_SCAN {
+        let x = start + (i as f64 + 0.5) * step;
+        let value = f(x);
+        if value == 0.0 || value.signum() != previous.1.signum() {
+            let (mut lo, mut hi) = (previous.0, x);
+            for _ in 0..60 {
+                let mid = 0.5 * (lo + hi);
+                if f(mid).signum() == f(lo).signum() {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            roots.push(0.5 * (lo + hi));
+        }
+        previous = (x, value);
+    }
+    roots
+}
+
+// maximum of a unimodal f on [lo, hi] by golden-section search
+fn golden_maximum(f: impl Fn(f64) -> f64, mut lo: f64, mut hi: f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    lo = lo.max(0.0);
+    for _ in 0..100 {
+        let a = hi - ratio * (hi - lo);
+        let b = lo + ratio * (hi - lo);
+        if f(a) < f(b) {
+            lo = a;
+        } else {
+            hi = b;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{self, QuantumNumbers, Sampler};
+
+    fn orbital(n: i32, l: i32, m: i32) -> Orbital {
+        Orbital::new(QuantumNumbers::new(n, l, m).unwrap())
+    }
+
+    #[test]
+    fn test_closed_forms_match_integrals() {
+        for (n, l) in [(1, 0), (2, 1), (3, 0), (4, 2)] {
+            let orbital = orbital(n, l, 0);
+            let props = Properties::analytic(&orbital);
+            let moment = |k: i32| {
+                let dr = orbital.r_max() / 100_000.0;
+                (1..100_000)
+                    .map(|i| {
+                        let r = i as f64 * dr;
+                        r.powi(2 + k) * orbital.radial(r).powi(2) * dr
+                    })
+                    .sum::<f64>()
+            };
+            assert!((moment(1) - props.mean_radius).abs() < 1e-4 * props.mean_radius);
+            assert!((moment(2) - props.mean_square_radius).abs() < 1e-3 * props.mean_square_radius);
+            assert!((moment(-1) - props.mean_inverse_radius).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_nodes_and_most_probable_radius() {
+        // 2s: node at 2a, peak at (3 + sqrt 5) a; l = n - 1 peaks at n^2 a
+        let props = Properties::analytic(&orbital(2, 0, 0));
+        assert_eq!(props.node_radii.len(), 1);
+        assert!((props.node_radii[0] - 2.0).abs() < 1e-9);
+        assert!((props.most_probable_radius - (3.0 + 5f64.sqrt())).abs() < 1e-6);
+        assert!((Properties::analytic(&orbital(3, 2, 1)).most_probable_radius - 9.0).abs() < 1e-6);
+
+        // 3p: node at 6a; 3dz2: cones at cos^2 theta = 1/3
+        assert!((Properties::analytic(&orbital(3, 1, 0)).node_radii[0] - 6.0).abs() < 1e-9);
+        let dz2 = Properties::analytic(&orbital(3, 2, 0));
+        let cone = (1.0 / 3f64.sqrt()).acos();
+        assert_eq!(dz2.node_angles.len(), 2);
+        assert!((dz2.node_angles[0] - cone).abs() < 1e-9);
+        assert!((dz2.node_angles[1] - (PI - cone)).abs() < 1e-9);
+
+        // 4f(xyz) style real harmonic: l - |m| = 1 cone (the xy plane) and 2 planes
+        let real = Properties::analytic(&orbital(4, 3, -2).with_harmonic(Harmonic::Real));
+        assert_eq!((real.node_angles.len(), real.node_planes), (1, 2));
+        assert!(real.node_radii.is_empty());
+    }
+
+    #[test]
+    fn test_sample_agrees_with_closed_forms() {
+        let orbital = orbital(3, 1, 1).with_species(physics::SPECIES_PRESETS[2]);
+        let mut sampler = Sampler::with_seed(9);
+        let particles = sampler.generate(&orbital, 50_000);
+        let exact = Properties::analytic(&orbital);
+        let sample = SampleProperties::from_particles(&particles, 2, orbital.r_max());
+
+        assert!(sample.mean_radius.deviation(exact.mean_radius).abs() < 4.0);
+        assert!(
+            sample
+                .mean_square_radius
+                .deviation(exact.mean_square_radius)
+                .abs()
+                < 4.0
+        );
+        assert!(
+            sample
+                .mean_inverse_radius
+                .deviation(exact.mean_inverse_radius)
+                .abs()
+                < 4.0
+        );
+        assert!(sample.energy.deviation(exact.energy).abs() < 4.0);
+        assert!((sample.most_probable_radius - exact.most_probable_radius).abs() < 0.5);
+        assert_eq!(report(&orbital, &particles).len(), 10);
+    }
+
+    #[test]
+    fn test_report_node_lines() {
+        let mut sampler = Sampler::with_seed(3);
+        let mut nodes = |orbital: &Orbital| {
+            let particles = sampler.generate(orbital, 1000);
+            report(orbital, &particles)[7..9].to_vec()
+        };
+        assert_eq!(
+            nodes(&orbital(1, 0, 0)),
+            [
+                "  Radial nodes: 0",
+                "  Angular nodes: 0 (0 cones, 0 planes through z)"
+            ]
+        );
+        assert_eq!(
+            nodes(&orbital(2, 1, 1)),
+            [
+                "  Radial nodes: 0",
+                "  Angular nodes: 1 (0 cones, 1 plane through z)"
+            ]
+        );
+        assert_eq!(
+            nodes(&orbital(2, 0, 0)),
+            [
+                "  Radial nodes: 1 at r = 2.0000 bohr",
+                "  Angular nodes: 0 (0 cones, 0 planes through z)"
+            ]
+        );
+        assert_eq!(
+            nodes(&orbital(2, 1, 0))[1],
+            "  Angular nodes: 1 (1 cone at theta = 90.0000 deg, 0 planes through z)"
+        );
+    }
+}
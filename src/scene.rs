@@ -0,0 +1,853 @@
+// what the viewer is showing, one variant per kind of state or cube file
+// each scene knows how to sample itself, which markers to draw and how to frame itself
+
+use atom_simulator::cube::CubeFile;
+use atom_simulator::dirac::DiracState;
+use atom_simulator::hybrid::HybridSet;
+use atom_simulator::isosurface::{self, IsoLevel, Mesh};
+use atom_simulator::molecule::MolecularOrbital;
+use atom_simulator::momentum::MomentumOrbital;
+use atom_simulator::physics;
+use atom_simulator::properties;
+use atom_simulator::slater::{self, Atom, Occupation, SlaterOrbital};
+use atom_simulator::spin::{self, SpinOrbital};
+use atom_simulator::stark::{self, StarkState};
+use atom_simulator::superposition::{Ensemble, Superposition, ATOMIC_TIME_FS};
+use nalgebra_glm as glm;
+
+pub enum Scene {
+    Orbital(physics::Orbital),
+    // the same orbital as a momentum distribution, drawn with px, py and pz axes
+    Momentum(MomentumOrbital),
+    Superposition {
+        state: Superposition,
+        // proposal pool re-weighted at every frame, built by the first sample call
+        ensemble: Option<Ensemble>,
+        // atomic units
+        time: f64,
+    },
+    // one member of an equivalent hybrid set, stationary since its terms share n
+    Hybrid {
+        set: HybridSet,
+        index: usize,
+        state: Superposition,
+    },
+    Molecule(MolecularOrbital),
+    Atom {
+        atom: Atom,
+        view: AtomView,
+    },
+    // a parabolic state of one shell, polarized by a uniform field
+    Stark(StarkState),
+    // |n l j mj>, drawn as its spin-up and spin-down components
+    Spin(SpinOrbital),
+    // exact dirac-coulomb state on the left, the matching schrodinger orbital on the right
+    Dirac(DiracState),
+    Cube {
+        name: String,
+        cube: CubeFile,
+    },
+}
+
+// what part of a many-electron atom is drawn
+pub enum AtomView {
+    // one slater-type orbital, e.g. 2px of carbon
+    Orbital(SlaterOrbital),
+    // the spherically averaged density of the valence subshells
+    Valence,
+    // the same for every occupied subshell, core included
+    Configuration,
+}
+
+impl AtomView {
+    // occupations drawn by the shell views, None for a single orbital
+    pub fn occupations(&self, atom: &Atom) -> Option<Vec<Occupation>> {
+        match self {
+            AtomView::Orbital(_) => None,
+            AtomView::Valence => Some(atom.valence()),
+            AtomView::Configuration => Some(atom.occupations.clone()),
+        }
+    }
+}
+
+// wall-clock seconds for the slowest quantum beat of an animated superposition
+const BEAT_SECONDS: f64 = 4.0;
+
+// a larger opaque sphere drawn alongside the particles, e.g. a nucleus from a cube header
+pub struct Marker {
+    pub position: glm::DVec3,
+    pub color: glm::Vec4,
+    // radius relative to a particle sphere
+    pub size: f32,
+}
+
+impl Scene {
+    // superpositions draw a fresh proposal pool sized so that about num_particles survive
+    pub fn sample(
+        &mut self,
+        sampler: &mut physics::Sampler,
+        num_particles: usize,
+    ) -> Vec<physics::Particle> {
+        match self {
+            Scene::Orbital(orbital) => sampler.generate(orbital, num_particles),
+            Scene::Momentum(mo) => mo.sample(sampler, num_particles),
+            Scene::Superposition {
+                state,
+                ensemble,
+                time,
+            } => {
+                let proposals = num_particles * state.terms().len();
+                let pool = ensemble.insert(Ensemble::new(state, sampler, proposals));
+                pool.particles(*time, sampler.color_mode)
+            }
+            Scene::Hybrid { state, .. } => state.sample(sampler, 0.0, num_particles),
+            Scene::Molecule(mo) => mo.sample(sampler, num_particles),
+            // orbitals and configurations are checked to be bound when the scene is built
+            Scene::Atom { atom, view } => match view {
+                AtomView::Orbital(orbital) => {
+                    orbital.sample(sampler, num_particles).unwrap_or_default()
+                }
+                _ => {
+                    let occupations = view.occupations(atom).unwrap_or_default();
+                    atom.sample_shells(&occupations, sampler, num_particles)
+                        .unwrap_or_default()
+                }
+            },
+            Scene::Stark(state) => state.sample(sampler, num_particles),
+            Scene::Spin(state) => state.sample(sampler, num_particles),
+            Scene::Dirac(state) => state.sample_side_by_side(sampler, num_particles),
+            Scene::Cube { cube, .. } => cube.sample(sampler, num_particles),
+        }
+    }
+
+    // the analytic orbital behind a position or momentum scene
+    pub fn orbital(&self) -> Option<physics::Orbital> {
+        match self {
+            Scene::Orbital(orbital) => Some(*orbital),
+            Scene::Momentum(mo) => Some(*mo.orbital()),
+            _ => None,
+        }
+    }
+
+    // swaps in another orbital, keeping position or momentum space; ignored by other scenes
+    pub fn replace_orbital(&mut self, orbital: physics::Orbital) {
+        match self {
+            Scene::Orbital(current) => *current = orbital,
+            Scene::Momentum(mo) => *mo = MomentumOrbital::new(orbital),
+            _ => {}
+        }
+    }
+
+    // switches an orbital between position and momentum space, false for other scenes
+    pub fn toggle_momentum(&mut self) -> bool {
+        *self = match self {
+            Scene::Orbital(orbital) => Scene::Momentum(MomentumOrbital::new(*orbital)),
+            Scene::Momentum(mo) => Scene::Orbital(*mo.orbital()),
+            _ => return false,
+        };
+        true
+    }
+
+    // switches an atom between its valence and whole-configuration densities, false for
+    // other scenes; a single orbital goes to the valence view
+    pub fn next_atom_view(&mut self) -> bool {
+        let Scene::Atom { view, .. } = self else {
+            return false;
+        };
+        *view = match view {
+            AtomView::Valence => AtomView::Configuration,
+            _ => AtomView::Valence,
+        };
+        true
+    }
+
+    // steps a stark scene through the k values of its manifold and a spinor or dirac state
+    // through mj, false for other scenes
+    pub fn step_sublevel(&mut self, delta: i32) -> bool {
+        match self {
+            Scene::Stark(state) => *state = state.step(delta),
+            Scene::Spin(state) => *state = state.step_mj(delta),
+            Scene::Dirac(state) => *state = state.step_mj(delta),
+            _ => return false,
+        }
+        true
+    }
+
+    // switches a spinor or dirac state between j = l + 1/2 and l - 1/2, false for s states
+    // and other scenes
+    pub fn other_j(&mut self) -> bool {
+        match self {
+            Scene::Spin(state) => match state.other_j() {
+                Some(other) => *state = other,
+                None => return false,
+            },
+            Scene::Dirac(state) => match state.other_j() {
+                Some(other) => *state = other,
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // switches a hybrid scene to the next member of its set, false for other scenes
+    pub fn next_hybrid(&mut self) -> bool {
+        let Scene::Hybrid { set, index, state } = self else {
+            return false;
+        };
+        *index = (*index + 1) % set.count();
+        *state = set.hybrid(*index, state.species());
+        true
+    }
+
+    // superpositions with at least one energy gap change in time
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Scene::Superposition { state, .. } => !state.beat_periods().is_empty(),
+            _ => false,
+        }
+    }
+
+    // moves an animated scene forward by dt wall-clock seconds and returns the new cloud,
+    // None for static scenes
+    pub fn advance(
+        &mut self,
+        dt: f64,
+        color_mode: physics::ColorMode,
+    ) -> Option<Vec<physics::Particle>> {
+        let Scene::Superposition {
+            state,
+            ensemble: Some(ensemble),
+            time,
+        } = self
+        else {
+            return None;
+        };
+        let period = *state.beat_periods().first()?;
+
+        *time += dt * period / BEAT_SECONDS;
+        Some(ensemble.particles(*time, color_mode))
+    }
+
+    // isosurface colored by phase
+    // orbitals are evaluated on a grid of `points` per axis, cube files use their own grid
+    pub fn isosurface(&self, level: IsoLevel, points: usize) -> Mesh {
+        match self {
+            Scene::Orbital(orbital) => isosurface::orbital_isosurface(orbital, level, points),
+            Scene::Momentum(mo) => {
+                let cube = CubeFile::from_fn(mo.p_max(), points, |p| mo.density(p));
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Superposition { state, time, .. } => {
+                superposition_isosurface(state, *time, level, points)
+            }
+            Scene::Hybrid { state, .. } => superposition_isosurface(state, 0.0, level, points),
+            Scene::Molecule(mo) => {
+                let cube = CubeFile::from_fn(mo.extent(), points, |p| mo.density(p));
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Atom { atom, view } => {
+                let cube = match view {
+                    // s functions are positive everywhere, so their grid holds the density
+                    AtomView::Orbital(orbital) if orbital.subshell.l == 0 => {
+                        CubeFile::from_fn(orbital.r_max(), points, |p| orbital.density_at(p))
+                    }
+                    AtomView::Orbital(orbital) => {
+                        CubeFile::from_fn(orbital.r_max(), points, |p| orbital.psi_at(p).re)
+                    }
+                    _ => {
+                        let occupations = view.occupations(atom).unwrap_or_default();
+                        let half_width = occupations
+                            .iter()
+                            .filter_map(|o| atom.orbital(o.subshell, 0).ok())
+                            .map(|orbital| orbital.r_max())
+                            .fold(0.0, f64::max);
+                        CubeFile::from_fn(half_width, points, |p| {
+                            atom.shell_density(&occupations, p)
+                        })
+                    }
+                };
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Stark(state) => {
+                let half_width =
+                    CubeFile::shell_half_width(state.n(), state.species().length_scale());
+                let cube = CubeFile::from_fn(half_width, points, |p| state.density(p));
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Spin(state) => {
+                let half_width =
+                    CubeFile::shell_half_width(state.n(), state.species().length_scale());
+                let cube = CubeFile::from_fn(half_width, points, |p| state.density(p));
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Dirac(state) => {
+                let half_width = state.comparison_offset()
+                    + CubeFile::shell_half_width(state.n(), state.species().length_scale());
+                let cube = CubeFile::from_fn(half_width, points, |p| state.side_by_side_density(p));
+                isosurface::cube_isosurface(&cube, level)
+            }
+            Scene::Cube { cube, .. } => isosurface::cube_isosurface(cube, level),
+        }
+    }
+
+    pub fn markers(&self) -> Vec<Marker> {
+        match self {
+            Scene::Orbital(_)
+            | Scene::Superposition { .. }
+            | Scene::Hybrid { .. }
+            | Scene::Atom { .. }
+            | Scene::Stark(_)
+            | Scene::Spin(_) => Vec::new(),
+            Scene::Momentum(mo) => momentum_axes(mo),
+            Scene::Molecule(mo) => mo
+                .nuclei()
+                .iter()
+                .map(|nucleus| Marker {
+                    position: nucleus.position,
+                    color: element_color(nucleus.z),
+                    size: 6.0,
+                })
+                .collect(),
+            // the two nuclei of the comparison
+            Scene::Dirac(state) => [-1.0, 1.0]
+                .iter()
+                .map(|side| Marker {
+                    position: glm::vec3(side * state.comparison_offset(), 0.0, 0.0),
+                    color: element_color(state.z()),
+                    size: 6.0,
+                })
+                .collect(),
+            Scene::Cube { cube, .. } => cube
+                .atoms
+                .iter()
+                .map(|atom| Marker {
+                    position: physics::swap_yz(&atom.position),
+                    color: element_color(atom.number),
+                    size: 6.0,
+                })
+                .collect(),
+        }
+    }
+
+    // world point drawn at the origin and the length of one display unit in bohr
+    // orbitals use the species length scale so every system fills the view alike,
+    // cube files keep bohr and are centred on their grid box
+    pub fn frame(&self) -> (glm::DVec3, f64) {
+        match self {
+            Scene::Orbital(orbital) => (glm::vec3(0.0, 0.0, 0.0), orbital.species.length_scale()),
+            // momenta shrink as 1/n, so the unit does too
+            Scene::Momentum(mo) => (glm::vec3(0.0, 0.0, 0.0), mo.display_unit()),
+            Scene::Superposition { state, .. } | Scene::Hybrid { state, .. } => {
+                (glm::vec3(0.0, 0.0, 0.0), state.species().length_scale())
+            }
+            Scene::Molecule(mo) => (mo.centroid(), mo.species().length_scale()),
+            // slater orbitals are scaled like a hydrogenic orbital of charge Z_eff
+            Scene::Atom { atom, view } => {
+                let effective_charge = match view {
+                    AtomView::Orbital(orbital) => orbital.effective_charge,
+                    _ => view
+                        .occupations(atom)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|o| atom.effective_charge(o.subshell))
+                        .fold(f64::INFINITY, f64::min),
+                };
+                (glm::vec3(0.0, 0.0, 0.0), 1.0 / effective_charge)
+            }
+            Scene::Stark(state) => (glm::vec3(0.0, 0.0, 0.0), state.species().length_scale()),
+            Scene::Spin(state) => (glm::vec3(0.0, 0.0, 0.0), state.species().length_scale()),
+            Scene::Dirac(state) => (glm::vec3(0.0, 0.0, 0.0), state.species().length_scale()),
+            Scene::Cube { cube, .. } => (physics::swap_yz(&cube.center()), 1.0),
+        }
+    }
+
+    // short name used for export file names
+    pub fn file_stem(&self) -> String {
+        match self {
+            Scene::Orbital(orbital) => {
+                let qn = orbital.quantum_numbers;
+                orbital
+                    .label()
+                    .unwrap_or_else(|| format!("n{}_l{}_m{}", qn.n(), qn.l(), qn.m()))
+            }
+            Scene::Momentum(mo) => {
+                format!("momentum_{}", Scene::Orbital(*mo.orbital()).file_stem())
+            }
+            Scene::Superposition { .. } => "superposition".to_string(),
+            Scene::Hybrid { set, index, .. } => format!("{}_{}", set.name(), index + 1),
+            Scene::Molecule(mo) if mo.terms().len() == 2 && mo.nuclei().len() == 2 => mo
+                .label()
+                .replace('σ', "sigma")
+                .replace('π', "pi")
+                .replace('*', "_star"),
+            Scene::Molecule(_) => "molecule".to_string(),
+            Scene::Atom { atom, view } => match view {
+                AtomView::Orbital(orbital) => format!("{}_{}", atom.symbol(), orbital.label()),
+                AtomView::Valence => format!("{}_valence", atom.symbol()),
+                AtomView::Configuration => format!("{}_configuration", atom.symbol()),
+            },
+            Scene::Stark(state) => {
+                format!("stark_n{}_k{}_m{}", state.n(), state.k(), state.m())
+            }
+            Scene::Spin(state) => state.label().replace(", mj=", "_mj").replace('/', "-"),
+            Scene::Dirac(state) => format!(
+                "dirac_Z{}_{}",
+                state.z(),
+                state
+                    .pauli()
+                    .label()
+                    .replace(", mj=", "_mj")
+                    .replace('/', "-")
+            ),
+            Scene::Cube { name, .. } => name.clone(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Scene::Orbital(orbital) => {
+                let qn = orbital.quantum_numbers;
+                let (n, l, m) = (qn.n(), qn.l(), qn.m());
+                let real = orbital.harmonic == physics::Harmonic::Real;
+                match orbital.label() {
+                    Some(label) if real => {
+                        format!("Atom Simulator - {} (n={}, l={}, m={})", label, n, l, m)
+                    }
+                    _ if real => format!("Atom Simulator - n={}, l={}, m={} (real)", n, l, m),
+                    _ => format!("Atom Simulator - n={}, l={}, m={}", n, l, m),
+                }
+            }
+            Scene::Momentum(mo) => format!(
+                "{} in momentum space",
+                Scene::Orbital(*mo.orbital()).title()
+            ),
+            Scene::Superposition { state, time, .. } => {
+                format!("Atom Simulator - {}, t = {:.2} a.u.", state.label(), time)
+            }
+            Scene::Hybrid { set, index, .. } => {
+                format!("Atom Simulator - {} hybrid", set.label(*index))
+            }
+            Scene::Molecule(mo) => match mo.nuclei().as_slice() {
+                [a, b] => format!(
+                    "Atom Simulator - {}, R = {:.2} bohr",
+                    mo.label(),
+                    glm::distance(&a.position, &b.position)
+                ),
+                nuclei => format!("Atom Simulator - {} ({} nuclei)", mo.label(), nuclei.len()),
+            },
+            Scene::Atom { atom, view } => match view {
+                AtomView::Orbital(orbital) => format!(
+                    "Atom Simulator - {} {} (Slater, Z_eff = {:.2})",
+                    atom.symbol(),
+                    orbital.label(),
+                    orbital.effective_charge
+                ),
+                AtomView::Valence => {
+                    format!("Atom Simulator - {} valence density", atom.symbol())
+                }
+                AtomView::Configuration => format!(
+                    "Atom Simulator - {} {}",
+                    atom.symbol(),
+                    atom.configuration()
+                ),
+            },
+            Scene::Stark(state) => format!(
+                "Atom Simulator - Stark {}, F = {:.2e} a.u.",
+                state.label(),
+                state.field()
+            ),
+            Scene::Spin(state) => format!(
+                "Atom Simulator - {}, B = {} T (spin up red, down green)",
+                state.label(),
+                state.field()
+            ),
+            Scene::Dirac(state) => format!(
+                "Atom Simulator - Dirac {}, Z = {} (left) vs Schrödinger (right)",
+                state.label(),
+                state.z()
+            ),
+            Scene::Cube { name, .. } => format!("Atom Simulator - {}.cube", name),
+        }
+    }
+
+    // comment line for exported files
+    pub fn description(&self) -> String {
+        match self {
+            Scene::Orbital(orbital) => format!("{} ({})", self.title(), orbital.species.name),
+            Scene::Momentum(mo) => format!(
+                "{} ({}, p in hbar/bohr)",
+                self.title(),
+                mo.orbital().species.name
+            ),
+            Scene::Superposition { state, .. } | Scene::Hybrid { state, .. } => {
+                format!("{} ({})", self.title(), state.species().name)
+            }
+            Scene::Molecule(mo) => format!("{} ({})", self.title(), mo.species().name),
+            Scene::Atom { atom, .. } => format!("{} [{}]", self.title(), atom.configuration()),
+            Scene::Stark(state) => format!("{} ({})", self.title(), state.species().name),
+            Scene::Spin(state) => format!("{} ({})", self.title(), state.species().name),
+            Scene::Dirac(state) => format!("{} ({})", self.title(), state.species().name),
+            Scene::Cube { cube, .. } => format!("{} {}", self.title(), cube.comments[0].trim()),
+        }
+    }
+
+    // closed-form expectation values and nodes of an orbital, checked against the sampled
+    // cloud; other scenes print nothing
+    pub fn print_properties(&self, particles: &[physics::Particle]) {
+        if let Scene::Orbital(orbital) = self {
+            for line in properties::report(orbital, particles) {
+                println!("{}", line);
+            }
+        }
+    }
+
+    // reports the physical scales that the display units hide
+    pub fn print_summary(&self) {
+        match self {
+            Scene::Orbital(orbital) => {
+                let species = orbital.species;
+                let n = orbital.quantum_numbers.n();
+                let energy = species.energy(n);
+
+                println!("Species: {} (Z = {})", species.name, species.z);
+                println!(
+                    "Display unit: a = {:.6e} bohr, E_{} = {:.6} hartree ({:.4} eV)",
+                    species.length_scale(),
+                    n,
+                    energy,
+                    energy * physics::HARTREE_EV
+                );
+            }
+            Scene::Momentum(mo) => {
+                let species = mo.orbital().species;
+                let n = mo.orbital().quantum_numbers.n();
+                let p0 = mo.momentum_scale();
+                let kinetic = mo.mean_square_momentum() / (2.0 * species.reduced_mass());
+
+                println!("Species: {} (Z = {})", species.name, species.z);
+                println!(
+                    "Display unit: p0 / 4n = {:.6e} hbar/bohr, with p0 = hbar / a = {:.6} hbar/bohr",
+                    mo.display_unit(),
+                    p0
+                );
+                println!(
+                    "<p> = {:.6} hbar/bohr, sqrt<p^2> = p0 / {} = {:.6} hbar/bohr",
+                    mo.mean_momentum(),
+                    n,
+                    mo.mean_square_momentum().sqrt()
+                );
+                println!(
+                    "<T> = <p^2> / 2 mu = {:.6} hartree = -E_{} (virial theorem)",
+                    kinetic, n
+                );
+                println!(
+                    "Compton profile J(0) = {:.6} bohr/hbar, J(p0 / n) = {:.6} bohr/hbar",
+                    mo.compton_profile(0.0),
+                    mo.compton_profile(p0 / n as f64)
+                );
+            }
+            Scene::Superposition { state, time, .. } => {
+                let species = state.species();
+                println!("Species: {} (Z = {})", species.name, species.z);
+                println!("State: {}", state.label());
+                println!(
+                    "<E> = {:.6} hartree ({:.4} eV), t = {:.3} a.u. ({:.4} fs)",
+                    state.mean_energy(),
+                    state.mean_energy() * physics::HARTREE_EV,
+                    time,
+                    time * ATOMIC_TIME_FS
+                );
+                match state.beat_periods().as_slice() {
+                    [] => println!("No quantum beats: all terms share one energy"),
+                    periods => {
+                        let text: Vec<String> = periods
+                            .iter()
+                            .map(|p| format!("{:.3} a.u. ({:.4} fs)", p, p * ATOMIC_TIME_FS))
+                            .collect();
+                        println!("Beat periods: {}", text.join(", "));
+                    }
+                }
+            }
+            Scene::Hybrid { set, index, state } => {
+                let species = state.species();
+                let d = set.directions()[*index];
+                println!("Species: {} (Z = {})", species.name, species.z);
+                println!("{} = {}", set.label(*index), state.label());
+                println!(
+                    "Lobe along ({:.3}, {:.3}, {:.3}) in orbital axes, E_{} = {:.6} hartree",
+                    d.x,
+                    d.y,
+                    d.z,
+                    set.shell(),
+                    species.energy(set.shell())
+                );
+            }
+            Scene::Molecule(mo) => {
+                let species = mo.species();
+                println!(
+                    "Species: {} (Z = {}) on every centre",
+                    species.name, species.z
+                );
+                for (i, nucleus) in mo.nuclei().iter().enumerate() {
+                    // orbital axes, z being the molecular axis
+                    let p = nucleus.position;
+                    println!(
+                        "Nucleus {} at ({:.3}, {:.3}, {:.3}) bohr",
+                        i + 1,
+                        p.x,
+                        p.z,
+                        p.y
+                    );
+                }
+                let terms = mo.terms();
+                for i in 0..terms.len() {
+                    for j in (0..i).filter(|&j| terms[i].centre != terms[j].centre) {
+                        let s = mo.overlap(i, j);
+                        if s.norm() > 1e-3 {
+                            println!("Overlap S_{}{} = {:.4}", j + 1, i + 1, s.re);
+                        }
+                    }
+                }
+            }
+            Scene::Atom { atom, view } => {
+                let charge = match atom.charge() {
+                    0 => String::new(),
+                    1 => "+".to_string(),
+                    -1 => "-".to_string(),
+                    q => format!("{}{}", q.abs(), if q > 0 { '+' } else { '-' }),
+                };
+                println!(
+                    "{}{} (Z = {}): {}",
+                    atom.symbol(),
+                    charge,
+                    atom.z,
+                    atom.configuration()
+                );
+                println!("  subshell   Z_eff      n*    zeta   <r> (bohr)");
+                for o in &atom.occupations {
+                    let Ok(orbital) = atom.orbital(o.subshell, 0) else {
+                        continue;
+                    };
+                    println!(
+                        "  {:<4}{:>6}{:>8.2}{:>8.1}{:>8.3}{:>10.3}",
+                        o.subshell.label(),
+                        format!("({})", o.electrons),
+                        orbital.effective_charge,
+                        orbital.n_star,
+                        orbital.zeta,
+                        orbital.mean_radius()
+                    );
+                }
+                if let Some(occupations) = view.occupations(atom) {
+                    println!("Subshell colors (phase coloring, C):");
+                    for (i, o) in occupations.iter().enumerate() {
+                        let c = slater::subshell_color(i) * 255.0;
+                        // 24-bit ansi swatch, ignored by terminals without color
+                        println!(
+                            "  \x1b[38;2;{};{};{}m\u{2588}\u{2588}\x1b[0m {} ({} e)",
+                            c.x as u8,
+                            c.y as u8,
+                            c.z as u8,
+                            o.subshell.label(),
+                            o.electrons
+                        );
+                    }
+                }
+            }
+            Scene::Stark(state) => {
+                let species = state.species();
+                let axis = state.axis();
+                let n = state.n();
+                println!("Species: {} (Z = {})", species.name, species.z);
+                println!("Stark state {} = {}", state.label(), state.state().label());
+                println!(
+                    "Field F = {:.3e} a.u. ({:.3e} V/m) along ({:.3}, {:.3}, {:.3}) in orbital axes",
+                    state.field(),
+                    state.field() * stark::FIELD_AU_V_PER_M,
+                    axis.x,
+                    axis.y,
+                    axis.z
+                );
+                println!(
+                    "Dipole <z> = {:.4} bohr, E_{} = {:.6} hartree, first-order shift {:+.6e} hartree \
+                     ({:+.4e} eV)",
+                    state.dipole(),
+                    n,
+                    species.energy(n),
+                    state.energy_shift(),
+                    state.energy_shift() * physics::HARTREE_EV
+                );
+                let shifts: Vec<String> = state
+                    .manifold_shifts()
+                    .iter()
+                    .map(|(k, shift)| format!("k={}: {:+.3e}", k, shift))
+                    .collect();
+                println!(
+                    "Shifts for m={} in hartree: {}",
+                    state.m(),
+                    shifts.join(", ")
+                );
+                if state.field() > 0.1 * state.ionization_field() {
+                    println!(
+                        "Note: F is beyond a tenth of the ionization field {:.3e} a.u., so first \
+                         order is only qualitative.",
+                        state.ionization_field()
+                    );
+                }
+            }
+            Scene::Spin(state) => {
+                let species = state.species();
+                let n = state.n();
+                println!("Species: {} (Z = {})", species.name, species.z);
+                let parts: Vec<String> = state
+                    .components()
+                    .iter()
+                    .zip(["up", "down"])
+                    .filter_map(|((c, orbital), spin)| {
+                        let m = orbital.as_ref()?.quantum_numbers.m();
+                        Some(format!("{:+.4} |m={}, {}>", c, m, spin))
+                    })
+                    .collect();
+                println!("{} = {}", state.label(), parts.join(" "));
+                println!(
+                    "Spin up {:.1}%, spin down {:.1}%",
+                    100.0 * state.spin_up_probability(),
+                    100.0 * (1.0 - state.spin_up_probability())
+                );
+                println!(
+                    "E_{} = {:.8} hartree, fine structure {:+.4e} hartree ({:+.4e} eV)",
+                    n,
+                    species.energy(n),
+                    state.fine_structure_shift(),
+                    state.fine_structure_shift() * physics::HARTREE_EV
+                );
+                println!(
+                    "Zeeman at B = {} T: g_j = {:.4}, shift {:+.4e} hartree ({:+.4e} eV)",
+                    state.field(),
+                    state.lande_g(),
+                    state.zeeman_shift(),
+                    state.zeeman_shift() * physics::HARTREE_EV
+                );
+                println!(
+                    "Total E = {:.8} hartree ({:.6} eV)",
+                    state.energy(),
+                    state.energy() * physics::HARTREE_EV
+                );
+                let splitting = state.fine_structure_splitting();
+                let zeeman_scale = spin::BOHR_MAGNETON * state.field().abs() / spin::TESLA_AU;
+                if splitting > 0.0 && zeeman_scale > 0.1 * splitting {
+                    println!(
+                        "Note: mu_B B is {:.2} of the fine-structure splitting, so the weak-field \
+                         (anomalous) Zeeman shift is only approximate; Paschen-Back sets in above it.",
+                        zeeman_scale / splitting
+                    );
+                }
+            }
+            Scene::Dirac(state) => {
+                let pauli = state.schrodinger_energy() + state.pauli().fine_structure_shift();
+                println!("Species: {} (Z = {})", state.species().name, state.z());
+                println!(
+                    "{}: gamma = {:.6}, large component {:.3}%, small component {:.3}%",
+                    state.label(),
+                    state.gamma(),
+                    100.0 * state.large_fraction(),
+                    100.0 * (1.0 - state.large_fraction())
+                );
+                println!(
+                    "E_Dirac = {:.8} hartree ({:.6} eV), E_Schrödinger = {:.8} hartree, \
+                     with first-order fine structure {:.8} hartree",
+                    state.binding_energy(),
+                    state.binding_energy() * physics::HARTREE_EV,
+                    state.schrodinger_energy(),
+                    pauli
+                );
+                println!(
+                    "<r> = {:.6} bohr (Dirac) vs {:.6} bohr (Schrödinger), ratio {:.4}",
+                    state.mean_radius(),
+                    state.schrodinger_mean_radius(),
+                    state.mean_radius() / state.schrodinger_mean_radius()
+                );
+            }
+            Scene::Cube { cube, .. } => {
+                for comment in &cube.comments {
+                    println!("  {}", comment.trim());
+                }
+                println!(
+                    "Grid: {} x {} x {} voxels, spacing {:.4} angstrom, {} atoms",
+                    cube.shape[0],
+                    cube.shape[1],
+                    cube.shape[2],
+                    cube.spacing_angstrom(),
+                    cube.atoms.len()
+                );
+                println!(
+                    "Sampling {}, display unit: 1 bohr",
+                    if cube.is_signed() {
+                        "|psi|^2 of a signed orbital"
+                    } else {
+                        "the density as stored"
+                    }
+                );
+            }
+        }
+    }
+}
+
+// density isosurface of a superposition at time t, on a box sized by its largest n
+fn superposition_isosurface(
+    state: &Superposition,
+    time: f64,
+    level: IsoLevel,
+    points: usize,
+) -> Mesh {
+    let half_width = state
+        .terms()
+        .iter()
+        .map(|t| CubeFile::default_half_width(&t.orbital))
+        .fold(0.0, f64::max);
+    let cube = CubeFile::from_fn(half_width, points, |p| state.density(p, time));
+    isosurface::cube_isosurface(&cube, level)
+}
+
+// dotted px (red), py (green) and pz (blue) axes out to 3 p0 / n, with larger dots at
+// multiples of p0 / n and at the positive ends
+// pz runs along the orbital z axis, which is +y of the sampler frame
+fn momentum_axes(mo: &MomentumOrbital) -> Vec<Marker> {
+    let tick = 4.0 * mo.display_unit();
+    let axes = [
+        (glm::vec3(1.0, 0.0, 0.0), glm::vec4(0.9, 0.2, 0.2, 1.0)),
+        (glm::vec3(0.0, 0.0, 1.0), glm::vec4(0.2, 0.85, 0.2, 1.0)),
+        (glm::vec3(0.0, 1.0, 0.0), glm::vec4(0.3, 0.45, 1.0, 1.0)),
+    ];
+    let mut markers = Vec::new();
+    for (direction, color) in axes {
+        for step in -24..=24 {
+            let size = match step {
+                24 => 5.0,
+                _ if step % 8 == 0 => 3.0,
+                _ => 1.5,
+            };
+            markers.push(Marker {
+                position: direction * (step as f64 * tick / 8.0),
+                color,
+                size,
+            });
+        }
+    }
+    markers
+}
+
+// cpk-style colors for the common elements, pink for everything else
+fn element_color(number: i32) -> glm::Vec4 {
+    match number {
+        1 => glm::vec4(0.9, 0.9, 0.9, 1.0),
+        6 => glm::vec4(0.35, 0.35, 0.35, 1.0),
+        7 => glm::vec4(0.2, 0.3, 0.95, 1.0),
+        8 => glm::vec4(0.95, 0.1, 0.1, 1.0),
+        9 | 17 => glm::vec4(0.1, 0.9, 0.2, 1.0),
+        15 => glm::vec4(1.0, 0.5, 0.0, 1.0),
+        16 => glm::vec4(0.95, 0.85, 0.2, 1.0),
+        _ => glm::vec4(0.9, 0.45, 0.7, 1.0),
+    }
+}
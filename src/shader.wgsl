@@ -28,12 +28,15 @@ fn vs_main(
 
     // per-instance color computed from probability density
     @location(2) instance_color: vec4<f32>,
+
+    // per-instance size, 1 for particles and larger for atom markers
+    @location(3) instance_scale: f32,
 ) -> VertexOutput {
 
     var out: VertexOutput;
 
     // small uniform scale so each sampled point becomes a tiny sphere
-    let scale = 0.05;
+    let scale = 0.05 * instance_scale;
 
     // final position = scaled sphere vertex + instance offset
     // then transformed by view_proj into clip space
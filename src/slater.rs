@@ -0,0 +1,664 @@
+use crate::physics::{
+    self, spherical_harmonic, spherical_to_cartesian, ColorMode, Harmonic, Particle,
+    QuantumNumbers, Sampler,
+};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::distributions::Distribution;
+use rand::Rng;
+use statrs::distribution::Gamma;
+use statrs::function::gamma;
+use std::f64::consts::PI;
+
+// many-electron atoms in the slater-type orbital picture
+// each electron sees the nucleus through the others as an effective charge Z - s from
+// slater's rules, and its orbital is N r^(n*-1) exp(-zeta r) Y_lm with zeta = Z_eff / n*
+// shells are filled in aufbau (madelung) order, so the known exceptions such as Cr and
+// Cu get the textbook 4s2 3d(n-2) configuration; any other configuration can be given
+// explicitly, e.g. "[Ar] 3d5 4s1" for chromium or "1s1 2s1" for excited helium
+
+pub const ELEMENTS: [&str; 54] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
+    "Ar", "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As",
+    "Se", "Br", "Kr", "Rb", "Sr", "Y", "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In",
+    "Sn", "Sb", "Te", "I", "Xe",
+];
+
+// element from its symbol ("O", case-insensitive) or atomic number ("8")
+pub fn parse_element(text: &str) -> Result<i32, String> {
+    let text = text.trim();
+    let z = match text.parse::<i32>() {
+        Ok(z) => z,
+        Err(_) => ELEMENTS
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(text))
+            .map(|i| i as i32 + 1)
+            .ok_or_else(|| format!("unknown element '{}'", text))?,
+    };
+    if !(1..=ELEMENTS.len() as i32).contains(&z) {
+        return Err(format!("Z must be between 1 and {}", ELEMENTS.len()));
+    }
+    Ok(z)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subshell {
+    pub n: i32,
+    pub l: i32,
+}
+
+impl Subshell {
+    pub fn capacity(&self) -> u32 {
+        2 * (2 * self.l as u32 + 1)
+    }
+
+    // e.g. "2p"
+    pub fn label(&self) -> String {
+        format!("{}{}", self.n, physics::subshell_letter(self.l))
+    }
+
+    // slater grouping (1s)(2s,2p)(3s,3p)(3d)(4s,4p)(4d)(4f)(5s,5p)..., in shielding order
+    fn group(&self) -> (i32, i32) {
+        (self.n, (self.l - 1).max(0))
+    }
+}
+
+// subshells in filling order, by increasing n + l and then n
+pub fn aufbau_order() -> Vec<Subshell> {
+    let mut order: Vec<Subshell> = (1..=7)
+        .flat_map(|n| (0..n.min(4)).map(move |l| Subshell { n, l }))
+        .collect();
+    order.sort_by_key(|s| (s.n + s.l, s.n));
+    order
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Occupation {
+    pub subshell: Subshell,
+    pub electrons: u32,
+}
+
+// atom or ion with one electron configuration
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atom {
+    pub z: i32,
+    pub occupations: Vec<Occupation>,
+}
+
+impl Atom {
+    // neutral atom in its aufbau ground configuration
+    pub fn aufbau(z: i32) -> Result<Self, String> {
+        if !(1..=ELEMENTS.len() as i32).contains(&z) {
+            return Err(format!("Z must be between 1 and {}", ELEMENTS.len()));
+        }
+
+        let mut remaining = z as u32;
+        let mut occupations = Vec::new();
+        for subshell in aufbau_order() {
+            if remaining == 0 {
+                break;
+            }
+            let electrons = remaining.min(subshell.capacity());
+            occupations.push(Occupation {
+                subshell,
+                electrons,
+            });
+            remaining -= electrons;
+        }
+
+        Ok(Self { z, occupations })
+    }
+
+    // explicit configuration such as "1s2 2s2 2p6", "1s² 2s² 2p⁶" or "[Ne] 3s2 3p1"
+    // without a nuclear charge the atom is neutral, Z being the number of electrons
+    pub fn with_configuration(z: Option<i32>, text: &str) -> Result<Self, String> {
+        let occupations = parse_configuration(text)?;
+        let electrons: u32 = occupations.iter().map(|o| o.electrons).sum();
+        let z = z.unwrap_or(electrons as i32);
+        if !(1..=ELEMENTS.len() as i32).contains(&z) {
+            return Err(format!("Z must be between 1 and {}", ELEMENTS.len()));
+        }
+        // too many electrons for the nuclear charge leave an outer subshell unbound
+        let atom = Self { z, occupations };
+        for o in &atom.occupations {
+            atom.orbital(o.subshell, 0)?;
+        }
+        Ok(atom)
+    }
+
+    pub fn electrons(&self) -> u32 {
+        self.occupations.iter().map(|o| o.electrons).sum()
+    }
+
+    // net charge, 0 for a neutral atom
+    pub fn charge(&self) -> i32 {
+        self.z - self.electrons() as i32
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        ELEMENTS[self.z as usize - 1]
+    }
+
+    // e.g. "1s2 2s2 2p4", in filling order
+    pub fn configuration(&self) -> String {
+        self.occupations
+            .iter()
+            .map(|o| format!("{}{}", o.subshell.label(), o.electrons))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn electrons_in(&self, subshell: Subshell) -> u32 {
+        self.occupations
+            .iter()
+            .filter(|o| o.subshell == subshell)
+            .map(|o| o.electrons)
+            .sum()
+    }
+
+    // Z - s for one electron in `subshell` by slater's rules
+    // others in the same group shield 0.35 (0.30 within 1s); for s and p electrons the
+    // n - 1 shell shields 0.85 and deeper shells 1.00, for d and f electrons every group
+    // to the left shields 1.00; groups to the right do not shield
+    // an empty subshell is treated as holding one added electron
+    pub fn effective_charge(&self, subshell: Subshell) -> f64 {
+        let group = subshell.group();
+        let mut shielding = 0.0;
+
+        for o in &self.occupations {
+            let other = o.subshell;
+            let mut electrons = o.electrons as f64;
+            if other == subshell {
+                electrons -= 1.0;
+            }
+
+            let per_electron = if other.group() == group {
+                if subshell.n == 1 {
+                    0.30
+                } else {
+                    0.35
+                }
+            } else if other.group() > group {
+                0.0
+            } else if subshell.l >= 2 {
+                1.0
+            } else if other.n == subshell.n - 1 {
+                0.85
+            } else {
+                1.0
+            };
+            shielding += per_electron * electrons;
+        }
+
+        self.z as f64 - shielding
+    }
+
+    // slater-type orbital for one real orbital of a subshell, e.g. 2px of carbon
+    // fails when the other electrons shield the whole nuclear charge, as for a 3s
+    // electron added to helium, since the function would not be bound
+    pub fn orbital(&self, subshell: Subshell, m: i32) -> Result<SlaterOrbital, String> {
+        let effective_charge = self.effective_charge(subshell);
+        if effective_charge <= 0.0 {
+            return Err(format!(
+                "{} of {} is fully shielded (Z_eff = {:.2}), so it has no bound slater function",
+                subshell.label(),
+                self.symbol(),
+                effective_charge
+            ));
+        }
+        Ok(SlaterOrbital::new(subshell, m, effective_charge))
+    }
+
+    // orbital from a real label such as "2px" or "3dz2"
+    pub fn orbital_from_label(&self, label: &str) -> Result<SlaterOrbital, String> {
+        let qn: QuantumNumbers = physics::parse_orbital_label(label)?;
+        self.orbital(
+            Subshell {
+                n: qn.n(),
+                l: qn.l(),
+            },
+            qn.m(),
+        )
+    }
+
+    // outermost shell plus any partly filled inner d or f subshell
+    pub fn valence(&self) -> Vec<Occupation> {
+        let n_max = self
+            .occupations
+            .iter()
+            .map(|o| o.subshell.n)
+            .max()
+            .unwrap_or(1);
+        self.occupations
+            .iter()
+            .copied()
+            .filter(|o| {
+                o.subshell.n == n_max || (o.subshell.l >= 2 && o.electrons < o.subshell.capacity())
+            })
+            .collect()
+    }
+
+    // total density of the given occupations, each subshell averaged over its m so the
+    // sum is spherical: sum_i N_i R_i(r)^2 / (4 pi), in electrons per bohr^3
+    pub fn shell_density(&self, occupations: &[Occupation], pos: &glm::DVec3) -> f64 {
+        let r = glm::length(pos);
+        occupations
+            .iter()
+            .filter_map(|o| {
+                let radial = self.orbital(o.subshell, 0).ok()?.radial(r);
+                Some(o.electrons as f64 * radial * radial / (4.0 * PI))
+            })
+            .sum()
+    }
+
+    // samples of the shell density: each particle picks a subshell with probability
+    // proportional to its occupancy, then a radius from that slater function and a
+    // uniform direction; colored by density relative to the densest sample
+    // a density has no phase, so phase mode colors each particle by the subshell it came
+    // from instead, subshell i taking subshell_color(i)
+    pub fn sample_shells(
+        &self,
+        occupations: &[Occupation],
+        sampler: &mut Sampler,
+        num_particles: usize,
+    ) -> Result<Vec<Particle>, String> {
+        let total: u32 = occupations.iter().map(|o| o.electrons).sum();
+        let orbitals = occupations
+            .iter()
+            .map(|o| self.orbital(o.subshell, 0))
+            .collect::<Result<Vec<SlaterOrbital>, String>>()?;
+
+        let mut positions = Vec::with_capacity(num_particles);
+        let mut sources = Vec::with_capacity(num_particles);
+        for _ in 0..num_particles {
+            let mut u = sampler.rng().gen_range(0..total);
+            let mut index = 0;
+            while u >= occupations[index].electrons {
+                u -= occupations[index].electrons;
+                index += 1;
+            }
+            let r = orbitals[index].sample_r(sampler)?;
+            let theta = (1.0 - 2.0 * sampler.rng().gen::<f64>()).acos();
+            let phi = sampler.rng().gen_range(0.0..2.0 * PI);
+            positions.push(spherical_to_cartesian(r, theta, phi));
+            sources.push(index);
+        }
+
+        let densities: Vec<f64> = positions
+            .iter()
+            .map(|p| self.shell_density(occupations, p))
+            .collect();
+        let max_density = densities.iter().copied().fold(0.0, f64::max);
+
+        Ok(positions
+            .into_iter()
+            .zip(densities)
+            .zip(sources)
+            .map(|((position, density), source)| {
+                let intensity = (1000.0 * density / max_density).ln_1p() / 1001.0f64.ln();
+                let color = match sampler.color_mode {
+                    ColorMode::Density => physics::heatmap_cool(intensity),
+                    ColorMode::Phase => {
+                        let v = (0.3 + 0.7 * intensity) as f32;
+                        let base = subshell_color(source);
+                        glm::vec4(base.x * v, base.y * v, base.z * v, 0.6)
+                    }
+                };
+                Particle {
+                    position,
+                    color,
+                    density,
+                }
+            })
+            .collect())
+    }
+}
+
+// distinct colors for the subshells of a configuration, cycling after ten
+pub fn subshell_color(index: usize) -> glm::Vec4 {
+    const PALETTE: [[f32; 3]; 10] = [
+        [0.90, 0.30, 0.25],
+        [0.25, 0.55, 0.95],
+        [0.35, 0.80, 0.35],
+        [0.95, 0.75, 0.20],
+        [0.70, 0.40, 0.90],
+        [0.20, 0.85, 0.85],
+        [0.95, 0.50, 0.75],
+        [0.95, 0.55, 0.15],
+        [0.60, 0.85, 0.20],
+        [0.85, 0.85, 0.85],
+    ];
+    let [r, g, b] = PALETTE[index % PALETTE.len()];
+    glm::vec4(r, g, b, 1.0)
+}
+
+// "1s2 2s2 2p6", "1s² 2s² 2p⁶" or "[Ne] 3s2 3p1"; a subshell without a count holds one
+// electron and a bracketed noble gas stands for its aufbau core
+pub fn parse_configuration(text: &str) -> Result<Vec<Occupation>, String> {
+    const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    let text: String = text
+        .chars()
+        .map(|c| match SUPERSCRIPTS.iter().position(|s| *s == c) {
+            Some(digit) => char::from(b'0' + digit as u8),
+            None => c,
+        })
+        .collect();
+
+    let mut occupations: Vec<Occupation> = Vec::new();
+    let mut push = |occupation: Occupation| -> Result<(), String> {
+        if occupations
+            .iter()
+            .any(|o| o.subshell == occupation.subshell)
+        {
+            return Err(format!("{} appears twice", occupation.subshell.label()));
+        }
+        occupations.push(occupation);
+        Ok(())
+    };
+
+    for token in text.split(|c: char| c.is_whitespace() || c == '.') {
+        if token.is_empty() {
+            continue;
+        }
+        if let Some(core) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            let z = parse_element(core)?;
+            if !matches!(z, 2 | 10 | 18 | 36 | 54) {
+                return Err(format!("[{}] is not a noble gas core", core));
+            }
+            for occupation in Atom::aufbau(z)?.occupations {
+                push(occupation)?;
+            }
+            continue;
+        }
+
+        let invalid = || format!("invalid subshell '{}'", token);
+        let digits = token.chars().take_while(|c| c.is_ascii_digit()).count();
+        let n: i32 = token[..digits].parse().map_err(|_| invalid())?;
+        let mut rest = token[digits..].chars();
+        let l = match rest.next() {
+            Some('s') => 0,
+            Some('p') => 1,
+            Some('d') => 2,
+            Some('f') => 3,
+            _ => return Err(invalid()),
+        };
+        let count = rest.as_str();
+        let electrons: u32 = if count.is_empty() {
+            1
+        } else {
+            count.parse().map_err(|_| invalid())?
+        };
+
+        let subshell = Subshell { n, l };
+        if n < 1 || l >= n {
+            return Err(format!("{} does not exist: l must be below n", token));
+        }
+        if electrons == 0 || electrons > subshell.capacity() {
+            return Err(format!(
+                "{} holds 1 to {} electrons, not {}",
+                subshell.label(),
+                subshell.capacity(),
+                electrons
+            ));
+        }
+        push(Occupation {
+            subshell,
+            electrons,
+        })?;
+    }
+
+    if occupations.is_empty() {
+        return Err("empty configuration".to_string());
+    }
+    Ok(occupations)
+}
+
+// slater's effective principal quantum number n*
+fn effective_n(n: i32) -> f64 {
+    match n {
+        1 => 1.0,
+        2 => 2.0,
+        3 => 3.0,
+        4 => 3.7,
+        5 => 4.0,
+        _ => 4.2,
+    }
+}
+
+// one slater-type orbital with a real spherical harmonic
+// nodeless in r, so the 2s and 1s functions of an atom are not orthogonal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlaterOrbital {
+    pub subshell: Subshell,
+    pub m: i32,
+    pub effective_charge: f64,
+    pub n_star: f64,
+    // exponent zeta = Z_eff / n*, bohr^-1
+    pub zeta: f64,
+}
+
+impl SlaterOrbital {
+    pub fn new(subshell: Subshell, m: i32, effective_charge: f64) -> Self {
+        let n_star = effective_n(subshell.n);
+        Self {
+            subshell,
+            m,
+            effective_charge,
+            n_star,
+            zeta: effective_charge / n_star,
+        }
+    }
+
+    // normalized R(r) = (2 zeta)^(n* + 1/2) / sqrt(gamma(2n* + 1)) r^(n*-1) exp(-zeta r)
+    pub fn radial(&self, r: f64) -> f64 {
+        let two_zeta = 2.0 * self.zeta;
+        let norm = two_zeta.powf(self.n_star + 0.5) / gamma::gamma(2.0 * self.n_star + 1.0).sqrt();
+        norm * r.powf(self.n_star - 1.0) * (-self.zeta * r).exp()
+    }
+
+    pub fn psi_at(&self, pos: &glm::DVec3) -> Complex64 {
+        let (r, theta, phi) = physics::cartesian_to_spherical(pos);
+        let l = self.subshell.l;
+        self.radial(r) * spherical_harmonic(l, self.m, theta, phi, Harmonic::Real)
+    }
+
+    pub fn density_at(&self, pos: &glm::DVec3) -> f64 {
+        self.psi_at(pos).norm_sqr()
+    }
+
+    // <r> = (2n* + 1) / (2 zeta)
+    pub fn mean_radius(&self) -> f64 {
+        (2.0 * self.n_star + 1.0) / (2.0 * self.zeta)
+    }
+
+    // radius well past the tail of r^2 R^2, for grids
+    pub fn r_max(&self) -> f64 {
+        let shape = 2.0 * self.n_star + 1.0;
+        (shape + 8.0 * shape.sqrt()) / (2.0 * self.zeta)
+    }
+
+    pub fn label(&self) -> String {
+        physics::orbital_label(self.subshell.n, self.subshell.l, self.m)
+            .unwrap_or_else(|| self.subshell.label())
+    }
+
+    // r^2 R(r)^2 is a gamma distribution with shape 2n* + 1 and rate 2 zeta
+    fn sample_r(&self, sampler: &mut Sampler) -> Result<f64, String> {
+        let distribution = Gamma::new(2.0 * self.n_star + 1.0, 2.0 * self.zeta)
+            .map_err(|_| format!("{} has no bound slater function", self.label()))?;
+        Ok(distribution.sample(sampler.rng()))
+    }
+
+    // exact samples: gamma-distributed radius and the cached angular tables of the sampler
+    // colors follow the hydrogenic clouds, with 1 / Z_eff as the length scale
+    pub fn sample(
+        &self,
+        sampler: &mut Sampler,
+        num_particles: usize,
+    ) -> Result<Vec<Particle>, String> {
+        let l = self.subshell.l;
+        let scale = (1.0 / self.effective_charge).powi(3);
+
+        (0..num_particles)
+            .map(|_| {
+                let r = self.sample_r(sampler)?;
+                let (theta, phi) = sampler.sample_angles(l, self.m, Harmonic::Real);
+                let position = spherical_to_cartesian(r, theta, phi);
+                let psi =
+                    self.radial(r) * spherical_harmonic(l, self.m, theta, phi, Harmonic::Real);
+                let density = psi.norm_sqr();
+                let intensity = physics::density_intensity(density * scale);
+                let color = match sampler.color_mode {
+                    ColorMode::Density => physics::heatmap_cool(intensity),
+                    ColorMode::Phase => physics::sign_color(psi.arg().abs(), intensity),
+                };
+                Ok(Particle {
+                    position,
+                    color,
+                    density,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aufbau_configurations() {
+        assert_eq!(Atom::aufbau(8).unwrap().configuration(), "1s2 2s2 2p4");
+        assert_eq!(
+            Atom::aufbau(26).unwrap().configuration(),
+            "1s2 2s2 2p6 3s2 3p6 4s2 3d6"
+        );
+        assert_eq!(parse_element("fe"), Ok(26));
+        assert_eq!(parse_element("54"), Ok(54));
+        assert!(parse_element("Xx").is_err());
+        assert!(Atom::aufbau(55).is_err());
+    }
+
+    #[test]
+    fn test_slater_rules() {
+        let p2 = Subshell { n: 2, l: 1 };
+        // carbon 2p: 6 - (3 * 0.35 + 2 * 0.85) = 3.25
+        let carbon = Atom::aufbau(6).unwrap();
+        assert!((carbon.effective_charge(p2) - 3.25).abs() < 1e-12);
+        // zinc 3d: 30 - (9 * 0.35 + 18 * 1.00) = 8.85, 4s: 30 - (0.35 + 18 * 0.85 + 10) = 4.35
+        let zinc = Atom::aufbau(30).unwrap();
+        assert!((zinc.effective_charge(Subshell { n: 3, l: 2 }) - 8.85).abs() < 1e-12);
+        assert!((zinc.effective_charge(Subshell { n: 4, l: 0 }) - 4.35).abs() < 1e-12);
+
+        let valence: Vec<String> = zinc.valence().iter().map(|o| o.subshell.label()).collect();
+        assert_eq!(valence, ["4s"]);
+        let iron = Atom::aufbau(26).unwrap();
+        assert_eq!(iron.valence().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_configuration() {
+        let neon = Atom::with_configuration(None, "1s² 2s² 2p⁶").unwrap();
+        assert_eq!(neon.z, 10);
+        assert_eq!(
+            neon.configuration(),
+            Atom::aufbau(10).unwrap().configuration()
+        );
+
+        let chromium = Atom::with_configuration(Some(24), "[Ar] 3d5 4s1").unwrap();
+        assert_eq!(chromium.electrons(), 24);
+        assert_eq!(chromium.charge(), 0);
+        let sodium_ion = Atom::with_configuration(Some(11), "[Ne]").unwrap();
+        assert_eq!(sodium_ion.charge(), 1);
+
+        assert_eq!(
+            parse_configuration("1s 2s").unwrap()[1],
+            Occupation {
+                subshell: Subshell { n: 2, l: 0 },
+                electrons: 1
+            }
+        );
+        assert!(parse_configuration("1s2 2p7").is_err());
+        assert!(parse_configuration("1s2 1p1").is_err());
+        assert!(parse_configuration("[Ne] 2p1").is_err());
+        assert!(parse_configuration("[O] 3s1").is_err());
+    }
+
+    #[test]
+    fn test_subshell_colored_sampling() {
+        // neon: 2 of 10 electrons are 1s, and they sit closest to the nucleus
+        let neon = Atom::aufbau(10).unwrap();
+        let mut sampler = Sampler::with_seed(5);
+        sampler.color_mode = ColorMode::Phase;
+        let particles = neon
+            .sample_shells(&neon.occupations, &mut sampler, 20_000)
+            .unwrap();
+
+        let core = subshell_color(0);
+        let is_core = |p: &Particle| {
+            let scale = p.color.x / core.x;
+            (p.color.y - core.y * scale).abs() < 1e-5 && (p.color.z - core.z * scale).abs() < 1e-5
+        };
+        let core_count = particles.iter().filter(|p| is_core(p)).count();
+        assert!((core_count as f64 / 20_000.0 - 0.2).abs() < 0.015);
+
+        let mean_r = |core: bool| {
+            let selected: Vec<f64> = particles
+                .iter()
+                .filter(|p| is_core(p) == core)
+                .map(|p| glm::length(&p.position))
+                .collect();
+            selected.iter().sum::<f64>() / selected.len() as f64
+        };
+        assert!(mean_r(true) < 0.5 * mean_r(false));
+    }
+
+    #[test]
+    fn test_slater_orbital_sampling() {
+        let carbon = Atom::aufbau(6).unwrap();
+        let orbital = carbon.orbital_from_label("2pz").unwrap();
+        let mut sampler = Sampler::with_seed(11);
+        let particles = orbital.sample(&mut sampler, 20_000).unwrap();
+        let mean_r = particles
+            .iter()
+            .map(|p| glm::length(&p.position))
+            .sum::<f64>()
+            / 20_000.0;
+        // <r> = 5 / (2 * 1.625)
+        assert!((mean_r - orbital.mean_radius()).abs() < 0.02 * orbital.mean_radius());
+        assert!((orbital.mean_radius() - 5.0 / 3.25).abs() < 1e-12);
+
+        // the radial function is normalized
+        let dr = 1e-3;
+        let norm: f64 = (1..40_000)
+            .map(|i| {
+                let r = i as f64 * dr;
+                let radial = orbital.radial(r);
+                r * r * radial * radial * dr
+            })
+            .sum();
+        assert!((norm - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fully_shielded_orbitals() {
+        // both helium electrons shield a third one in n = 3 completely
+        let helium = Atom::aufbau(2).unwrap();
+        for label in ["3s", "3dz2"] {
+            let error = helium.orbital_from_label(label).unwrap_err();
+            assert!(error.contains("fully shielded"));
+        }
+        assert!(helium.orbital_from_label("2s").is_ok());
+
+        // anions past the point where slater's rules still bind every subshell
+        let error = Atom::with_configuration(Some(1), "1s2 2s2 2p6").unwrap_err();
+        assert!(error.starts_with("2s of H"));
+        let error = Atom::with_configuration(Some(3), "1s2 2s2 2p3").unwrap_err();
+        assert!(error.starts_with("2s of Li"));
+        assert!(Atom::with_configuration(Some(8), "1s2 2s2 2p6").is_ok());
+
+        let unbound = SlaterOrbital::new(Subshell { n: 3, l: 0 }, 0, 0.0);
+        let mut sampler = Sampler::with_seed(1);
+        assert!(unbound.sample(&mut sampler, 10).is_err());
+    }
+}
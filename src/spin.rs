@@ -0,0 +1,432 @@
+use crate::physics::{
+    self, spherical_to_cartesian, ColorMode, Harmonic, Orbital, Particle, QuantumNumbers, Sampler,
+    Species,
+};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+use statrs::function::factorial::factorial;
+
+// spin-orbit coupled states |n l j mj> of a hydrogen-like atom
+// the orbital and spin angular momenta are added with clebsch-gordan coefficients,
+// |j mj> = sum_ms <l, mj - ms; 1/2, ms | j mj> |l, mj - ms> |ms>, so the state is a
+// two-component spinor whose spin-up and spin-down parts are ordinary complex orbitals
+// half-integers are passed around doubled, e.g. two_j = 3 for j = 3/2
+
+// fine-structure constant
+pub const ALPHA: f64 = 7.297_352_569_3e-3;
+
+// one atomic unit of magnetic flux density in tesla
+pub const TESLA_AU: f64 = 2.350_517_567_58e5;
+
+// bohr magneton in hartree per atomic unit of field, e hbar / 2 m_e
+pub const BOHR_MAGNETON: f64 = 0.5;
+
+// electron spin g-factor
+pub const G_SPIN: f64 = 2.002_319_304_36;
+
+// default zeeman field in tesla
+pub const DEFAULT_FIELD_T: f64 = 1.0;
+
+// <j1 m1; j2 m2 | j m> in the condon-shortley convention, all arguments doubled,
+// by racah's single-sum formula; zero when the triangle or projection rules fail
+pub fn clebsch_gordan(
+    two_j1: i32,
+    two_m1: i32,
+    two_j2: i32,
+    two_m2: i32,
+    two_j: i32,
+    two_m: i32,
+) -> f64 {
+    if two_m1 + two_m2 != two_m
+        || two_j < (two_j1 - two_j2).abs()
+        || two_j > two_j1 + two_j2
+        || (two_j1 + two_j2 + two_j) % 2 != 0
+        || [(two_j1, two_m1), (two_j2, two_m2), (two_j, two_m)]
+            .iter()
+            .any(|&(j, m)| m.abs() > j || (j + m) % 2 != 0)
+    {
+        return 0.0;
+    }
+
+    // every combination below is an integer once the doubled values are halved
+    let f = |twice: i32| factorial((twice / 2) as u64);
+    let triangle =
+        f(two_j1 + two_j2 - two_j) * f(two_j1 - two_j2 + two_j) * f(-two_j1 + two_j2 + two_j)
+            / f(two_j1 + two_j2 + two_j + 2);
+    let projections = f(two_j + two_m)
+        * f(two_j - two_m)
+        * f(two_j1 - two_m1)
+        * f(two_j1 + two_m1)
+        * f(two_j2 - two_m2)
+        * f(two_j2 + two_m2);
+
+    let k_min = 0
+        .max((two_j2 - two_j - two_m1) / 2)
+        .max((two_j1 - two_j + two_m2) / 2);
+    let k_max = ((two_j1 + two_j2 - two_j) / 2)
+        .min((two_j1 - two_m1) / 2)
+        .min((two_j2 + two_m2) / 2);
+    let sum: f64 = (k_min..=k_max)
+        .map(|k| {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            sign / (factorial(k as u64)
+                * f(two_j1 + two_j2 - two_j - 2 * k)
+                * f(two_j1 - two_m1 - 2 * k)
+                * f(two_j2 + two_m2 - 2 * k)
+                * f(two_j - two_j2 + two_m1 + 2 * k)
+                * f(two_j - two_j1 - two_m2 + 2 * k))
+        })
+        .sum();
+
+    ((two_j + 1) as f64 * triangle * projections).sqrt() * sum
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpinOrbital {
+    n: i32,
+    l: i32,
+    two_j: i32,
+    two_mj: i32,
+    species: Species,
+    // field along the orbital z axis in tesla, for the zeeman shift
+    field: f64,
+}
+
+impl SpinOrbital {
+    // j must be l +- 1/2 (only 1/2 for s states) and mj one of -j..j
+    pub fn new(n: i32, l: i32, two_j: i32, two_mj: i32, species: Species) -> Result<Self, String> {
+        QuantumNumbers::new(n, l, 0).map_err(|e| e.to_string())?;
+        if two_j != 2 * l + 1 && (two_j != 2 * l - 1 || l == 0) {
+            return Err(format!(
+                "j must be l +- 1/2 for l={}, not {}",
+                l,
+                half(two_j)
+            ));
+        }
+        if two_mj.abs() > two_j || (two_j + two_mj) % 2 != 0 {
+            return Err(format!(
+                "mj must be one of -{0} to {0} in steps of 1, not {1}",
+                half(two_j),
+                half(two_mj)
+            ));
+        }
+        Ok(Self {
+            n,
+            l,
+            two_j,
+            two_mj,
+            species,
+            field: DEFAULT_FIELD_T,
+        })
+    }
+
+    // parses "2p3/2" (mj = j) or "2p3/2,-1/2"
+    pub fn parse(text: &str, species: Species) -> Result<Self, String> {
+        let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let (term, mj) = match text.split_once(',') {
+            Some((term, mj)) => (term, Some(mj)),
+            None => (text.as_str(), None),
+        };
+
+        let letter = term
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(|| format!("expected a label such as 2p3/2, not '{}'", term))?;
+        let n = term[..letter]
+            .parse::<i32>()
+            .map_err(|_| format!("invalid n in '{}'", term))?;
+        let l = term[letter..]
+            .chars()
+            .next()
+            .and_then(physics::parse_subshell_letter)
+            .ok_or_else(|| format!("unknown subshell letter in '{}'", term))?;
+        let two_j = parse_half(&term[letter + 1..])?;
+        let two_mj = mj.map(parse_half).transpose()?.unwrap_or(two_j);
+        Self::new(n, l, two_j, two_mj, species)
+    }
+
+    pub fn with_species(mut self, species: Species) -> Self {
+        self.species = species;
+        self
+    }
+
+    // field strength along the orbital z axis in tesla
+    pub fn with_field(mut self, field: f64) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn n(&self) -> i32 {
+        self.n
+    }
+
+    pub fn l(&self) -> i32 {
+        self.l
+    }
+
+    pub fn j(&self) -> f64 {
+        self.two_j as f64 / 2.0
+    }
+
+    pub fn mj(&self) -> f64 {
+        self.two_mj as f64 / 2.0
+    }
+
+    pub fn species(&self) -> Species {
+        self.species
+    }
+
+    pub fn field(&self) -> f64 {
+        self.field
+    }
+
+    // clebsch-gordan weight and orbital of the spin-up and spin-down parts, None where
+    // m = mj -+ 1/2 falls outside -l..l
+    pub fn components(&self) -> [(f64, Option<Orbital>); 2] {
+        [1, -1].map(|two_ms| {
+            let two_m = self.two_mj - two_ms;
+            if two_m.abs() > 2 * self.l {
+                return (0.0, None);
+            }
+            let c = clebsch_gordan(2 * self.l, two_m, 1, two_ms, self.two_j, self.two_mj);
+            let orbital = Orbital::new(
+                QuantumNumbers::new(self.n, self.l, two_m / 2).expect("|m| <= l was checked"),
+            )
+            .with_harmonic(Harmonic::Complex)
+            .with_species(self.species);
+            (c, Some(orbital))
+        })
+    }
+
+    // probability of finding the electron with spin up, |c_up|^2
+    pub fn spin_up_probability(&self) -> f64 {
+        self.components()[0].0.powi(2)
+    }
+
+    // (psi_up, psi_down) at a point of the sampler frame
+    pub fn spinor(&self, pos: &glm::DVec3) -> (Complex64, Complex64) {
+        let [up, down] = self
+            .components()
+            .map(|(c, orbital)| orbital.map_or(Complex64::new(0.0, 0.0), |o| c * o.psi_at(pos)));
+        (up, down)
+    }
+
+    // |psi_up|^2 + |psi_down|^2, the two spin components do not interfere
+    pub fn density(&self, pos: &glm::DVec3) -> f64 {
+        let (up, down) = self.spinor(pos);
+        up.norm_sqr() + down.norm_sqr()
+    }
+
+    // lande factor g_j = 1 + (g_s - 1) (j(j+1) - l(l+1) + 3/4) / (2 j(j+1))
+    pub fn lande_g(&self) -> f64 {
+        let j = self.j();
+        let l = self.l as f64;
+        1.0 + (G_SPIN - 1.0) * (j * (j + 1.0) - l * (l + 1.0) + 0.75) / (2.0 * j * (j + 1.0))
+    }
+
+    // dirac fine-structure correction to order (Z alpha)^2 in hartree,
+    // E_n (Z alpha)^2 / n^2 (n / (j + 1/2) - 3/4); it depends on n and j only
+    pub fn fine_structure_shift(&self) -> f64 {
+        let n = self.n as f64;
+        let z_alpha = self.species.z as f64 * ALPHA;
+        self.species.energy(self.n) * z_alpha.powi(2) / (n * n) * (n / (self.j() + 0.5) - 0.75)
+    }
+
+    // weak-field (anomalous) zeeman shift g_j mu_B B mj in hartree
+    pub fn zeeman_shift(&self) -> f64 {
+        self.lande_g() * BOHR_MAGNETON * self.field / TESLA_AU * self.mj()
+    }
+
+    // splitting between j = l + 1/2 and j = l - 1/2 in hartree, zero for s states
+    pub fn fine_structure_splitting(&self) -> f64 {
+        self.other_j().map_or(0.0, |partner| {
+            (self.fine_structure_shift() - partner.fine_structure_shift()).abs()
+        })
+    }
+
+    // E_n + fine structure + zeeman, in hartree
+    pub fn energy(&self) -> f64 {
+        self.species.energy(self.n) + self.fine_structure_shift() + self.zeeman_shift()
+    }
+
+    // the next mj of the multiplet, wrapping around
+    pub fn step_mj(&self, delta: i32) -> Self {
+        let count = self.two_j + 1;
+        let index = (self.two_mj + self.two_j) / 2;
+        let index = (index + delta).rem_euclid(count);
+        Self {
+            two_mj: 2 * index - self.two_j,
+            ..*self
+        }
+    }
+
+    // the other j of the same n and l, keeping mj where it fits, None for s states
+    pub fn other_j(&self) -> Option<Self> {
+        if self.l == 0 {
+            return None;
+        }
+        let two_j = 4 * self.l - self.two_j;
+        Some(Self {
+            two_j,
+            two_mj: self.two_mj.clamp(-two_j, two_j),
+            ..*self
+        })
+    }
+
+    // e.g. "2p3/2, mj=-1/2"
+    pub fn label(&self) -> String {
+        format!(
+            "{}{}{}, mj={}",
+            self.n,
+            physics::subshell_letter(self.l),
+            half(self.two_j),
+            half(self.two_mj)
+        )
+    }
+
+    // picks a spin component with its weight |c|^2, samples that orbital exactly and colors
+    // the particle by spin: density mode tints up and down apart, phase mode shows arg(psi)
+    // of the component the particle came from
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        let components = self.components();
+        let p_up = self.spin_up_probability();
+        let scale = self.species.length_scale().powi(3);
+
+        (0..num_particles)
+            .map(|_| {
+                let up = sampler.rng().gen::<f64>() < p_up;
+                let (c, orbital) = components[usize::from(!up)];
+                let orbital = orbital.expect("a component with weight has an orbital");
+                let (r, theta, phi) = sampler.sample_spherical(&orbital);
+                let position = spherical_to_cartesian(r, theta, phi);
+                let psi = c * orbital.psi(r, theta, phi);
+                let intensity = physics::density_intensity(psi.norm_sqr() * scale);
+                let color = match sampler.color_mode {
+                    ColorMode::Density => spin_color(up, intensity),
+                    ColorMode::Phase => physics::phase_wheel(psi.arg(), intensity),
+                };
+                Particle {
+                    position,
+                    color,
+                    density: self.density(&position),
+                }
+            })
+            .collect()
+    }
+}
+
+// spin up red, spin down green, darker where the component is thin
+pub fn spin_color(up: bool, value: f64) -> glm::Vec4 {
+    let v = (0.3 + 0.7 * value.clamp(0.0, 1.0)) as f32;
+    if up {
+        glm::vec4(v, 0.3 * v, 0.25 * v, 0.6)
+    } else {
+        glm::vec4(0.2 * v, 0.85 * v, 0.55 * v, 0.6)
+    }
+}
+
+// "3/2" or "-1/2" doubled, also whole numbers such as "1"
+fn parse_half(text: &str) -> Result<i32, String> {
+    let invalid = || format!("'{}' is not a half-integer such as 3/2", text);
+    match text.split_once('/') {
+        Some((numerator, "2")) => numerator.parse::<i32>().map_err(|_| invalid()),
+        Some(_) => Err(invalid()),
+        None => text.parse::<i32>().map(|x| 2 * x).map_err(|_| invalid()),
+    }
+}
+
+// doubled value as "3/2", "-1/2" or "1"
+fn half(twice: i32) -> String {
+    if twice % 2 == 0 {
+        (twice / 2).to_string()
+    } else {
+        format!("{}/2", twice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::HYDROGEN;
+
+    #[test]
+    fn test_clebsch_gordan() {
+        // spin-1/2 coupling in closed form, j = l + 1/2 and l - 1/2
+        for l in 0..4 {
+            let l2 = 2 * l;
+            for two_mj in (-(l2 + 1)..=l2 + 1).step_by(2) {
+                let mj = two_mj as f64 / 2.0;
+                let denom = (2 * l + 1) as f64;
+                let up = clebsch_gordan(l2, two_mj - 1, 1, 1, l2 + 1, two_mj);
+                let down = clebsch_gordan(l2, two_mj + 1, 1, -1, l2 + 1, two_mj);
+                assert!((up - ((l as f64 + mj + 0.5) / denom).sqrt()).abs() < 1e-12);
+                assert!((down - ((l as f64 - mj + 0.5) / denom).sqrt()).abs() < 1e-12);
+                if l > 0 && two_mj.abs() < l2 {
+                    let up = clebsch_gordan(l2, two_mj - 1, 1, 1, l2 - 1, two_mj);
+                    let down = clebsch_gordan(l2, two_mj + 1, 1, -1, l2 - 1, two_mj);
+                    assert!((up + ((l as f64 - mj + 0.5) / denom).sqrt()).abs() < 1e-12);
+                    assert!((down - ((l as f64 + mj + 0.5) / denom).sqrt()).abs() < 1e-12);
+                }
+            }
+        }
+        // two spin-1/2 particles: the singlet and the m = 0 triplet
+        let r = 0.5f64.sqrt();
+        assert!((clebsch_gordan(1, 1, 1, -1, 0, 0) - r).abs() < 1e-12);
+        assert!((clebsch_gordan(1, -1, 1, 1, 0, 0) + r).abs() < 1e-12);
+        assert!((clebsch_gordan(1, 1, 1, -1, 2, 0) - r).abs() < 1e-12);
+        assert_eq!(clebsch_gordan(2, 2, 2, 2, 2, 4), 0.0);
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let state = SpinOrbital::parse("2p3/2, -1/2", HYDROGEN).unwrap();
+        assert_eq!(
+            (state.n(), state.l(), state.j(), state.mj()),
+            (2, 1, 1.5, -0.5)
+        );
+        assert_eq!(state.label(), "2p3/2, mj=-1/2");
+        assert_eq!(SpinOrbital::parse("3d5/2", HYDROGEN).unwrap().mj(), 2.5);
+        assert!(SpinOrbital::parse("1s3/2", HYDROGEN).is_err());
+        assert!(SpinOrbital::parse("2s-1/2", HYDROGEN).is_err());
+        assert!(SpinOrbital::parse("2p1/2,3/2", HYDROGEN).is_err());
+        assert!(SpinOrbital::parse("2p1", HYDROGEN).is_err());
+    }
+
+    #[test]
+    fn test_energies() {
+        // 2p3/2 - 2p1/2 is the 0.365 cm^-1 lamb-free splitting of hydrogen, 1.66e-6 hartree
+        let p32 = SpinOrbital::parse("2p3/2", HYDROGEN).unwrap();
+        let p12 = p32.other_j().unwrap();
+        let splitting = p32.fine_structure_shift() - p12.fine_structure_shift();
+        assert!((splitting - 1.664e-6).abs() < 1e-9, "{}", splitting);
+        assert!((p32.fine_structure_splitting() - splitting).abs() < 1e-15);
+        // 2s1/2 and 2p1/2 are degenerate in dirac theory
+        let s12 = SpinOrbital::parse("2s1/2", HYDROGEN).unwrap();
+        assert!((s12.fine_structure_shift() - p12.fine_structure_shift()).abs() < 1e-15);
+
+        // lande factors 2, 4/3 and 2/3 with g_s = 2
+        assert!((s12.lande_g() - G_SPIN).abs() < 1e-12);
+        assert!((p32.lande_g() - 4.0 / 3.0).abs() < 1e-3);
+        assert!((p12.lande_g() - 2.0 / 3.0).abs() < 1e-3);
+        // mu_B * 1 T = 5.788e-5 eV
+        let shift = s12.with_field(1.0).zeeman_shift() / G_SPIN / 0.5 * physics::HARTREE_EV;
+        assert!((shift - 5.788e-5).abs() < 1e-8, "{}", shift);
+    }
+
+    #[test]
+    fn test_sampled_spin_fraction() {
+        // 2p3/2, mj=1/2 is 2/3 spin up (m = 0) and 1/3 spin down (m = 1)
+        let state = SpinOrbital::parse("2p3/2,1/2", HYDROGEN).unwrap();
+        assert!((state.spin_up_probability() - 2.0 / 3.0).abs() < 1e-12);
+
+        let particles = state.sample(&mut Sampler::with_seed(5), 30_000);
+        let up = particles.iter().filter(|p| p.color.x > p.color.y).count();
+        let fraction = up as f64 / particles.len() as f64;
+        assert!((fraction - 2.0 / 3.0).abs() < 0.015, "{}", fraction);
+
+        // particles carry the total density of both components
+        let p = particles[7].position;
+        let (u, d) = state.spinor(&p);
+        assert!((particles[7].density - u.norm_sqr() - d.norm_sqr()).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,358 @@
+use crate::physics::{Harmonic, Orbital, Particle, QuantumNumbers, Sampler, Species};
+use crate::superposition::{Superposition, Term};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+
+// hydrogen-like atoms in a uniform electric field F, to first order in F
+// H' = F z couples l to l +- 1 inside each degenerate n shell and conserves m along the
+// field, so diagonalizing z there gives the parabolic states |n n1 n2 m>, labelled here by
+// k = n1 - n2; each carries a permanent dipole <z> = (3/2) n k a and shifts by
+// E1 = F <z> = (3/2) n k F a in atomic units, where F is in hartree / (e bohr)
+// the electron is pushed against the field, so the k < 0 states are the low-energy ones
+// states are built along the orbital z axis and rotated onto the chosen field axis
+
+// one atomic unit of electric field in volts per metre
+pub const FIELD_AU_V_PER_M: f64 = 5.142_206_747_63e11;
+
+// default field strength in atomic units, about 5 MV/cm
+pub const DEFAULT_FIELD: f64 = 1e-3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StarkState {
+    n: i32,
+    k: i32,
+    m: i32,
+    // unit vector along the field in the sampler frame
+    axis: glm::DVec3,
+    // atomic units
+    field: f64,
+    // the state in its own frame, field along the orbital z axis
+    state: Superposition,
+}
+
+impl StarkState {
+    // parabolic state (n, k, m) with the field along the orbital z axis
+    // k runs from -(n - |m| - 1) to n - |m| - 1 in steps of 2
+    pub fn new(n: i32, k: i32, m: i32, species: Species) -> Result<Self, String> {
+        QuantumNumbers::new(n, m.abs(), m).map_err(|e| e.to_string())?;
+        let ks = manifold(n, m);
+        if !ks.contains(&k) {
+            return Err(format!("k must be one of {:?} for n={}, m={}", ks, n, m));
+        }
+
+        let terms = mixing_coefficients(n, k, m)
+            .into_iter()
+            .filter(|(_, c)| c.abs() > 1e-12)
+            .map(|(l, c)| Term {
+                coefficient: Complex64::new(c, 0.0),
+                orbital: Orbital::new(QuantumNumbers::new(n, l, m).expect("l lies in |m|..n"))
+                    .with_harmonic(Harmonic::Complex)
+                    .with_species(species),
+            })
+            .collect();
+
+        Ok(Self {
+            n,
+            k,
+            m,
+            axis: glm::vec3(0.0, 1.0, 0.0),
+            field: DEFAULT_FIELD,
+            state: Superposition::new(terms)?,
+        })
+    }
+
+    // parses "n,k,m", e.g. "2,1,0" for the upfield n = 2 state
+    pub fn parse(text: &str, species: Species) -> Result<Self, String> {
+        let numbers = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<i32>()
+                    .map_err(|_| format!("'{}' is not an integer", s))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match numbers.as_slice() {
+            [n, k, m] => Self::new(*n, *k, *m, species),
+            _ => Err("expected n,k,m".to_string()),
+        }
+    }
+
+    // field along `axis`, given in orbital axes (x, y, z)
+    pub fn with_axis(mut self, axis: glm::DVec3) -> Result<Self, String> {
+        let length = glm::length(&axis);
+        if !(length > 0.0 && length.is_finite()) {
+            return Err("the field axis must be a nonzero vector".to_string());
+        }
+        self.axis = glm::vec3(axis.x, axis.z, axis.y) / length;
+        Ok(self)
+    }
+
+    // field strength in atomic units; only the energies depend on it at first order
+    pub fn with_field(mut self, field: f64) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn n(&self) -> i32 {
+        self.n
+    }
+
+    pub fn k(&self) -> i32 {
+        self.k
+    }
+
+    pub fn m(&self) -> i32 {
+        self.m
+    }
+
+    pub fn field(&self) -> f64 {
+        self.field
+    }
+
+    // field direction in orbital axes (x, y, z)
+    pub fn axis(&self) -> glm::DVec3 {
+        glm::vec3(self.axis.x, self.axis.z, self.axis.y)
+    }
+
+    pub fn species(&self) -> Species {
+        self.state.species()
+    }
+
+    // expansion in the spherical states of the shell, in the field frame
+    pub fn state(&self) -> &Superposition {
+        &self.state
+    }
+
+    // parabolic quantum numbers (n1, n2), with n = n1 + n2 + |m| + 1
+    pub fn parabolic(&self) -> (i32, i32) {
+        let sum = self.n - self.m.abs() - 1;
+        ((sum + self.k) / 2, (sum - self.k) / 2)
+    }
+
+    // permanent dipole <z> along the field in bohr, (3/2) n k a
+    pub fn dipole(&self) -> f64 {
+        1.5 * (self.n * self.k) as f64 * self.species().length_scale()
+    }
+
+    // first-order stark shift F <z> in hartree
+    pub fn energy_shift(&self) -> f64 {
+        self.field * self.dipole()
+    }
+
+    // E_n + E1 in hartree
+    pub fn energy(&self) -> f64 {
+        self.species().energy(self.n) + self.energy_shift()
+    }
+
+    // first-order shifts of every k in this state's (n, m) block, lowest first
+    pub fn manifold_shifts(&self) -> Vec<(i32, f64)> {
+        let unit = 1.5 * self.n as f64 * self.field * self.species().length_scale();
+        manifold(self.n, self.m)
+            .into_iter()
+            .map(|k| (k, unit * k as f64))
+            .collect()
+    }
+
+    // field at which the saddle point -2 sqrt(Z F) of -Z/r + F z drops to E_n, E_n^2 / (4 Z),
+    // 1 / (16 n^4) for hydrogen; the shell ionizes and first order fails well before it
+    pub fn ionization_field(&self) -> f64 {
+        let species = self.species();
+        species.energy(self.n).powi(2) / (4.0 * species.z as f64)
+    }
+
+    // the same state with the next k of the manifold, wrapping around
+    pub fn step(&self, delta: i32) -> Self {
+        let ks = manifold(self.n, self.m);
+        let i = ks.iter().position(|&k| k == self.k).unwrap_or(0) as i32;
+        let k = ks[(i + delta).rem_euclid(ks.len() as i32) as usize];
+        let mut next = Self::new(self.n, k, self.m, self.species()).expect("k from the manifold");
+        next.axis = self.axis;
+        next.field = self.field;
+        next
+    }
+
+    // e.g. "n=2, k=1, m=0 (n1=1, n2=0)"
+    pub fn label(&self) -> String {
+        let (n1, n2) = self.parabolic();
+        format!(
+            "n={}, k={}, m={} (n1={}, n2={})",
+            self.n, self.k, self.m, n1, n2
+        )
+    }
+
+    fn to_local(&self, pos: &glm::DVec3) -> glm::DVec3 {
+        glm::transpose(&self.rotation()) * pos
+    }
+
+    // columns are the field frame's axes in the sampler frame, the orbital axis (+y) on the field
+    fn rotation(&self) -> glm::DMat3 {
+        let y = self.axis;
+        let helper = if y.x.abs() < 0.9 {
+            glm::vec3(1.0, 0.0, 0.0)
+        } else {
+            glm::vec3(0.0, 0.0, 1.0)
+        };
+        let x = glm::normalize(&(helper - y * glm::dot(&helper, &y)));
+        let z = glm::cross(&x, &y);
+        glm::mat3(x.x, y.x, z.x, x.y, y.y, z.y, x.z, y.z, z.z)
+    }
+
+    pub fn psi(&self, pos: &glm::DVec3) -> Complex64 {
+        self.state.psi(&self.to_local(pos), 0.0)
+    }
+
+    pub fn density(&self, pos: &glm::DVec3) -> f64 {
+        self.psi(pos).norm_sqr()
+    }
+
+    // exact samples of the field-frame state, rotated onto the field axis
+    pub fn sample(&self, sampler: &mut Sampler, num_particles: usize) -> Vec<Particle> {
+        let rotation = self.rotation();
+        let mut particles = self.state.sample(sampler, 0.0, num_particles);
+        for particle in &mut particles {
+            particle.position = rotation * particle.position;
+        }
+        particles
+    }
+}
+
+// allowed k for one (n, m) block, lowest first
+pub fn manifold(n: i32, m: i32) -> Vec<i32> {
+    let top = n - m.abs() - 1;
+    if top < 0 {
+        return Vec::new();
+    }
+    (0..=top).map(|i| 2 * i - top).collect()
+}
+
+// parses a field axis: x, y, z with an optional sign, or components such as "1,1,0"
+pub fn parse_axis(text: &str) -> Result<glm::DVec3, String> {
+    let text = text.trim().to_lowercase();
+    let (sign, name) = match text.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, text.trim_start_matches('+')),
+    };
+    let axis = match name {
+        "x" => glm::vec3(1.0, 0.0, 0.0),
+        "y" => glm::vec3(0.0, 1.0, 0.0),
+        "z" => glm::vec3(0.0, 0.0, 1.0),
+        _ => {
+            let parts = text
+                .split(',')
+                .map(|s| s.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("invalid axis '{}', expected x, y, z or x,y,z", text))?;
+            return match parts.as_slice() {
+                [x, y, z] if x * x + y * y + z * z > 0.0 => Ok(glm::vec3(*x, *y, *z)),
+                _ => Err(format!(
+                    "invalid axis '{}', expected x, y, z or x,y,z",
+                    text
+                )),
+            };
+        }
+    };
+    Ok(axis * sign)
+}
+
+// coefficients c_l of |n k m> = sum_l c_l |n l m> for complex harmonics
+// z is tridiagonal in l with <n l m|z|n l-1 m> = -(3/2) n a b_l, where
+// b_l = sqrt((n^2 - l^2)(l^2 - m^2) / (4 l^2 - 1)), and its eigenvalues are (3/2) n k a,
+// so the eigenvector follows from the three-term recurrence with c_|m| = 1
+fn mixing_coefficients(n: i32, k: i32, m: i32) -> Vec<(i32, f64)> {
+    let m_abs = m.abs();
+    let coupling = |l: i32| {
+        let (n, l, m) = (n as f64, l as f64, m as f64);
+        -((n * n - l * l) * (l * l - m * m) / (4.0 * l * l - 1.0)).sqrt()
+    };
+
+    let mut c = vec![1.0];
+    for l in m_abs..n - 1 {
+        let i = (l - m_abs) as usize;
+        let previous = if i > 0 { coupling(l) * c[i - 1] } else { 0.0 };
+        c.push((k as f64 * c[i] - previous) / coupling(l + 1));
+    }
+
+    let norm = c.iter().map(|x| x * x).sum::<f64>().sqrt();
+    (m_abs..n).zip(c.into_iter().map(|x| x / norm)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::HYDROGEN;
+
+    #[test]
+    fn test_manifold_and_parabolic_numbers() {
+        assert_eq!(manifold(3, 0), vec![-2, 0, 2]);
+        assert_eq!(manifold(3, 1), vec![-1, 1]);
+        assert_eq!(manifold(3, -2), vec![0]);
+
+        let state = StarkState::new(3, -1, 1, HYDROGEN).unwrap();
+        assert_eq!(state.parabolic(), (0, 1));
+        assert!(StarkState::new(3, 1, 0, HYDROGEN).is_err());
+        assert!(StarkState::new(2, 0, 2, HYDROGEN).is_err());
+        assert!(StarkState::parse("2,1,0", HYDROGEN).is_ok());
+    }
+
+    #[test]
+    fn test_n2_states_are_sp_hybrids() {
+        // |2, +-1, 0> = (2s -+ 2p0) / sqrt 2 with the radial functions positive at the origin
+        let up = StarkState::new(2, 1, 0, HYDROGEN).unwrap();
+        let terms = up.state().terms();
+        assert_eq!(terms.len(), 2);
+        let half = 0.5f64.sqrt();
+        assert!((terms[0].coefficient.re - half).abs() < 1e-12);
+        assert!((terms[1].coefficient.re + half).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_mixing_diagonalizes_z() {
+        // every eigenvector of the tridiagonal block is orthogonal to the others
+        for (n, m) in [(3, 0), (4, 1), (5, 0), (6, 2)] {
+            let ks = manifold(n, m);
+            for &a in &ks {
+                for &b in &ks {
+                    let ca = mixing_coefficients(n, a, m);
+                    let cb = mixing_coefficients(n, b, m);
+                    let overlap: f64 = ca.iter().zip(&cb).map(|(x, y)| x.1 * y.1).sum();
+                    let expected = if a == b { 1.0 } else { 0.0 };
+                    assert!((overlap - expected).abs() < 1e-9, "n={} {} {}", n, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dipole_along_field_axis() {
+        // <r . axis> of the sampled cloud matches (3/2) n k a for any axis
+        let axis = glm::vec3(1.0, 1.0, 0.0);
+        let state = StarkState::new(3, 2, 0, HYDROGEN)
+            .unwrap()
+            .with_axis(axis)
+            .unwrap();
+        assert!((state.dipole() - 9.0).abs() < 1e-12);
+
+        let particles = state.sample(&mut Sampler::with_seed(3), 40_000);
+        // the axis in sampler coordinates is (x, z, y)
+        let along = glm::normalize(&glm::vec3(1.0, 0.0, 1.0));
+        let mean = particles
+            .iter()
+            .map(|p| glm::dot(&p.position, &along))
+            .sum::<f64>()
+            / particles.len() as f64;
+        assert!((mean - 9.0).abs() < 0.3, "<z> = {}", mean);
+
+        // the rotated density is still the sampled one
+        let p = particles[0].position;
+        assert!((state.density(&p) - particles[0].density).abs() < 1e-9 * particles[0].density);
+    }
+
+    #[test]
+    fn test_parse_axis() {
+        assert_eq!(parse_axis("-x"), Ok(glm::vec3(-1.0, 0.0, 0.0)));
+        assert_eq!(parse_axis(" Z "), Ok(glm::vec3(0.0, 0.0, 1.0)));
+        assert_eq!(parse_axis("1,0,1"), Ok(glm::vec3(1.0, 0.0, 1.0)));
+        assert!(parse_axis("0,0,0").is_err());
+        assert!(parse_axis("w").is_err());
+    }
+}
@@ -0,0 +1,551 @@
+use crate::physics::{
+    self, cartesian_to_spherical, spherical_to_cartesian, ColorMode, Harmonic, Orbital, Particle,
+    QuantumNumbers, Sampler, Species,
+};
+use nalgebra_glm as glm;
+use num_complex::Complex64;
+use rand::Rng;
+
+// coherent superpositions sum_i c_i psi_i of eigenstates of one hydrogen-like species
+// each term evolves as exp(-i E_n t) with E_n = -mu Z^2 / (2 n^2), so |psi(t)|^2 beats
+// at the energy differences; t is in atomic units of time, hbar / hartree
+
+// one atomic unit of time in femtoseconds
+pub const ATOMIC_TIME_FS: f64 = 2.418_884_326_585_7e-2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Term {
+    pub coefficient: Complex64,
+    pub orbital: Orbital,
+}
+
+// normalized superposition, sum |c_i|^2 = 1
+// terms are distinct eigenstates, and terms sharing n and l use the same kind of harmonic,
+// so they are orthonormal and the coefficients alone fix the norm
+#[derive(Clone, Debug, PartialEq)]
+pub struct Superposition {
+    terms: Vec<Term>,
+}
+
+impl Superposition {
+    pub fn new(mut terms: Vec<Term>) -> Result<Self, String> {
+        let species = terms.first().ok_or("empty superposition")?.orbital.species;
+
+        for (i, a) in terms.iter().enumerate() {
+            if a.orbital.species != species {
+                return Err("all terms must belong to the same species".to_string());
+            }
+            let qa = a.orbital.quantum_numbers;
+            for b in &terms[..i] {
+                let qb = b.orbital.quantum_numbers;
+                if (qa.n(), qa.l()) != (qb.n(), qb.l()) {
+                    continue;
+                }
+                // s states are the same function in either basis
+                if qa.l() > 0 && a.orbital.harmonic != b.orbital.harmonic {
+                    return Err(format!(
+                        "terms with n={}, l={} must all use real or all use complex harmonics",
+                        qa.n(),
+                        qa.l()
+                    ));
+                }
+                if qa.m() == qb.m() {
+                    return Err(format!("{} appears more than once", state_name(&a.orbital)));
+                }
+            }
+        }
+
+        let norm = terms
+            .iter()
+            .map(|t| t.coefficient.norm_sqr())
+            .sum::<f64>()
+            .sqrt();
+        if norm == 0.0 {
+            return Err("all coefficients are zero".to_string());
+        }
+        for term in &mut terms {
+            term.coefficient /= norm;
+        }
+
+        Ok(Self { terms })
+    }
+
+    // parses e.g. "1s + 2pz", "0.6*1s - 0.8i*2pz" or "[2,1,1] + 1@90*[2,1,-1]"
+    // states are real orbital labels or [n,l,m] for complex harmonics,
+    // coefficients are real, imaginary (0.5i) or polar (magnitude@degrees) and default to 1
+    pub fn parse(text: &str, species: Species) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        for (sign, term) in split_terms(text)? {
+            let (coefficient, state) = match term.split_once('*') {
+                Some((c, s)) => (parse_coefficient(c)?, s),
+                None => (Complex64::new(1.0, 0.0), term.as_str()),
+            };
+            terms.push(Term {
+                coefficient: coefficient * sign,
+                orbital: parse_state(state)?.with_species(species),
+            });
+        }
+        Self::new(terms)
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    pub fn species(&self) -> Species {
+        self.terms[0].orbital.species
+    }
+
+    // energy of term i in hartree
+    pub fn energy(&self, i: usize) -> f64 {
+        let orbital = &self.terms[i].orbital;
+        orbital.species.energy(orbital.quantum_numbers.n())
+    }
+
+    // <H>, constant in time
+    pub fn mean_energy(&self) -> f64 {
+        (0..self.terms.len())
+            .map(|i| self.terms[i].coefficient.norm_sqr() * self.energy(i))
+            .sum()
+    }
+
+    // distinct beat periods 2 pi / |E_i - E_j| in atomic time units, longest first
+    pub fn beat_periods(&self) -> Vec<f64> {
+        let mut periods: Vec<f64> = Vec::new();
+        for i in 0..self.terms.len() {
+            for j in 0..i {
+                let gap = (self.energy(i) - self.energy(j)).abs();
+                if gap > 1e-12 {
+                    periods.push(2.0 * std::f64::consts::PI / gap);
+                }
+            }
+        }
+        periods.sort_by(|a, b| b.total_cmp(a));
+        periods.dedup_by(|a, b| (*a - *b).abs() <= 1e-9 * *b);
+        periods
+    }
+
+    // time-independent eigenfunctions psi_i at a cartesian point
+    pub fn amplitudes(&self, pos: &glm::DVec3) -> Vec<Complex64> {
+        let (r, theta, phi) = cartesian_to_spherical(pos);
+        self.terms
+            .iter()
+            .map(|t| t.orbital.psi(r, theta, phi))
+            .collect()
+    }
+
+    // time-dependent coefficients c_i exp(-i E_i t)
+    pub fn coefficients_at(&self, t: f64) -> Vec<Complex64> {
+        self.terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| term.coefficient * Complex64::from_polar(1.0, -self.energy(i) * t))
+            .collect()
+    }
+
+    // psi(t) from precomputed amplitudes
+    pub fn evolve(&self, amplitudes: &[Complex64], t: f64) -> Complex64 {
+        combine(&self.coefficients_at(t), amplitudes)
+    }
+
+    pub fn psi(&self, pos: &glm::DVec3, t: f64) -> Complex64 {
+        self.evolve(&self.amplitudes(pos), t)
+    }
+
+    pub fn density(&self, pos: &glm::DVec3, t: f64) -> f64 {
+        self.psi(pos, t).norm_sqr()
+    }
+
+    // sum_i |c_i|^2 |psi_i|^2, the proposal density of the samplers below
+    fn mixture(&self, amplitudes: &[Complex64]) -> f64 {
+        self.terms
+            .iter()
+            .zip(amplitudes)
+            .map(|(term, a)| term.coefficient.norm_sqr() * a.norm_sqr())
+            .sum()
+    }
+
+    // one draw from the mixture: pick a term by |c_i|^2, then sample its eigenstate
+    fn propose(&self, sampler: &mut Sampler) -> glm::DVec3 {
+        let mut u: f64 = sampler.rng().gen();
+        let mut index = self.terms.len() - 1;
+        for (i, term) in self.terms.iter().enumerate() {
+            u -= term.coefficient.norm_sqr();
+            if u < 0.0 {
+                index = i;
+                break;
+            }
+        }
+        let (r, theta, phi) = sampler.sample_spherical(&self.terms[index].orbital);
+        spherical_to_cartesian(r, theta, phi)
+    }
+
+    // exact samples of |psi(t)|^2 by rejection from the mixture
+    // by cauchy-schwarz |sum c_i psi_i|^2 <= K sum |c_i|^2 |psi_i|^2 for K terms, so a
+    // proposal is kept with probability |psi(t)|^2 / (K mixture), on average one in K
+    pub fn sample(&self, sampler: &mut Sampler, t: f64, num_particles: usize) -> Vec<Particle> {
+        let bound = self.terms.len() as f64;
+        let real = self.is_real_at(t);
+        let mut particles = Vec::with_capacity(num_particles);
+
+        while particles.len() < num_particles {
+            let position = self.propose(sampler);
+            let amplitudes = self.amplitudes(&position);
+            let psi = self.evolve(&amplitudes, t);
+            let u: f64 = sampler.rng().gen();
+
+            if u * bound * self.mixture(&amplitudes) < psi.norm_sqr() {
+                particles.push(self.particle(position, psi, real, sampler.color_mode));
+            }
+        }
+
+        particles
+    }
+
+    // psi(t) is real when every harmonic and every evolved coefficient is, e.g. at t = 0
+    // for real coefficients; the phases of terms with different n make it complex later
+    fn is_real_at(&self, t: f64) -> bool {
+        self.terms
+            .iter()
+            .zip(self.coefficients_at(t))
+            .all(|(term, c)| {
+                c.im == 0.0
+                    && (term.orbital.harmonic == Harmonic::Real
+                        || term.orbital.quantum_numbers.l() == 0)
+            })
+    }
+
+    // particle at a point where the state has amplitude psi, colored like the eigenstate clouds
+    fn particle(
+        &self,
+        position: glm::DVec3,
+        psi: Complex64,
+        real: bool,
+        color_mode: ColorMode,
+    ) -> Particle {
+        let density = psi.norm_sqr();
+        let intensity = physics::density_intensity(density * self.species().length_scale().powi(3));
+        let color = match color_mode {
+            ColorMode::Density => physics::heatmap_cool(intensity),
+            ColorMode::Phase if real => physics::sign_color(psi.arg().abs(), intensity),
+            ColorMode::Phase => physics::phase_wheel(psi.arg(), intensity),
+        };
+
+        Particle {
+            position,
+            color,
+            density,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format_terms(
+            self.terms
+                .iter()
+                .map(|t| (t.coefficient, state_name(&t.orbital))),
+        )
+    }
+}
+
+// fixed pool of mixture proposals that is re-weighted at each time instead of re-sampled
+// every proposal keeps its own uniform number, so the subset accepted at any t is an exact
+// sample of |psi(t)|^2, and particles persist from frame to frame instead of flickering
+// the eigenfunctions are evaluated once, after which each frame is a few complex products
+pub struct Ensemble {
+    state: Superposition,
+    positions: Vec<glm::DVec3>,
+    // psi_i at every proposal, terms.len() values per proposal
+    amplitudes: Vec<Complex64>,
+    // u K mixture, compared against |psi(t)|^2
+    bounds: Vec<f64>,
+}
+
+impl Ensemble {
+    pub fn new(state: &Superposition, sampler: &mut Sampler, num_proposals: usize) -> Self {
+        let bound = state.terms.len() as f64;
+        let mut ensemble = Ensemble {
+            state: state.clone(),
+            positions: Vec::with_capacity(num_proposals),
+            amplitudes: Vec::with_capacity(num_proposals * state.terms.len()),
+            bounds: Vec::with_capacity(num_proposals),
+        };
+
+        for _ in 0..num_proposals {
+            let position = state.propose(sampler);
+            let amplitudes = state.amplitudes(&position);
+            let u: f64 = sampler.rng().gen();
+
+            ensemble.bounds.push(u * bound * state.mixture(&amplitudes));
+            ensemble.positions.push(position);
+            ensemble.amplitudes.extend(amplitudes);
+        }
+
+        ensemble
+    }
+
+    pub fn state(&self) -> &Superposition {
+        &self.state
+    }
+
+    // the accepted particles at time t, about num_proposals / K of them
+    pub fn particles(&self, t: f64, color_mode: ColorMode) -> Vec<Particle> {
+        let k = self.state.terms.len();
+        let coefficients = self.state.coefficients_at(t);
+        let real = self.state.is_real_at(t);
+        self.positions
+            .iter()
+            .zip(self.amplitudes.chunks(k))
+            .zip(&self.bounds)
+            .filter_map(|((position, amplitudes), bound)| {
+                let psi = combine(&coefficients, amplitudes);
+                (*bound < psi.norm_sqr())
+                    .then(|| self.state.particle(*position, psi, real, color_mode))
+            })
+            .collect()
+    }
+}
+
+fn combine(coefficients: &[Complex64], amplitudes: &[Complex64]) -> Complex64 {
+    coefficients
+        .iter()
+        .zip(amplitudes)
+        .map(|(c, a)| c * a)
+        .sum()
+}
+
+// label for one eigenstate: chemist's label when there is one, [n,l,m] otherwise
+fn state_name(orbital: &Orbital) -> String {
+    let qn = orbital.quantum_numbers;
+    orbital
+        .label()
+        .unwrap_or_else(|| format!("[{},{},{}]", qn.n(), qn.l(), qn.m()))
+}
+
+pub(crate) fn format_coefficient(c: Complex64) -> String {
+    if c.im.abs() < 5e-4 {
+        format!("{:.3}", c.re)
+    } else if c.re.abs() < 5e-4 {
+        format!("{:.3}i", c.im)
+    } else {
+        format!("({:.3}{:+.3}i)", c.re, c.im)
+    }
+}
+
+// "0.600 1s - 0.800i 2pz": real and imaginary coefficients lend their sign to
+// the separator, general complex ones keep theirs inside the parentheses
+pub(crate) fn format_terms(terms: impl IntoIterator<Item = (Complex64, String)>) -> String {
+    let mut label = String::new();
+    for (i, (c, state)) in terms.into_iter().enumerate() {
+        let negative = if c.im.abs() < 5e-4 {
+            c.re < 0.0
+        } else {
+            c.re.abs() < 5e-4 && c.im < 0.0
+        };
+        let sign = match (i, negative) {
+            (0, false) => "",
+            (0, true) => "-",
+            (_, false) => " + ",
+            (_, true) => " - ",
+        };
+        let c = if negative { -c } else { c };
+        label.push_str(&format!("{}{} {}", sign, format_coefficient(c), state));
+    }
+    label
+}
+
+// splits at top-level '+' and '-', keeping the sign of each term
+// signs inside [n,l,m], (x,y,z), in exponents such as 1e-3 and of the phase in
+// magnitude@degrees such as 1@-90 are not separators
+pub(crate) fn split_terms(text: &str) -> Result<Vec<(f64, String)>, String> {
+    let mut terms = Vec::new();
+    let mut sign = 1.0;
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            _ => {}
+        }
+        let exponent = current.ends_with(['e', 'E'])
+            && current[..current.len() - 1].ends_with(|d: char| d.is_ascii_digit() || d == '.');
+        let phase = current.ends_with('@');
+        if (c == '+' || c == '-') && depth == 0 && !exponent && !phase {
+            if !current.trim().is_empty() {
+                terms.push((sign, current.trim().to_string()));
+            } else if !terms.is_empty() || sign < 0.0 {
+                return Err(format!("missing term before '{}'", c));
+            }
+            sign = if c == '-' { -1.0 } else { 1.0 };
+            current.clear();
+        } else if !c.is_whitespace() {
+            current.push(c);
+        }
+    }
+
+    if current.is_empty() {
+        return Err("missing term at the end".to_string());
+    }
+    terms.push((sign, current));
+    Ok(terms)
+}
+
+// "0.6", "0.8i", "-i" or "1@90" (magnitude@degrees)
+pub(crate) fn parse_coefficient(text: &str) -> Result<Complex64, String> {
+    let invalid = || format!("invalid coefficient '{}'", text);
+
+    if let Some((magnitude, degrees)) = text.split_once('@') {
+        let magnitude: f64 = magnitude.parse().map_err(|_| invalid())?;
+        let degrees: f64 = degrees.parse().map_err(|_| invalid())?;
+        return Ok(Complex64::from_polar(magnitude, degrees.to_radians()));
+    }
+
+    if let Some(imaginary) = text.strip_suffix('i') {
+        let value = match imaginary {
+            "" | "+" => 1.0,
+            "-" => -1.0,
+            _ => imaginary.parse().map_err(|_| invalid())?,
+        };
+        return Ok(Complex64::new(0.0, value));
+    }
+
+    Ok(Complex64::new(text.parse().map_err(|_| invalid())?, 0.0))
+}
+
+// "2pz" (real harmonic) or "[2,1,-1]" (complex harmonic)
+pub(crate) fn parse_state(text: &str) -> Result<Orbital, String> {
+    match text.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(inner) => {
+            let numbers = inner
+                .split(',')
+                .map(|s| s.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("invalid state '{}'", text))?;
+            match numbers.as_slice() {
+                [n, l, m] => {
+                    let qn = QuantumNumbers::new(*n, *l, *m).map_err(|e| e.to_string())?;
+                    Ok(Orbital::new(qn).with_harmonic(Harmonic::Complex))
+                }
+                _ => Err(format!("expected [n,l,m], got '{}'", text)),
+            }
+        }
+        None => Orbital::from_label(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::HYDROGEN;
+
+    fn dipole_state() -> Superposition {
+        Superposition::parse("1s + 2pz", HYDROGEN).unwrap()
+    }
+
+    // mean position along the orbital z axis, which is +y in the sampler frame
+    fn mean_z(particles: &[Particle]) -> f64 {
+        particles.iter().map(|p| p.position.y).sum::<f64>() / particles.len() as f64
+    }
+
+    #[test]
+    fn test_parse() {
+        let state = Superposition::parse("0.6*1s - 0.8i*2pz", HYDROGEN).unwrap();
+        let terms = state.terms();
+        assert_eq!(terms.len(), 2);
+        assert!((terms[0].coefficient - Complex64::new(0.6, 0.0)).norm() < 1e-12);
+        assert!((terms[1].coefficient - Complex64::new(0.0, -0.8)).norm() < 1e-12);
+        assert_eq!(terms[1].orbital.harmonic, Harmonic::Real);
+
+        let state = Superposition::parse("[2,1,1] + 1@90*[2,1,-1]", HYDROGEN).unwrap();
+        let c = state.terms()[1].coefficient * 2f64.sqrt();
+        assert!((c - Complex64::new(0.0, 1.0)).norm() < 1e-12);
+        assert_eq!(state.terms()[1].orbital.quantum_numbers.m(), -1);
+
+        let state = Superposition::parse("1@-90*2pz + 1s", HYDROGEN).unwrap();
+        let c = state.terms()[0].coefficient * 2f64.sqrt();
+        assert!((c - Complex64::new(0.0, -1.0)).norm() < 1e-12);
+        assert_eq!(state.terms()[1].orbital.quantum_numbers.n(), 1);
+        let state = Superposition::parse("1s - 1@+45*2pz", HYDROGEN).unwrap();
+        assert_eq!(state.terms().len(), 2);
+    }
+
+    #[test]
+    fn test_label() {
+        let state = Superposition::parse("0.6*1s - 0.8i*[2,1,1]", HYDROGEN).unwrap();
+        assert_eq!(state.label(), "0.600 1s - 0.800i [2,1,1]");
+        let state = Superposition::parse("-0.6*1s + 0.8*2pz", HYDROGEN).unwrap();
+        assert_eq!(state.label(), "-0.600 1s + 0.800 2pz");
+        let state = Superposition::parse("1s - 1@45*2s", HYDROGEN).unwrap();
+        assert_eq!(state.label(), "0.707 1s + (-0.500-0.500i) 2s");
+    }
+
+    #[test]
+    fn test_is_real_at() {
+        assert!(dipole_state().is_real_at(0.0));
+        assert!(!dipole_state().is_real_at(1.0));
+        let state = Superposition::parse("0.6*1s - 0.8i*2pz", HYDROGEN).unwrap();
+        assert!(!state.is_real_at(0.0));
+        let state = Superposition::parse("1s + [2,1,1]", HYDROGEN).unwrap();
+        assert!(!state.is_real_at(0.0));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Superposition::parse("1s + 1s", HYDROGEN).is_err());
+        assert!(Superposition::parse("2pz + [2,1,1]", HYDROGEN).is_err());
+        assert!(Superposition::parse("1s +", HYDROGEN).is_err());
+        assert!(Superposition::parse("0*1s", HYDROGEN).is_err());
+        assert!(Superposition::parse("1x*1s", HYDROGEN).is_err());
+    }
+
+    #[test]
+    fn test_density_is_normalized() {
+        let state = Superposition::parse("1s + 0.5i*2pz + 3dxy", HYDROGEN).unwrap();
+        let (points, half_width) = (80, 30.0);
+        let h = 2.0 * half_width / points as f64;
+        let mut total = 0.0;
+        for i in 0..points {
+            for j in 0..points {
+                for k in 0..points {
+                    let pos = glm::vec3(i as f64 + 0.5, j as f64 + 0.5, k as f64 + 0.5) * h
+                        - glm::vec3(half_width, half_width, half_width);
+                    total += state.density(&pos, 3.7) * h * h * h;
+                }
+            }
+        }
+        assert!((total - 1.0).abs() < 0.02, "integral {}", total);
+    }
+
+    #[test]
+    fn test_quantum_beat_period() {
+        let state = dipole_state();
+        let period = state.beat_periods()[0];
+        // E_2 - E_1 = 3/8 hartree
+        assert!((period - 2.0 * std::f64::consts::PI / 0.375).abs() < 1e-9);
+
+        let pos = glm::vec3(0.3, 1.1, -0.4);
+        assert!((state.density(&pos, 1.0) - state.density(&pos, 1.0 + period)).abs() < 1e-12);
+        assert!((state.density(&pos, 1.0) - state.density(&pos, 1.0 + period / 2.0)).abs() > 1e-3);
+    }
+
+    #[test]
+    fn test_dipole_oscillates() {
+        // <z>(t) = <1s|z|2pz> cos(dE t) with <1s|z|2pz> = 128 sqrt(2) / 243 = 0.745
+        let state = dipole_state();
+        let half_period = state.beat_periods()[0] / 2.0;
+        let mut sampler = Sampler::with_seed(9);
+
+        let start = mean_z(&state.sample(&mut sampler, 0.0, 20_000));
+        let half = mean_z(&state.sample(&mut sampler, half_period, 20_000));
+        assert!((start - 0.745).abs() < 0.08, "{}", start);
+        assert!((half + 0.745).abs() < 0.08, "{}", half);
+
+        // the re-weighted ensemble shows the same oscillation
+        let ensemble = Ensemble::new(&state, &mut sampler, 40_000);
+        let start = mean_z(&ensemble.particles(0.0, ColorMode::Density));
+        let half = mean_z(&ensemble.particles(half_period, ColorMode::Density));
+        assert!((start - 0.745).abs() < 0.08, "{}", start);
+        assert!((half + 0.745).abs() < 0.08, "{}", half);
+    }
+}
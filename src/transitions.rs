@@ -0,0 +1,544 @@
+use crate::physics::{
+    spherical_harmonic, Harmonic, Orbital, QuantumNumbers, Species, BOHR_ANGSTROM, HARTREE_EV,
+};
+use crate::spin::ALPHA;
+use crate::superposition::parse_state;
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+// electric dipole transitions between hydrogen-like states
+// the matrix element factors into a radial and an angular integral,
+//   <n'l'm'|r|nlm> = integral R_n'l' R_nl r^3 dr * integral Y*_l'm' r_hat Y_lm dOmega,
+// the first by simpson's rule over the radial functions and the second on a theta-phi grid of
+// the harmonics, so real and complex states are handled alike
+// in atomic units, with mu the reduced mass, dE the photon energy and c = 1 / alpha,
+//   f = 2/3 mu dE |d|^2 (absorption from the lower state), A = 4/3 dE^3 |d|^2 / c^3 (emission)
+
+// atomic unit of time in seconds
+const AU_TIME: f64 = 2.418_884_326_585_7e-17;
+
+// angular grid: the midpoint rule in theta, and in phi, where it is exact for the low-order
+// trigonometric polynomials that products of harmonics make
+const THETA_STEPS: usize = 400;
+const PHI_STEPS: usize = 64;
+
+// radial step in units of the species length scale a
+const RADIAL_STEP: f64 = 0.01;
+
+// below this fraction of |radial integral|^2 an angular integral counts as zero
+const ZERO_TOLERANCE: f64 = 1e-10;
+
+const SERIES_NAMES: [&str; 6] = [
+    "Lyman",
+    "Balmer",
+    "Paschen",
+    "Brackett",
+    "Pfund",
+    "Humphreys",
+];
+
+const LINE_NAMES: [&str; 8] = [
+    "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    upper: Orbital,
+    lower: Orbital,
+    radial: f64,
+    // <lower| r_hat |upper> in orbital axes (x, y, z)
+    angular: [Complex64; 3],
+}
+
+impl Transition {
+    // the states may come in either order; the one with larger n is the upper level
+    pub fn new(first: Orbital, second: Orbital) -> Result<Self, String> {
+        if first.species != second.species {
+            return Err("both states must belong to the same species".to_string());
+        }
+        let (n1, n2) = (first.quantum_numbers.n(), second.quantum_numbers.n());
+        if n1 == n2 {
+            return Err(format!(
+                "{} and {} share n = {} and are degenerate without fine structure, so there is no line",
+                state_name(&first),
+                state_name(&second),
+                n1
+            ));
+        }
+        let (upper, lower) = if n1 > n2 {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        Ok(Self {
+            upper,
+            lower,
+            radial: radial_integral(&upper, &lower),
+            angular: angular_integral(&upper, &lower),
+        })
+    }
+
+    // "3dz2 -> 2pz" or "[2,1,1] -> 1s", labels being real states and brackets complex ones
+    pub fn parse(text: &str, species: Species) -> Result<Self, String> {
+        let (first, second) = text
+            .split_once("->")
+            .ok_or_else(|| format!("expected '<state> -> <state>', got '{}'", text))?;
+        let first = parse_state(first.trim())?.with_species(species);
+        let second = parse_state(second.trim())?.with_species(species);
+        Self::new(first, second)
+    }
+
+    pub fn upper(&self) -> &Orbital {
+        &self.upper
+    }
+
+    pub fn lower(&self) -> &Orbital {
+        &self.lower
+    }
+
+    // integral R_upper R_lower r^3 dr in bohr
+    pub fn radial_integral(&self) -> f64 {
+        self.radial
+    }
+
+    // <lower|r|upper> in bohr, in orbital axes (x, y, z)
+    pub fn dipole(&self) -> [Complex64; 3] {
+        self.angular.map(|c| c * self.radial)
+    }
+
+    pub fn dipole_squared(&self) -> f64 {
+        self.dipole().iter().map(|c| c.norm_sqr()).sum()
+    }
+
+    // photon energy in hartree
+    pub fn energy(&self) -> f64 {
+        let species = self.upper.species;
+        species.energy(self.upper.quantum_numbers.n())
+            - species.energy(self.lower.quantum_numbers.n())
+    }
+
+    // vacuum wavelength in nm
+    pub fn wavelength(&self) -> f64 {
+        wavelength(self.energy())
+    }
+
+    // None for an allowed transition, else which selection rule it breaks
+    pub fn forbidden_reason(&self) -> Option<String> {
+        let (upper, lower) = (self.upper.quantum_numbers, self.lower.quantum_numbers);
+        let delta_l = upper.l() - lower.l();
+        if delta_l.abs() != 1 {
+            return Some(format!(
+                "delta l = {}, while the dipole operator needs delta l = +-1",
+                delta_l
+            ));
+        }
+        let angular: f64 = self.angular.iter().map(|c| c.norm_sqr()).sum();
+        if angular > ZERO_TOLERANCE {
+            return None;
+        }
+        Some(match (self.upper.harmonic, self.lower.harmonic) {
+            (Harmonic::Complex, Harmonic::Complex) => format!(
+                "delta m = {}, while the dipole operator needs |delta m| <= 1",
+                upper.m() - lower.m()
+            ),
+            _ => "no component of r connects these angular shapes".to_string(),
+        })
+    }
+
+    pub fn allowed(&self) -> bool {
+        self.forbidden_reason().is_none()
+    }
+
+    // absorption oscillator strength from the lower to the upper sublevel
+    pub fn oscillator_strength(&self) -> f64 {
+        if !self.allowed() {
+            return 0.0;
+        }
+        2.0 / 3.0 * self.upper.species.reduced_mass() * self.energy() * self.dipole_squared()
+    }
+
+    // spontaneous emission rate from the upper to the lower sublevel, in 1/s
+    pub fn einstein_a(&self) -> f64 {
+        if !self.allowed() {
+            return 0.0;
+        }
+        einstein_a(self.energy(), self.dipole_squared())
+    }
+
+    // orbital axes along which the dipole points, "x, y" for circular polarization
+    pub fn polarization(&self) -> String {
+        let total = self.dipole_squared();
+        let axes: Vec<&str> = ["x", "y", "z"]
+            .iter()
+            .zip(self.dipole())
+            .filter(|(_, c)| c.norm_sqr() > ZERO_TOLERANCE * total)
+            .map(|(axis, _)| *axis)
+            .collect();
+        axes.join(", ")
+    }
+
+    pub fn report(&self) -> Vec<String> {
+        let energy = self.energy();
+        let mut lines = vec![
+            format!(
+                "Electric dipole transition {} -> {} in {}",
+                state_name(&self.upper),
+                state_name(&self.lower),
+                self.upper.species.name
+            ),
+            format!(
+                "  photon energy {:.6} hartree = {:.4} eV, vacuum wavelength {:.4} nm",
+                energy,
+                energy * HARTREE_EV,
+                self.wavelength()
+            ),
+            format!(
+                "  radial integral {:.6} bohr, |<f|r|i>|^2 = {:.6} bohr^2",
+                self.radial,
+                self.dipole_squared()
+            ),
+        ];
+        match self.forbidden_reason() {
+            Some(reason) => lines.push(format!("  forbidden: {}", reason)),
+            None => {
+                let d = self.dipole();
+                lines.push(format!(
+                    "  allowed, polarized along {}: <f|r|i> = ({}, {}, {}) bohr",
+                    self.polarization(),
+                    format_complex(d[0]),
+                    format_complex(d[1]),
+                    format_complex(d[2])
+                ));
+                lines.push(format!(
+                    "  oscillator strength f = {:.6}, Einstein A = {:.4e} 1/s",
+                    self.oscillator_strength(),
+                    self.einstein_a()
+                ));
+            }
+        }
+        lines
+    }
+}
+
+// every allowed one-photon decay of a state to the sublevels of lower shells, in the state's
+// own harmonics
+pub fn decays(orbital: &Orbital) -> Vec<Transition> {
+    let qn = orbital.quantum_numbers;
+    let mut channels = Vec::new();
+    for n in 1..qn.n() {
+        for l in [qn.l() - 1, qn.l() + 1] {
+            if l < 0 || l >= n {
+                continue;
+            }
+            for m in -l..=l {
+                let lower = Orbital::new(QuantumNumbers::new(n, l, m).expect("valid sublevel"))
+                    .with_harmonic(orbital.harmonic)
+                    .with_species(orbital.species);
+                if let Ok(transition) = Transition::new(*orbital, lower) {
+                    if transition.allowed() {
+                        channels.push(transition);
+                    }
+                }
+            }
+        }
+    }
+    channels
+}
+
+// decay channels with their wavelengths, rates and branching ratios, and the radiative lifetime
+pub fn decay_report(orbital: &Orbital) -> Vec<String> {
+    let name = state_name(orbital);
+    let channels = decays(orbital);
+    if orbital.quantum_numbers.n() == 1 {
+        return vec![format!("{} is the ground state and does not decay", name)];
+    }
+    if channels.is_empty() {
+        return vec![format!(
+            "{} has no electric dipole decay; it is metastable and decays by two photons",
+            name
+        )];
+    }
+
+    let total: f64 = channels.iter().map(|t| t.einstein_a()).sum();
+    let mut lines = vec![format!(
+        "Radiative decays of {} in {}: lifetime {:.4} ns",
+        name,
+        orbital.species.name,
+        1e9 / total
+    )];
+    lines.push(format!(
+        "  {:<10} {:>16} {:>14} {:>10}  {}",
+        "to", "wavelength (nm)", "A (1/s)", "branching", "polarization"
+    ));
+    for transition in &channels {
+        lines.push(format!(
+            "  {:<10} {:>16.4} {:>14.4e} {:>10.4}  {}",
+            state_name(transition.lower()),
+            transition.wavelength(),
+            transition.einstein_a(),
+            transition.einstein_a() / total,
+            transition.polarization()
+        ));
+    }
+    lines
+}
+
+// a line n_upper -> n_lower, with f and A summed over the sublevels of the final shell and
+// averaged over those of the initial one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line {
+    pub upper: i32,
+    pub lower: i32,
+    // photon energy in hartree and vacuum wavelength in nm
+    pub energy: f64,
+    pub wavelength: f64,
+    // absorption oscillator strength from the lower shell
+    pub oscillator_strength: f64,
+    // emission rate from the upper shell in 1/s
+    pub einstein_a: f64,
+}
+
+impl Line {
+    // each pair l' = l +- 1 contributes max(l, l') R^2, the m-sum of the angular integrals, so
+    //   f = 2/3 mu dE sum / n_lower^2, A = 4/3 dE^3 / c^3 sum / n_upper^2
+    pub fn new(species: Species, upper: i32, lower: i32) -> Self {
+        assert!(
+            upper > lower && lower >= 1,
+            "a line needs n_upper > n_lower >= 1"
+        );
+        let shell = |n: i32, l: i32| {
+            Orbital::new(QuantumNumbers::new(n, l, 0).expect("valid shell")).with_species(species)
+        };
+        let mut sum = 0.0;
+        for l_upper in 0..upper {
+            for l_lower in [l_upper - 1, l_upper + 1] {
+                if l_lower < 0 || l_lower >= lower {
+                    continue;
+                }
+                let radial = radial_integral(&shell(upper, l_upper), &shell(lower, l_lower));
+                sum += l_upper.max(l_lower) as f64 * radial * radial;
+            }
+        }
+        let energy = species.energy(upper) - species.energy(lower);
+        Self {
+            upper,
+            lower,
+            energy,
+            wavelength: wavelength(energy),
+            oscillator_strength: 2.0 / 3.0 * species.reduced_mass() * energy * sum
+                / (lower * lower) as f64,
+            einstein_a: einstein_a(energy, sum / (upper * upper) as f64),
+        }
+    }
+
+    // "Balmer alpha", or "Balmer 12-2" past the greek letters
+    pub fn name(&self) -> String {
+        let series = series_name(self.lower);
+        match LINE_NAMES.get((self.upper - self.lower - 1) as usize) {
+            Some(letter) => format!("{} {}", series, letter),
+            None => format!("{} {}-{}", series, self.upper, self.lower),
+        }
+    }
+}
+
+// the first `count` lines of the series ending on n = lower
+pub fn series(species: Species, lower: i32, count: usize) -> Vec<Line> {
+    (1..=count as i32)
+        .map(|k| Line::new(species, lower + k, lower))
+        .collect()
+}
+
+// lyman, balmer and paschen lines of a species, each series closed by its limit
+pub fn line_table(species: Species, count: usize) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "Spectral lines of {}, Z = {}: vacuum wavelengths, f and A averaged over sublevels",
+            species.name, species.z
+        ),
+        format!(
+            "  {:<18} {:>8} {:>16} {:>12} {:>10} {:>12}",
+            "line", "n -> n'", "wavelength (nm)", "energy (eV)", "f", "A (1/s)"
+        ),
+    ];
+    for lower in 1..=3 {
+        for line in series(species, lower, count) {
+            lines.push(format!(
+                "  {:<18} {:>8} {:>16.4} {:>12.5} {:>10.6} {:>12.4e}",
+                line.name(),
+                format!("{} -> {}", line.upper, line.lower),
+                line.wavelength,
+                line.energy * HARTREE_EV,
+                line.oscillator_strength,
+                line.einstein_a
+            ));
+        }
+        let limit = -species.energy(lower);
+        lines.push(format!(
+            "  {:<18} {:>8} {:>16.4} {:>12.5}",
+            format!("{} limit", series_name(lower)),
+            format!("inf -> {}", lower),
+            wavelength(limit),
+            limit * HARTREE_EV
+        ));
+    }
+    lines
+}
+
+pub fn series_name(lower: i32) -> String {
+    SERIES_NAMES
+        .get((lower - 1) as usize)
+        .map_or_else(|| format!("n = {}", lower), |name| name.to_string())
+}
+
+// integral R_upper R_lower r^3 dr by simpson's rule
+// the lower state's exponential confines the integrand to about 10 n^2 + 30 n lengths a
+fn radial_integral(upper: &Orbital, lower: &Orbital) -> f64 {
+    let a = lower.species.length_scale();
+    let n = lower.quantum_numbers.n() as f64;
+    let r_max = ((10.0 * n * n + 30.0 * n) * a).min(upper.r_max().max(lower.r_max()));
+    let steps = 2 * ((r_max / (RADIAL_STEP * a)) as usize / 2).max(1000);
+    let h = r_max / steps as f64;
+    let f = |r: f64| upper.radial(r) * lower.radial(r) * r * r * r;
+    let inner: f64 = (1..steps)
+        .map(|i| {
+            let coefficient = if i % 2 == 1 { 4.0 } else { 2.0 };
+            coefficient * f(i as f64 * h)
+        })
+        .sum();
+    (f(0.0) + inner + f(r_max)) * h / 3.0
+}
+
+// integral Y*_lower r_hat Y_upper dOmega on the theta-phi grid
+fn angular_integral(upper: &Orbital, lower: &Orbital) -> [Complex64; 3] {
+    let (qu, ql) = (upper.quantum_numbers, lower.quantum_numbers);
+    let d_theta = PI / THETA_STEPS as f64;
+    let d_phi = 2.0 * PI / PHI_STEPS as f64;
+    let mut sum = [Complex64::new(0.0, 0.0); 3];
+    for i in 0..THETA_STEPS {
+        let theta = (i as f64 + 0.5) * d_theta;
+        let (sin, cos) = theta.sin_cos();
+        for j in 0..PHI_STEPS {
+            let phi = j as f64 * d_phi;
+            let product = spherical_harmonic(ql.l(), ql.m(), theta, phi, lower.harmonic).conj()
+                * spherical_harmonic(qu.l(), qu.m(), theta, phi, upper.harmonic)
+                * sin
+                * d_theta
+                * d_phi;
+            sum[0] += product * sin * phi.cos();
+            sum[1] += product * sin * phi.sin();
+            sum[2] += product * cos;
+        }
+    }
+    sum
+}
+
+// vacuum wavelength 2 pi c / dE of a photon of energy dE hartree, in nm
+fn wavelength(energy: f64) -> f64 {
+    2.0 * PI / (ALPHA * energy) * BOHR_ANGSTROM / 10.0
+}
+
+// 4/3 dE^3 |d|^2 / c^3 in 1/s
+fn einstein_a(energy: f64, dipole_squared: f64) -> f64 {
+    4.0 / 3.0 * energy.powi(3) * dipole_squared * ALPHA.powi(3) / AU_TIME
+}
+
+// the label of a real state, else the [n,l,m] that parse_state reads back
+fn state_name(orbital: &Orbital) -> String {
+    let qn = orbital.quantum_numbers;
+    orbital
+        .label()
+        .unwrap_or_else(|| format!("[{},{},{}]", qn.n(), qn.l(), qn.m()))
+}
+
+fn format_complex(c: Complex64) -> String {
+    match (c.re.abs() < 1e-9, c.im.abs() < 1e-9) {
+        (true, true) => "0".to_string(),
+        (false, true) => format!("{:.4}", c.re),
+        (true, false) => format!("{:.4}i", c.im),
+        (false, false) => format!("{:.4}{:+.4}i", c.re, c.im),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{HYDROGEN, SPECIES_PRESETS};
+
+    fn transition(text: &str) -> Transition {
+        Transition::parse(text, HYDROGEN).unwrap()
+    }
+
+    #[test]
+    fn test_lyman_alpha() {
+        // R(2p, 1s) = 2^7 sqrt(6) / 3^5 bohr, A = 6.2649e8 1/s from every 2p sublevel
+        let expected = 128.0 * 6f64.sqrt() / 243.0;
+        for text in ["2pz -> 1s", "[2,1,1] -> [1,0,0]", "1s -> 2px"] {
+            let t = transition(text);
+            assert!((t.radial_integral() - expected).abs() < 1e-6, "{}", text);
+            assert!(t.allowed());
+            assert!(
+                (t.einstein_a() / 6.2649e8 - 1.0).abs() < 1e-3,
+                "{}",
+                t.einstein_a()
+            );
+            assert!(
+                (t.wavelength() - 121.5023).abs() < 1e-3,
+                "{}",
+                t.wavelength()
+            );
+        }
+        assert_eq!(transition("2pz -> 1s").polarization(), "z");
+        assert_eq!(transition("[2,1,-1] -> 1s").polarization(), "x, y");
+
+        // f from 1s sums over the three 2p sublevels to the l-averaged 0.4162
+        let f: f64 = ["2px", "2py", "2pz"]
+            .iter()
+            .map(|p| transition(&format!("1s -> {}", p)).oscillator_strength())
+            .sum();
+        assert!((f - 0.4162).abs() < 1e-4, "{}", f);
+    }
+
+    #[test]
+    fn test_selection_rules() {
+        for text in [
+            "3dz2 -> 1s",
+            "2s -> 1s",
+            "[3,2,2] -> [2,1,0]",
+            "3dxy -> 2pz",
+        ] {
+            let t = transition(text);
+            assert!(!t.allowed(), "{}", text);
+            assert_eq!(t.einstein_a(), 0.0);
+        }
+        assert!(transition("3dz2 -> 2pz").allowed());
+        assert!(Transition::parse("2s -> 2pz", HYDROGEN).is_err());
+
+        // 2p decays only to 1s, and every sublevel lives 1.596 ns
+        let channels = decays(&Orbital::new(QuantumNumbers::new(2, 1, 1).unwrap()));
+        assert_eq!(channels.len(), 1);
+        let lifetime = 1e9 / channels[0].einstein_a();
+        assert!((lifetime - 1.596).abs() < 2e-3, "{}", lifetime);
+        assert!(decays(&Orbital::new(QuantumNumbers::new(2, 0, 0).unwrap())).is_empty());
+    }
+
+    #[test]
+    fn test_line_table() {
+        // balmer alpha of hydrogen: f = 0.6407, A = 4.4101e7 1/s, and with the proton's
+        // reduced mass lyman alpha sits at the measured 121.567 nm
+        let proton = SPECIES_PRESETS[1];
+        let alpha = Line::new(proton, 3, 2);
+        assert_eq!(alpha.name(), "Balmer alpha");
+        assert!((alpha.oscillator_strength - 0.6407).abs() < 1e-4);
+        assert!((alpha.einstein_a / 4.4101e7 - 1.0).abs() < 1e-3);
+        assert!((Line::new(proton, 2, 1).wavelength - 121.567).abs() < 2e-3);
+
+        // f is independent of Z, A grows as Z^4
+        let helium = Line::new(SPECIES_PRESETS[2], 3, 2);
+        assert!((helium.oscillator_strength - 0.6407).abs() < 1e-4);
+        assert!((helium.einstein_a / alpha.einstein_a / 16.0 - 1.0).abs() < 1e-3);
+
+        let table = line_table(proton, 5);
+        assert_eq!(table.len(), 2 + 3 * 6);
+        assert!(table.iter().any(|l| l.contains("Paschen epsilon")));
+    }
+}